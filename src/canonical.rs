@@ -0,0 +1,272 @@
+//! Helpers for types whose encoding must be canonical, i.e. two equal values always encode to
+//! the same bytes, e.g. because the encoded messages are signed or hashed.
+//!
+//! All the types provided by this crate encode canonically: integers have a fixed width,
+//! [`Varint`](crate::Varint)s and varint length prefixes are minimal. Types without a natural
+//! order on the wire, e.g. maps and sets, should write their entries sorted with
+//! [`sorted_by_encoded_key`], and floats should be written with [`f32_to_bits`] and
+//! [`f64_to_bits`], so every NaN is encoded the same way.
+//!
+//! [`Transformable::decode_canonical`] rejects input which is not the canonical encoding of the
+//! value it decodes to.
+
+#[cfg(feature = "alloc")]
+use ::alloc::vec::Vec;
+
+#[cfg(feature = "alloc")]
+use crate::Transformable;
+use crate::{ErrorKind, TransformError};
+
+/// The bits of the NaN every `f32` NaN is encoded as.
+pub const CANONICAL_NAN_F32: u32 = 0x7fc0_0000;
+
+/// The bits of the NaN every `f64` NaN is encoded as.
+pub const CANONICAL_NAN_F64: u64 = 0x7ff8_0000_0000_0000;
+
+/// Returns the bits of the float, with every NaN mapped to [`CANONICAL_NAN_F32`].
+///
+/// `0.0` and `-0.0` are kept apart, as they are different values.
+#[inline]
+pub fn f32_to_bits(val: f32) -> u32 {
+  if val.is_nan() {
+    CANONICAL_NAN_F32
+  } else {
+    val.to_bits()
+  }
+}
+
+/// Returns the bits of the float, with every NaN mapped to [`CANONICAL_NAN_F64`].
+///
+/// `0.0` and `-0.0` are kept apart, as they are different values.
+#[inline]
+pub fn f64_to_bits(val: f64) -> u64 {
+  if val.is_nan() {
+    CANONICAL_NAN_F64
+  } else {
+    val.to_bits()
+  }
+}
+
+/// Encodes the keys of the entries and returns them sorted by their encoded bytes, together with
+/// the values, so maps and sets are encoded the same way whatever their iteration order is.
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+pub fn sorted_by_encoded_key<'a, K, V, I>(entries: I) -> Result<Vec<(Vec<u8>, &'a V)>, K::Error>
+where
+  K: Transformable + 'a,
+  V: ?Sized + 'a,
+  I: IntoIterator<Item = (&'a K, &'a V)>,
+{
+  let mut sorted = entries
+    .into_iter()
+    .map(|(key, val)| Transformable::encode_to_vec(key).map(|key| (key, val)))
+    .collect::<Result<Vec<_>, _>>()?;
+  sorted.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+  Ok(sorted)
+}
+
+/// The error for input which is not the canonical encoding of the value it decodes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NonCanonical {
+  /// The offset of the first byte which differs from the canonical encoding.
+  pub offset: usize,
+}
+
+impl NonCanonical {
+  /// Returns an error if `src` is not the same as the canonical encoding `encoded`.
+  #[cfg(feature = "alloc")]
+  #[inline]
+  pub(crate) fn check(encoded: &[u8], src: &[u8]) -> Result<(), Self> {
+    if encoded == src {
+      return Ok(());
+    }
+
+    let offset = encoded
+      .iter()
+      .zip(src)
+      .position(|(a, b)| a != b)
+      .unwrap_or_else(|| encoded.len().min(src.len()));
+    Err(Self { offset })
+  }
+}
+
+impl core::fmt::Display for NonCanonical {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(
+      f,
+      "non-canonical encoding, which differs from the canonical one at byte {}",
+      self.offset
+    )
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NonCanonical {}
+
+/// The error type for errors that get returned by
+/// [`decode_canonical`](crate::Transformable::decode_canonical).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeCanonicalError<E> {
+  /// Returned when decoding or re-encoding the value fails.
+  Decode(E),
+  /// Returned when the input is not the canonical encoding of the value.
+  NonCanonical(NonCanonical),
+}
+
+impl<E: Into<TransformError>> From<DecodeCanonicalError<E>> for TransformError {
+  fn from(err: DecodeCanonicalError<E>) -> Self {
+    match err {
+      DecodeCanonicalError::Decode(e) => e.into(),
+      DecodeCanonicalError::NonCanonical(e) => Self::wrap(ErrorKind::InvalidValue, e),
+    }
+  }
+}
+
+impl<E> From<NonCanonical> for DecodeCanonicalError<E> {
+  #[inline]
+  fn from(err: NonCanonical) -> Self {
+    Self::NonCanonical(err)
+  }
+}
+
+impl<E: core::fmt::Display> core::fmt::Display for DecodeCanonicalError<E> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::Decode(err) => write!(f, "{err}"),
+      Self::NonCanonical(err) => write!(f, "{err}"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for DecodeCanonicalError<E> {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Decode(err) => Some(err),
+      Self::NonCanonical(err) => Some(err),
+    }
+  }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+  use super::*;
+  use crate::{BytesTransformError, Varint};
+  use std::collections::HashMap;
+
+  /// A map of ids to scores, whose iteration order is random.
+  #[derive(Debug, PartialEq)]
+  struct Scores(HashMap<u16, f64>);
+
+  impl Transformable for Scores {
+    type Error = BytesTransformError;
+
+    fn encode(&self, dst: &mut [u8]) -> Result<usize, Self::Error> {
+      if dst.len() < self.encoded_len() {
+        return Err(Self::Error::EncodeBufferTooSmall);
+      }
+
+      let mut offset = 0;
+      for (key, val) in
+        sorted_by_encoded_key(&self.0).map_err(|_| Self::Error::EncodeBufferTooSmall)?
+      {
+        dst[offset..offset + 2].copy_from_slice(&key);
+        dst[offset + 2..offset + 10].copy_from_slice(&f64_to_bits(*val).to_be_bytes());
+        offset += 10;
+      }
+      Ok(offset)
+    }
+
+    fn encoded_len(&self) -> usize {
+      self.0.len() * 10
+    }
+
+    fn decode(src: &[u8]) -> Result<(usize, Self), Self::Error> {
+      if src.len() % 10 != 0 {
+        return Err(Self::Error::NotEnoughBytes);
+      }
+
+      let map = src
+        .chunks_exact(10)
+        .map(|entry| {
+          let key = u16::from_be_bytes([entry[0], entry[1]]);
+          let val = f64::from_bits(u64::from_be_bytes(entry[2..].try_into().unwrap()));
+          (key, val)
+        })
+        .collect();
+      Ok((src.len(), Self(map)))
+    }
+  }
+
+  #[test]
+  fn test_sorted_by_encoded_key() {
+    let map = (0..100u16)
+      .map(|k| (k.wrapping_mul(7919), k))
+      .collect::<HashMap<_, _>>();
+    let sorted = sorted_by_encoded_key(&map).unwrap();
+    assert_eq!(sorted.len(), map.len());
+    assert!(sorted.windows(2).all(|w| w[0].0 < w[1].0));
+    for (key, val) in sorted {
+      let (_, key) = <u16 as Transformable>::decode(&key).unwrap();
+      assert_eq!(map[&key], *val);
+    }
+  }
+
+  #[test]
+  fn test_floats() {
+    assert_eq!(f32_to_bits(f32::NAN), f32_to_bits(-f32::NAN));
+    assert_eq!(f32_to_bits(f32::from_bits(0x7f80_0001)), CANONICAL_NAN_F32);
+    assert_eq!(
+      f64_to_bits(f64::from_bits(0xfff0_0000_0000_0001)),
+      CANONICAL_NAN_F64
+    );
+    assert_ne!(f32_to_bits(0.0), f32_to_bits(-0.0));
+    assert_eq!(f64_to_bits(1.5), 1.5f64.to_bits());
+  }
+
+  #[test]
+  fn test_decode_canonical() {
+    let scores = Scores((1..=3).map(|k| (k, k as f64 / 2.0)).collect());
+    let encoded = Transformable::encode_to_vec(&scores).unwrap();
+    assert_eq!(
+      <Scores as Transformable>::decode_canonical(&encoded).unwrap(),
+      (30, scores)
+    );
+
+    // entries out of order
+    let mut swapped = encoded.clone();
+    swapped.rotate_left(10);
+    assert!(matches!(
+      <Scores as Transformable>::decode_canonical(&swapped),
+      Err(DecodeCanonicalError::NonCanonical(NonCanonical {
+        offset: 1
+      }))
+    ));
+
+    // a NaN with a payload
+    let mut nan = encoded[..10].to_vec();
+    nan[2..].copy_from_slice(&0x7ff0_0000_0000_0001u64.to_be_bytes());
+    assert!(matches!(
+      <Scores as Transformable>::decode_canonical(&nan),
+      Err(DecodeCanonicalError::NonCanonical(NonCanonical {
+        offset: 3
+      }))
+    ));
+
+    // a varint which is longer than needed
+    assert_eq!(
+      <Varint<u32> as Transformable>::decode(&[0x81, 0x00]).unwrap(),
+      (2, Varint(1))
+    );
+    assert_eq!(
+      <Varint<u32> as Transformable>::decode_canonical(&[0x81, 0x00])
+        .unwrap_err()
+        .to_string(),
+      "non-canonical encoding, which differs from the canonical one at byte 0"
+    );
+    assert_eq!(
+      <Varint<u32> as Transformable>::decode_canonical(&[0x01, 0xff]).unwrap(),
+      (1, Varint(1))
+    );
+  }
+}
@@ -0,0 +1,460 @@
+//! Checksums for [`Checksummed`](crate::Checksummed) values.
+//!
+//! [`Crc32c`] is always available. `XxHash64` is a faster and stronger alternative for large
+//! messages, available with the `xxhash64` feature.
+
+use core::marker::PhantomData;
+
+use byteorder::{ByteOrder, NetworkEndian};
+
+/// The max number of bytes of a checksum on the wire.
+pub(crate) const MAX_SIZE: usize = 8;
+
+/// A checksum which is computed incrementally over the encoded bytes of a value.
+pub trait Checksum: Send + Sync + 'static {
+  /// The number of bytes of the checksum on the wire, from 1 to 8.
+  ///
+  /// Other sizes fail to compile once the checksum is used:
+  ///
+  /// ```rust,compile_fail
+  /// use transformable::{checksum::Checksum, Checksummed, Transformable};
+  ///
+  /// struct Wide;
+  ///
+  /// impl Checksum for Wide {
+  ///   const SIZE: usize = 16;
+  ///
+  ///   type State = u64;
+  ///
+  ///   fn update(state: &mut u64, data: &[u8]) {
+  ///     *state += data.len() as u64;
+  ///   }
+  ///
+  ///   fn finish(state: &u64) -> u64 {
+  ///     *state
+  ///   }
+  /// }
+  ///
+  /// Checksummed::<_, Wide>::new(1u32).encode_to_vec().unwrap();
+  /// ```
+  const SIZE: usize;
+
+  /// The state of the checksum while the bytes are fed into it.
+  type State: Default + Send + Sync + Unpin;
+
+  /// Feeds the given bytes into the checksum.
+  fn update(state: &mut Self::State, data: &[u8]);
+
+  /// Returns the checksum of all the bytes fed so far.
+  ///
+  /// Only the low [`SIZE`](Checksum::SIZE) bytes of the checksum are written to the wire and
+  /// compared, the others are ignored.
+  fn finish(state: &Self::State) -> u64;
+}
+
+/// The CRC-32C (Castagnoli) checksum, as used by iSCSI, SCTP and ext4.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Crc32c;
+
+/// The lookup table of the reflected CRC-32C polynomial.
+const CRC32C_TABLE: [u32; 256] = {
+  let mut table = [0u32; 256];
+  let mut i = 0;
+  while i < 256 {
+    let mut crc = i as u32;
+    let mut bit = 0;
+    while bit < 8 {
+      crc = if crc & 1 == 1 {
+        (crc >> 1) ^ 0x82f6_3b78
+      } else {
+        crc >> 1
+      };
+      bit += 1;
+    }
+    table[i] = crc;
+    i += 1;
+  }
+  table
+};
+
+/// The state of [`Crc32c`].
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy)]
+pub struct Crc32cState(u32);
+
+impl Default for Crc32cState {
+  #[inline]
+  fn default() -> Self {
+    Self(!0)
+  }
+}
+
+impl Checksum for Crc32c {
+  const SIZE: usize = 4;
+
+  type State = Crc32cState;
+
+  #[inline]
+  fn update(state: &mut Self::State, data: &[u8]) {
+    state.0 = data.iter().fold(state.0, |crc, b| {
+      CRC32C_TABLE[((crc ^ *b as u32) & 0xff) as usize] ^ (crc >> 8)
+    });
+  }
+
+  #[inline]
+  fn finish(state: &Self::State) -> u64 {
+    !state.0 as u64
+  }
+}
+
+/// The 64 bits xxHash checksum, with seed `0`.
+#[cfg(feature = "xxhash64")]
+#[cfg_attr(docsrs, doc(cfg(feature = "xxhash64")))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct XxHash64;
+
+/// The state of [`XxHash64`].
+#[cfg(feature = "xxhash64")]
+#[doc(hidden)]
+#[derive(Clone)]
+pub struct XxHash64State(xxhash_rust::xxh64::Xxh64);
+
+#[cfg(feature = "xxhash64")]
+impl Default for XxHash64State {
+  #[inline]
+  fn default() -> Self {
+    Self(xxhash_rust::xxh64::Xxh64::new(0))
+  }
+}
+
+#[cfg(feature = "xxhash64")]
+impl Checksum for XxHash64 {
+  const SIZE: usize = 8;
+
+  type State = XxHash64State;
+
+  #[inline]
+  fn update(state: &mut Self::State, data: &[u8]) {
+    state.0.update(data);
+  }
+
+  #[inline]
+  fn finish(state: &Self::State) -> u64 {
+    state.0.digest()
+  }
+}
+
+/// Returns the checksum `C` of the given bytes.
+#[inline]
+pub(crate) fn checksum_of<C: Checksum>(data: &[u8]) -> u64 {
+  let mut state = C::State::default();
+  C::update(&mut state, data);
+  finish::<C>(&state)
+}
+
+/// Returns the checksum `C` of the bytes fed into the state, truncated to its low
+/// [`C::SIZE`](Checksum::SIZE) bytes.
+#[inline]
+pub(crate) fn finish<C: Checksum>(state: &C::State) -> u64 {
+  truncate::<C>(C::finish(state))
+}
+
+/// Truncates the checksum to its low [`C::SIZE`](Checksum::SIZE) bytes.
+#[inline]
+fn truncate<C: Checksum>(checksum: u64) -> u64 {
+  match C::SIZE {
+    MAX_SIZE => checksum,
+    size => checksum & ((1 << (size * 8)) - 1),
+  }
+}
+
+/// Fails to compile if the size of the checksum `C` is not from 1 to [`MAX_SIZE`] bytes.
+struct ValidSize<C>(PhantomData<C>);
+
+impl<C: Checksum> ValidSize<C> {
+  const OK: () = assert!(
+    C::SIZE >= 1 && C::SIZE <= MAX_SIZE,
+    "the size of a checksum must be from 1 to 8 bytes"
+  );
+}
+
+/// Writes the checksum into the first [`C::SIZE`](Checksum::SIZE) bytes of `dst`.
+#[inline]
+pub(crate) fn write_checksum<C: Checksum>(checksum: u64, dst: &mut [u8]) {
+  #[allow(clippy::let_unit_value)]
+  let () = ValidSize::<C>::OK;
+  NetworkEndian::write_uint(dst, truncate::<C>(checksum), C::SIZE);
+}
+
+/// Reads the checksum from the first [`C::SIZE`](Checksum::SIZE) bytes of `src`.
+#[inline]
+pub(crate) fn read_checksum<C: Checksum>(src: &[u8]) -> u64 {
+  #[allow(clippy::let_unit_value)]
+  let () = ValidSize::<C>::OK;
+  NetworkEndian::read_uint(src, C::SIZE)
+}
+
+/// Computes the checksum of the bytes which pass through the wrapped reader.
+#[cfg(any(feature = "std", feature = "embedded-io"))]
+pub(crate) struct ChecksumReader<'a, R: ?Sized, C: Checksum> {
+  inner: &'a mut R,
+  checksum: C::State,
+}
+
+#[cfg(any(feature = "std", feature = "embedded-io"))]
+impl<'a, R: ?Sized, C: Checksum> ChecksumReader<'a, R, C> {
+  #[inline]
+  pub(crate) fn new(inner: &'a mut R) -> Self {
+    Self {
+      inner,
+      checksum: C::State::default(),
+    }
+  }
+
+  /// Returns the checksum of the bytes read so far, and the wrapped reader to read the checksum
+  /// from.
+  #[inline]
+  pub(crate) fn finish(self) -> (u64, &'a mut R) {
+    (finish::<C>(&self.checksum), self.inner)
+  }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read + ?Sized, C: Checksum> std::io::Read for ChecksumReader<'_, R, C> {
+  fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    let n = self.inner.read(buf)?;
+    C::update(&mut self.checksum, &buf[..n]);
+    Ok(n)
+  }
+}
+
+#[cfg(feature = "async")]
+impl<R, C> futures_util::io::AsyncRead for ChecksumReader<'_, R, C>
+where
+  R: futures_util::io::AsyncRead + Unpin + ?Sized,
+  C: Checksum,
+{
+  fn poll_read(
+    self: core::pin::Pin<&mut Self>,
+    cx: &mut core::task::Context<'_>,
+    buf: &mut [u8],
+  ) -> core::task::Poll<std::io::Result<usize>> {
+    let this = self.get_mut();
+    let res = core::pin::Pin::new(&mut *this.inner).poll_read(cx, buf);
+    if let core::task::Poll::Ready(Ok(n)) = res {
+      C::update(&mut this.checksum, &buf[..n]);
+    }
+    res
+  }
+}
+
+#[cfg(feature = "owned-io")]
+impl<R: crate::OwnedRead + ?Sized, C: Checksum> crate::OwnedRead for ChecksumReader<'_, R, C> {
+  async fn read_exact_owned(&mut self, buf: Vec<u8>, len: usize) -> (std::io::Result<()>, Vec<u8>) {
+    let start = buf.len();
+    let (res, buf) = self.inner.read_exact_owned(buf, len).await;
+    if res.is_ok() {
+      C::update(&mut self.checksum, &buf[start..]);
+    }
+    (res, buf)
+  }
+}
+
+#[cfg(feature = "embedded-io")]
+impl<R: embedded_io::ErrorType + ?Sized, C: Checksum> embedded_io::ErrorType
+  for ChecksumReader<'_, R, C>
+{
+  type Error = R::Error;
+}
+
+#[cfg(feature = "embedded-io")]
+impl<R: embedded_io::Read + ?Sized, C: Checksum> embedded_io::Read for ChecksumReader<'_, R, C> {
+  fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+    let n = self.inner.read(buf)?;
+    C::update(&mut self.checksum, &buf[..n]);
+    Ok(n)
+  }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<R: embedded_io_async::Read + ?Sized, C: Checksum> embedded_io_async::Read
+  for ChecksumReader<'_, R, C>
+{
+  async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+    let n = self.inner.read(buf).await?;
+    C::update(&mut self.checksum, &buf[..n]);
+    Ok(n)
+  }
+}
+
+/// Computes the checksum of the bytes which pass through the wrapped writer.
+#[cfg(feature = "std")]
+pub(crate) struct ChecksumWriter<'a, W: ?Sized, C: Checksum> {
+  inner: &'a mut W,
+  checksum: C::State,
+}
+
+#[cfg(feature = "std")]
+impl<'a, W: ?Sized, C: Checksum> ChecksumWriter<'a, W, C> {
+  #[inline]
+  pub(crate) fn new(inner: &'a mut W) -> Self {
+    Self {
+      inner,
+      checksum: C::State::default(),
+    }
+  }
+
+  /// Returns the checksum of the bytes written so far, and the wrapped writer to write the
+  /// checksum to.
+  #[inline]
+  pub(crate) fn finish(self) -> (u64, &'a mut W) {
+    (finish::<C>(&self.checksum), self.inner)
+  }
+
+  /// Feeds the first `n` bytes of `bufs` into the checksum.
+  fn update_vectored(&mut self, bufs: &[std::io::IoSlice<'_>], mut n: usize) {
+    for buf in bufs {
+      if n == 0 {
+        break;
+      }
+
+      let len = buf.len().min(n);
+      C::update(&mut self.checksum, &buf[..len]);
+      n -= len;
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write + ?Sized, C: Checksum> std::io::Write for ChecksumWriter<'_, W, C> {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    let n = self.inner.write(buf)?;
+    C::update(&mut self.checksum, &buf[..n]);
+    Ok(n)
+  }
+
+  fn write_vectored(&mut self, bufs: &[std::io::IoSlice<'_>]) -> std::io::Result<usize> {
+    let n = self.inner.write_vectored(bufs)?;
+    self.update_vectored(bufs, n);
+    Ok(n)
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    self.inner.flush()
+  }
+}
+
+#[cfg(feature = "async")]
+impl<W, C> futures_util::io::AsyncWrite for ChecksumWriter<'_, W, C>
+where
+  W: futures_util::io::AsyncWrite + Unpin + ?Sized,
+  C: Checksum,
+{
+  fn poll_write(
+    self: core::pin::Pin<&mut Self>,
+    cx: &mut core::task::Context<'_>,
+    buf: &[u8],
+  ) -> core::task::Poll<std::io::Result<usize>> {
+    let this = self.get_mut();
+    let res = core::pin::Pin::new(&mut *this.inner).poll_write(cx, buf);
+    if let core::task::Poll::Ready(Ok(n)) = res {
+      C::update(&mut this.checksum, &buf[..n]);
+    }
+    res
+  }
+
+  fn poll_write_vectored(
+    self: core::pin::Pin<&mut Self>,
+    cx: &mut core::task::Context<'_>,
+    bufs: &[std::io::IoSlice<'_>],
+  ) -> core::task::Poll<std::io::Result<usize>> {
+    let this = self.get_mut();
+    let res = core::pin::Pin::new(&mut *this.inner).poll_write_vectored(cx, bufs);
+    if let core::task::Poll::Ready(Ok(n)) = res {
+      this.update_vectored(bufs, n);
+    }
+    res
+  }
+
+  fn poll_flush(
+    self: core::pin::Pin<&mut Self>,
+    cx: &mut core::task::Context<'_>,
+  ) -> core::task::Poll<std::io::Result<()>> {
+    core::pin::Pin::new(&mut *self.get_mut().inner).poll_flush(cx)
+  }
+
+  fn poll_close(
+    self: core::pin::Pin<&mut Self>,
+    cx: &mut core::task::Context<'_>,
+  ) -> core::task::Poll<std::io::Result<()>> {
+    core::pin::Pin::new(&mut *self.get_mut().inner).poll_close(cx)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn checksum<C: Checksum>(data: &[u8]) -> u64 {
+    let mut state = C::State::default();
+    for chunk in data.chunks(3) {
+      C::update(&mut state, chunk);
+    }
+    let checksum = C::finish(&state);
+    assert_eq!(checksum, checksum_of::<C>(data));
+    checksum
+  }
+
+  #[test]
+  fn test_crc32c() {
+    assert_eq!(checksum::<Crc32c>(b""), 0);
+    assert_eq!(checksum::<Crc32c>(b"123456789"), 0xe306_9283);
+    assert_eq!(checksum::<Crc32c>(&[0u8; 32]), 0x8a91_36aa);
+  }
+
+  /// A checksum whose `finish` returns more bytes than it writes to the wire.
+  struct Wide;
+
+  impl Checksum for Wide {
+    const SIZE: usize = 2;
+
+    type State = u64;
+
+    fn update(state: &mut u64, data: &[u8]) {
+      for byte in data {
+        *state = state.rotate_left(8) ^ *byte as u64;
+      }
+    }
+
+    fn finish(state: &u64) -> u64 {
+      *state | 0xffff_0000_0000_0000
+    }
+  }
+
+  #[test]
+  fn test_truncated() {
+    assert_eq!(checksum_of::<Wide>(b"abc"), 0x6263);
+
+    let mut buf = [0u8; MAX_SIZE];
+    write_checksum::<Wide>(Wide::finish(&0x1234), &mut buf);
+    assert_eq!(buf[..2], [0x12, 0x34]);
+    assert_eq!(read_checksum::<Wide>(&buf), 0x1234);
+
+    let val = crate::Checksummed::<u32, Wide>::new(7);
+    let mut buf = [0u8; 6];
+    assert_eq!(crate::Transformable::encode(&val, &mut buf).unwrap(), 6);
+    let (readed, decoded) =
+      <crate::Checksummed<u32, Wide> as crate::Transformable>::decode(&buf).unwrap();
+    assert_eq!((readed, decoded.into_inner()), (6, 7));
+  }
+
+  #[cfg(feature = "xxhash64")]
+  #[test]
+  fn test_xxhash64() {
+    assert_eq!(checksum::<XxHash64>(b""), 0xef46_db37_51d8_e999);
+    assert_eq!(
+      checksum::<XxHash64>(b"123456789"),
+      xxhash_rust::xxh64::xxh64(b"123456789", 0)
+    );
+  }
+}
@@ -0,0 +1,162 @@
+//! Compression algorithms for [`Compressed`](crate::Compressed) values.
+//!
+//! Every algorithm has a one byte [`ID`](Algorithm::ID), which is written in front of the payload,
+//! so a peer decodes whatever algorithm the sender picked, as long as it is enabled on its side:
+//!
+//! | id  | algorithm | feature  |
+//! |-----|-----------|----------|
+//! | `0` | stored    |          |
+//! | `1` | `Lz4`     | `lz4`    |
+//! | `2` | `Zstd`    | `zstd`   |
+//! | `3` | `Snappy`  | `snappy` |
+//!
+//! A custom [`Algorithm`] is decoded by the [`Compressed`](crate::Compressed) values which use it,
+//! its id must not be [`STORED`], nor any of the ids above which are enabled.
+
+#[cfg(not(feature = "std"))]
+use ::alloc::vec::Vec;
+
+use crate::{ErrorKind, TransformError};
+
+/// The id of payloads which are stored uncompressed.
+pub const STORED: u8 = 0;
+
+/// A compression algorithm, which compresses the encoded bytes of a value.
+pub trait Algorithm: Send + Sync + 'static {
+  /// The id of the algorithm on the wire.
+  ///
+  /// It must not be [`STORED`], which fails to compile once the algorithm is used.
+  const ID: u8;
+
+  /// The name of the algorithm.
+  const NAME: &'static str;
+
+  /// Compresses the given bytes.
+  fn compress(src: &[u8]) -> Result<Vec<u8>, CompressionError>;
+
+  /// Decompresses the given bytes into `dst`, returning the number of bytes written.
+  fn decompress(src: &[u8], dst: &mut [u8]) -> Result<usize, CompressionError>;
+}
+
+/// The error type for errors that get returned when compressing or decompressing fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompressionError {
+  /// Returned when the payload is compressed with an unknown or disabled algorithm.
+  UnknownAlgorithm(u8),
+  /// Returned when the algorithm fails to compress.
+  Compress(&'static str),
+  /// Returned when the compressed payload is corrupted.
+  Decompress(&'static str),
+}
+
+impl From<CompressionError> for TransformError {
+  fn from(err: CompressionError) -> Self {
+    match err {
+      err @ CompressionError::Compress(_) => Self::wrap(ErrorKind::Custom, err),
+      err => Self::wrap(ErrorKind::InvalidValue, err),
+    }
+  }
+}
+
+impl core::fmt::Display for CompressionError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::UnknownAlgorithm(id) => write!(f, "unknown compression algorithm {id}"),
+      Self::Compress(name) => write!(f, "failed to compress with {name}"),
+      Self::Decompress(name) => write!(f, "corrupted {name} payload"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CompressionError {}
+
+/// The [LZ4](https://lz4.org) block format, which favors speed over ratio.
+#[cfg(feature = "lz4")]
+#[cfg_attr(docsrs, doc(cfg(feature = "lz4")))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Lz4;
+
+#[cfg(feature = "lz4")]
+impl Algorithm for Lz4 {
+  const ID: u8 = 1;
+  const NAME: &'static str = "lz4";
+
+  fn compress(src: &[u8]) -> Result<Vec<u8>, CompressionError> {
+    Ok(lz4_flex::block::compress(src))
+  }
+
+  fn decompress(src: &[u8], dst: &mut [u8]) -> Result<usize, CompressionError> {
+    lz4_flex::block::decompress_into(src, dst).map_err(|_| CompressionError::Decompress(Self::NAME))
+  }
+}
+
+/// [Zstandard](https://facebook.github.io/zstd), at its default level, which favors ratio over
+/// speed.
+#[cfg(feature = "zstd")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zstd")))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Zstd;
+
+#[cfg(feature = "zstd")]
+impl Algorithm for Zstd {
+  const ID: u8 = 2;
+  const NAME: &'static str = "zstd";
+
+  fn compress(src: &[u8]) -> Result<Vec<u8>, CompressionError> {
+    zstd::bulk::compress(src, zstd::DEFAULT_COMPRESSION_LEVEL)
+      .map_err(|_| CompressionError::Compress(Self::NAME))
+  }
+
+  fn decompress(src: &[u8], dst: &mut [u8]) -> Result<usize, CompressionError> {
+    zstd::bulk::decompress_to_buffer(src, dst).map_err(|_| CompressionError::Decompress(Self::NAME))
+  }
+}
+
+/// The [Snappy](https://github.com/google/snappy) raw format.
+#[cfg(feature = "snappy")]
+#[cfg_attr(docsrs, doc(cfg(feature = "snappy")))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Snappy;
+
+#[cfg(feature = "snappy")]
+impl Algorithm for Snappy {
+  const ID: u8 = 3;
+  const NAME: &'static str = "snappy";
+
+  fn compress(src: &[u8]) -> Result<Vec<u8>, CompressionError> {
+    snap::raw::Encoder::new()
+      .compress_vec(src)
+      .map_err(|_| CompressionError::Compress(Self::NAME))
+  }
+
+  fn decompress(src: &[u8], dst: &mut [u8]) -> Result<usize, CompressionError> {
+    snap::raw::Decoder::new()
+      .decompress(src, dst)
+      .map_err(|_| CompressionError::Decompress(Self::NAME))
+  }
+}
+
+/// Decompresses the payload compressed with the algorithm `id`, which is either `A` or one of the
+/// built-in algorithms, into `dst`, which must be filled exactly.
+pub(crate) fn decompress<A: Algorithm>(
+  id: u8,
+  src: &[u8],
+  dst: &mut [u8],
+) -> Result<(), CompressionError> {
+  let (name, written) = match id {
+    id if id == A::ID => (A::NAME, A::decompress(src, dst)?),
+    #[cfg(feature = "lz4")]
+    Lz4::ID => (Lz4::NAME, Lz4::decompress(src, dst)?),
+    #[cfg(feature = "zstd")]
+    Zstd::ID => (Zstd::NAME, Zstd::decompress(src, dst)?),
+    #[cfg(feature = "snappy")]
+    Snappy::ID => (Snappy::NAME, Snappy::decompress(src, dst)?),
+    id => return Err(CompressionError::UnknownAlgorithm(id)),
+  };
+
+  if written != dst.len() {
+    return Err(CompressionError::Decompress(name));
+  }
+  Ok(())
+}
@@ -0,0 +1,251 @@
+use super::*;
+
+/// The type can transform its representation between structured and byte form,
+/// depending on a caller-supplied context, e.g. the protocol version negotiated with the peer.
+///
+/// Every [`ContextFree`] type implements `TransformableWith<C>` for any context, by ignoring it.
+/// Composite types should pass the context down to their fields, so a field which depends on it
+/// is encoded the same way, no matter how deep it is nested. The built-in wrappers, e.g.
+/// `Checksummed` or `Compressed`, pass it down to the wrapped value.
+#[cfg(not(feature = "std"))]
+#[cfg_attr(docsrs, doc(cfg(not(feature = "std"))))]
+pub trait TransformableWith<C: ?Sized> {
+  /// The error type returned when encoding or decoding fails.
+  type Error: core::fmt::Display;
+
+  /// Encodes the value into the given buffer for transmission in the given context.
+  ///
+  /// Returns the number of bytes written to the buffer.
+  fn encode_with(&self, ctx: &C, dst: &mut [u8]) -> Result<usize, Self::Error>;
+
+  /// Encodes the value into a vec for transmission in the given context.
+  #[cfg(feature = "alloc")]
+  fn encode_to_vec_with(&self, ctx: &C) -> Result<Vec<u8>, Self::Error> {
+    let mut buf = ::alloc::vec![0u8; self.encoded_len_with(ctx)];
+    self.encode_with(ctx, &mut buf)?;
+    Ok(buf)
+  }
+
+  /// Returns the encoded length of the value in the given context.
+  fn encoded_len_with(&self, ctx: &C) -> usize;
+
+  /// Decodes the value from the given buffer received over the wire in the given context.
+  ///
+  /// Returns the number of bytes read from the buffer and the struct.
+  fn decode_with(ctx: &C, src: &[u8]) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized;
+}
+
+/// The type can transform its representation between structured and byte form,
+/// depending on a caller-supplied context, e.g. the protocol version negotiated with the peer.
+///
+/// Every [`ContextFree`] type implements `TransformableWith<C>` for any context, by ignoring it.
+/// Composite types should pass the context down to their fields, so a field which depends on it
+/// is encoded the same way, no matter how deep it is nested. The built-in wrappers, e.g.
+/// `Checksummed` or `Compressed`, pass it down to the wrapped value.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub trait TransformableWith<C: ?Sized>: Send + Sync + 'static {
+  /// The error type returned when encoding or decoding fails.
+  type Error: std::error::Error + Send + Sync + 'static;
+
+  /// Encodes the value into the given buffer for transmission in the given context.
+  ///
+  /// Returns the number of bytes written to the buffer.
+  fn encode_with(&self, ctx: &C, dst: &mut [u8]) -> Result<usize, Self::Error>;
+
+  /// Encodes the value into a vec for transmission in the given context.
+  fn encode_to_vec_with(&self, ctx: &C) -> Result<Vec<u8>, Self::Error> {
+    let mut buf = ::std::vec![0u8; self.encoded_len_with(ctx)];
+    self.encode_with(ctx, &mut buf)?;
+    Ok(buf)
+  }
+
+  /// Returns the encoded length of the value in the given context.
+  fn encoded_len_with(&self, ctx: &C) -> usize;
+
+  /// Decodes the value from the given buffer received over the wire in the given context.
+  ///
+  /// Returns the number of bytes read from the buffer and the struct.
+  fn decode_with(ctx: &C, src: &[u8]) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized;
+}
+
+/// A [`Transformable`] type whose encoding does not depend on any context, which implements
+/// [`TransformableWith`] for every context by ignoring it.
+///
+/// The built-in types opt in, except for the wrappers which pass the context down to the wrapped
+/// value. A type which implements [`Transformable`], but is encoded differently depending on a
+/// context, implements [`TransformableWith`] itself instead of opting in.
+pub trait ContextFree: Transformable {}
+
+impl<C: ?Sized, T: ContextFree> TransformableWith<C> for T {
+  type Error = T::Error;
+
+  #[inline]
+  fn encode_with(&self, _: &C, dst: &mut [u8]) -> Result<usize, Self::Error> {
+    Transformable::encode(self, dst)
+  }
+
+  #[cfg(feature = "alloc")]
+  #[inline]
+  fn encode_to_vec_with(&self, _: &C) -> Result<Vec<u8>, Self::Error> {
+    Transformable::encode_to_vec(self)
+  }
+
+  #[inline]
+  fn encoded_len_with(&self, _: &C) -> usize {
+    Transformable::encoded_len(self)
+  }
+
+  #[inline]
+  fn decode_with(_: &C, src: &[u8]) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized,
+  {
+    <T as Transformable>::decode(src)
+  }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+  use super::*;
+
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+  struct Version(u8);
+
+  /// A header whose `ttl` is only understood by v3 peers.
+  #[derive(Debug, Clone, PartialEq, Eq)]
+  struct Header {
+    id: u32,
+    ttl: Option<Ttl>,
+  }
+
+  /// A field which is encoded differently depending on the version.
+  #[derive(Debug, Clone, PartialEq, Eq)]
+  struct Ttl(u16);
+
+  impl TransformableWith<Version> for Ttl {
+    type Error = NumberTransformError;
+
+    fn encode_with(&self, ctx: &Version, dst: &mut [u8]) -> Result<usize, Self::Error> {
+      if *ctx >= Version(4) {
+        Transformable::encode(&(self.0 as u32), dst)
+      } else {
+        Transformable::encode(&self.0, dst)
+      }
+    }
+
+    fn encoded_len_with(&self, ctx: &Version) -> usize {
+      if *ctx >= Version(4) {
+        4
+      } else {
+        2
+      }
+    }
+
+    fn decode_with(ctx: &Version, src: &[u8]) -> Result<(usize, Self), Self::Error> {
+      if *ctx >= Version(4) {
+        <u32 as Transformable>::decode(src).map(|(n, ttl)| (n, Self(ttl as u16)))
+      } else {
+        <u16 as Transformable>::decode(src).map(|(n, ttl)| (n, Self(ttl)))
+      }
+    }
+  }
+
+  /// Without a context, the ttl is encoded the way v3 peers understand it.
+  impl Transformable for Ttl {
+    type Error = NumberTransformError;
+
+    fn encode(&self, dst: &mut [u8]) -> Result<usize, Self::Error> {
+      Transformable::encode(&self.0, dst)
+    }
+
+    fn encoded_len(&self) -> usize {
+      2
+    }
+
+    fn decode(src: &[u8]) -> Result<(usize, Self), Self::Error> {
+      <u16 as Transformable>::decode(src).map(|(n, ttl)| (n, Self(ttl)))
+    }
+  }
+
+  impl TransformableWith<Version> for Header {
+    type Error = NumberTransformError;
+
+    fn encode_with(&self, ctx: &Version, dst: &mut [u8]) -> Result<usize, Self::Error> {
+      let mut offset = self.id.encode_with(ctx, dst)?;
+      if *ctx >= Version(3) {
+        let ttl = self.ttl.as_ref().unwrap_or(&Ttl(0));
+        offset += ttl.encode_with(ctx, &mut dst[offset..])?;
+      }
+      Ok(offset)
+    }
+
+    fn encoded_len_with(&self, ctx: &Version) -> usize {
+      let mut len = self.id.encoded_len_with(ctx);
+      if *ctx >= Version(3) {
+        len += self.ttl.as_ref().unwrap_or(&Ttl(0)).encoded_len_with(ctx);
+      }
+      len
+    }
+
+    fn decode_with(ctx: &Version, src: &[u8]) -> Result<(usize, Self), Self::Error> {
+      let (mut offset, id) = u32::decode_with(ctx, src)?;
+      let ttl = if *ctx >= Version(3) {
+        let (readed, ttl) = Ttl::decode_with(ctx, &src[offset..])?;
+        offset += readed;
+        Some(ttl)
+      } else {
+        None
+      };
+      Ok((offset, Self { id, ttl }))
+    }
+  }
+
+  #[test]
+  fn test_context_is_passed_down() {
+    let header = Header {
+      id: 7,
+      ttl: Some(Ttl(64)),
+    };
+
+    let v2 = header.encode_to_vec_with(&Version(2)).unwrap();
+    assert_eq!(v2, [0, 0, 0, 7]);
+    assert_eq!(
+      Header::decode_with(&Version(2), &v2).unwrap(),
+      (4, Header { id: 7, ttl: None })
+    );
+
+    for (version, len) in [(Version(3), 6), (Version(4), 8)] {
+      let buf = header.encode_to_vec_with(&version).unwrap();
+      assert_eq!(buf.len(), len);
+      assert_eq!(header.encoded_len_with(&version), len);
+      assert_eq!(
+        Header::decode_with(&version, &buf).unwrap(),
+        (len, header.clone())
+      );
+    }
+  }
+
+  #[test]
+  fn test_context_is_passed_through_wrappers() {
+    let ttl = crate::Checksummed::<Ttl>::new(Ttl(64));
+    assert_eq!(Transformable::encoded_len(&ttl), 2 + 4);
+    let buf = ttl.encode_to_vec_with(&Version(4)).unwrap();
+    assert_eq!(buf.len(), 4 + 4);
+    assert_eq!(
+      crate::Checksummed::<Ttl>::decode_with(&Version(4), &buf).unwrap(),
+      (8, ttl)
+    );
+  }
+
+  #[test]
+  fn test_context_is_ignored_by_context_free() {
+    let buf = 42u64.encode_to_vec_with(&Version(3)).unwrap();
+    assert_eq!(buf, Transformable::encode_to_vec(&42u64).unwrap());
+    assert_eq!(u64::decode_with("any context", &buf).unwrap(), (8, 42));
+  }
+}
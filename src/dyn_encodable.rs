@@ -0,0 +1,155 @@
+use std::{boxed::Box, io, vec::Vec};
+
+use crate::{Encodable, GatherList};
+
+/// The error returned by [`DynEncodable`], which erases the error type of the value.
+#[derive(Debug)]
+pub struct DynEncodeError(Box<dyn std::error::Error + Send + Sync>);
+
+impl DynEncodeError {
+  /// Wraps the given error.
+  #[inline]
+  pub fn new<E: std::error::Error + Send + Sync + 'static>(err: E) -> Self {
+    Self(Box::new(err))
+  }
+
+  /// Returns a reference to the error of the value.
+  #[inline]
+  pub fn get_ref(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+    self.0.as_ref()
+  }
+
+  /// Consumes the wrapper, returning the error of the value.
+  #[inline]
+  pub fn into_inner(self) -> Box<dyn std::error::Error + Send + Sync> {
+    self.0
+  }
+}
+
+impl core::fmt::Display for DynEncodeError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    self.0.fmt(f)
+  }
+}
+
+impl std::error::Error for DynEncodeError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    self.0.source()
+  }
+}
+
+/// An object-safe [`Encodable`], so values of different types can be kept together, e.g. in a
+/// `Vec<Box<dyn DynEncodable>>` send queue.
+///
+/// It is implemented for every [`Encodable`], and `Box<dyn DynEncodable>` is [`Encodable`] again,
+/// so the queued values can be written with the usual methods.
+pub trait DynEncodable: Send + Sync {
+  /// Encodes the value into the given buffer for transmission.
+  ///
+  /// Returns the number of bytes written to the buffer.
+  fn encode(&self, dst: &mut [u8]) -> Result<usize, DynEncodeError>;
+
+  /// Returns the encoded length of the value.
+  /// This is used to pre-allocate a buffer for encoding.
+  fn encoded_len(&self) -> usize;
+
+  /// Encodes the value into the given writer for transmission.
+  fn encode_to_dyn_writer(&self, writer: &mut dyn io::Write) -> io::Result<usize>;
+
+  /// Encodes the value into a vec for transmission.
+  fn encode_to_vec(&self) -> Result<Vec<u8>, DynEncodeError> {
+    let mut buf = ::std::vec![0u8; DynEncodable::encoded_len(self)];
+    let len = DynEncodable::encode(self, &mut buf)?;
+    buf.truncate(len);
+    Ok(buf)
+  }
+
+  /// Describes the encoded form of the value as a list of byte segments, see
+  /// [`Encodable::as_io_slices`].
+  fn as_io_slices<'a>(&'a self, slices: &mut GatherList<'a>) -> bool;
+}
+
+impl<T: Encodable> DynEncodable for T {
+  fn encode(&self, dst: &mut [u8]) -> Result<usize, DynEncodeError> {
+    Encodable::encode(self, dst).map_err(DynEncodeError::new)
+  }
+
+  fn encoded_len(&self) -> usize {
+    Encodable::encoded_len(self)
+  }
+
+  fn encode_to_dyn_writer(&self, mut writer: &mut dyn io::Write) -> io::Result<usize> {
+    Encodable::encode_to_writer(self, &mut writer)
+  }
+
+  fn encode_to_vec(&self) -> Result<Vec<u8>, DynEncodeError> {
+    Encodable::encode_to_vec(self).map_err(DynEncodeError::new)
+  }
+
+  fn as_io_slices<'a>(&'a self, slices: &mut GatherList<'a>) -> bool {
+    Encodable::as_io_slices(self, slices)
+  }
+}
+
+impl Encodable for Box<dyn DynEncodable> {
+  type Error = DynEncodeError;
+
+  fn encode(&self, dst: &mut [u8]) -> Result<usize, Self::Error> {
+    DynEncodable::encode(self.as_ref(), dst)
+  }
+
+  fn encode_to_vec(&self) -> Result<Vec<u8>, Self::Error> {
+    DynEncodable::encode_to_vec(self.as_ref())
+  }
+
+  fn encode_to_writer<W: io::Write>(&self, writer: &mut W) -> io::Result<usize> {
+    DynEncodable::encode_to_dyn_writer(self.as_ref(), writer)
+  }
+
+  fn encoded_len(&self) -> usize {
+    DynEncodable::encoded_len(self.as_ref())
+  }
+
+  fn as_io_slices<'a>(&'a self, slices: &mut GatherList<'a>) -> bool {
+    DynEncodable::as_io_slices(self.as_ref(), slices)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::Transformable;
+  use std::string::String;
+
+  #[test]
+  fn test_dyn_encodable() {
+    let queue: Vec<Box<dyn DynEncodable>> = std::vec![
+      Box::new(7u64),
+      Box::new(String::from("hello world")),
+      Box::new(std::vec![1u8; 1024]),
+    ];
+
+    let mut batch = Vec::new();
+    for msg in &queue {
+      let len = msg.encode_to_dyn_writer(&mut batch).unwrap();
+      assert_eq!(len, DynEncodable::encoded_len(msg.as_ref()));
+    }
+    assert_eq!(batch.len(), 8 + 15 + 1028);
+
+    let (readed, id) = <u64 as Transformable>::decode(&batch).unwrap();
+    assert_eq!(id, 7);
+    let (n, name) = <String as Transformable>::decode(&batch[readed..]).unwrap();
+    assert_eq!(name, "hello world");
+    let (_, bytes) = <Vec<u8> as Transformable>::decode(&batch[readed + n..]).unwrap();
+    assert_eq!(bytes.len(), 1024);
+
+    // boxed values are encodable again
+    let mut writer = Vec::new();
+    Encodable::encode_to_writer(&queue[1], &mut writer).unwrap();
+    assert_eq!(writer, Encodable::encode_to_vec(&queue[1]).unwrap());
+    assert!(matches!(
+      Encodable::encode(&queue[0], &mut [0u8; 4]),
+      Err(e) if e.get_ref().to_string().contains("buffer is too small")
+    ));
+  }
+}
@@ -0,0 +1,344 @@
+use super::*;
+
+use byteorder::{ByteOrder, NetworkEndian};
+
+/// The error type returned by the [`embedded-io`](embedded_io) based methods of [`Transformable`].
+#[derive(Debug)]
+pub enum EmbeddedIoError<IO, E> {
+  /// Returned when the underlying reader or writer fails.
+  Io(IO),
+  /// Returned when the reader reaches EOF before the value is fully read.
+  UnexpectedEof,
+  /// Returned when the message does not fit in the inline buffer and the `alloc` feature is disabled.
+  MessageTooLarge(usize),
+  /// Returned when the buffer for the message can not be allocated.
+  AllocationFailed {
+    /// The number of bytes which failed to be allocated.
+    requested: usize,
+  },
+  /// Returned when a [`DecodeLimits`] is exceeded, e.g. by a type without limits of its own which
+  /// tries to read past the max message size.
+  Limit(LimitError),
+  /// Returned when encoding or decoding the value fails.
+  Transform(E),
+}
+
+impl<IO: core::fmt::Debug, E: core::fmt::Display> core::fmt::Display for EmbeddedIoError<IO, E> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::Io(e) => write!(f, "io error: {e:?}"),
+      Self::UnexpectedEof => write!(f, "unexpected end of file"),
+      Self::MessageTooLarge(len) => write!(
+        f,
+        "message of {len} bytes exceeds the inline buffer, enable the `alloc` feature to transform it"
+      ),
+      Self::AllocationFailed { requested } => write!(f, "failed to allocate {requested} bytes"),
+      Self::Limit(e) => write!(f, "{e}"),
+      Self::Transform(e) => write!(f, "{e}"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl<IO: core::fmt::Debug, E: std::error::Error> std::error::Error for EmbeddedIoError<IO, E> {}
+
+impl<IO, E> From<embedded_io::ReadExactError<IO>> for EmbeddedIoError<IO, E> {
+  fn from(err: embedded_io::ReadExactError<IO>) -> Self {
+    match err {
+      embedded_io::ReadExactError::UnexpectedEof => Self::UnexpectedEof,
+      embedded_io::ReadExactError::Other(e) => Self::Io(e),
+    }
+  }
+}
+
+/// Allocates a zeroed buffer of `len` bytes, failing instead of aborting if the memory can not be
+/// allocated.
+#[cfg(all(
+  any(feature = "embedded-io", feature = "embedded-io-async"),
+  feature = "alloc"
+))]
+pub(crate) fn try_zeroed<IO, E>(len: usize) -> Result<Vec<u8>, EmbeddedIoError<IO, E>> {
+  let mut buf = Vec::new();
+  buf
+    .try_reserve_exact(len)
+    .map_err(|_| EmbeddedIoError::AllocationFailed { requested: len })?;
+  buf.resize(len, 0);
+  Ok(buf)
+}
+
+/// Encodes the value into an inline buffer and writes it to the given writer.
+///
+/// Used by types whose encoded length never exceeds `MAX_INLINED_BYTES`.
+#[cfg(feature = "embedded-io")]
+pub(crate) fn encode_inlined_to<T, W>(
+  val: &T,
+  writer: &mut W,
+) -> Result<usize, EmbeddedIoError<W::Error, T::Error>>
+where
+  T: Transformable + ?Sized,
+  W: embedded_io::Write,
+{
+  let mut buf = [0u8; MAX_INLINED_BYTES];
+  let len = val.encode(&mut buf).map_err(EmbeddedIoError::Transform)?;
+  writer
+    .write_all(&buf[..len])
+    .map(|_| len)
+    .map_err(EmbeddedIoError::Io)
+}
+
+/// Async version of [`encode_inlined_to`].
+#[cfg(feature = "embedded-io-async")]
+pub(crate) async fn encode_inlined_to_async<T, W>(
+  val: &T,
+  writer: &mut W,
+) -> Result<usize, EmbeddedIoError<W::Error, T::Error>>
+where
+  T: Transformable + ?Sized,
+  W: embedded_io_async::Write,
+{
+  let mut buf = [0u8; MAX_INLINED_BYTES];
+  let len = val.encode(&mut buf).map_err(EmbeddedIoError::Transform)?;
+  writer
+    .write_all(&buf[..len])
+    .await
+    .map(|_| len)
+    .map_err(EmbeddedIoError::Io)
+}
+
+/// Reads exactly `N` bytes from the given reader and decodes the value from them.
+///
+/// Used by types which are always encoded to `N` bytes.
+#[cfg(feature = "embedded-io")]
+pub(crate) fn decode_fixed_from<T, R, const N: usize>(
+  reader: &mut R,
+) -> Result<(usize, T), EmbeddedIoError<R::Error, T::Error>>
+where
+  T: Transformable,
+  R: embedded_io::Read,
+{
+  let mut buf = [0u8; N];
+  reader.read_exact(&mut buf)?;
+  T::decode(&buf).map_err(EmbeddedIoError::Transform)
+}
+
+/// Async version of [`decode_fixed_from`].
+#[cfg(feature = "embedded-io-async")]
+pub(crate) async fn decode_fixed_from_async<T, R, const N: usize>(
+  reader: &mut R,
+) -> Result<(usize, T), EmbeddedIoError<R::Error, T::Error>>
+where
+  T: Transformable,
+  R: embedded_io_async::Read,
+{
+  let mut buf = [0u8; N];
+  reader.read_exact(&mut buf).await?;
+  T::decode(&buf).map_err(EmbeddedIoError::Transform)
+}
+
+#[cfg(feature = "embedded-io")]
+pub(crate) fn encode_to<T, W>(
+  val: &T,
+  writer: &mut W,
+) -> Result<usize, EmbeddedIoError<W::Error, T::Error>>
+where
+  T: Transformable + ?Sized,
+  W: embedded_io::Write,
+{
+  let encoded_len = val.encoded_len();
+  if encoded_len <= MAX_INLINED_BYTES {
+    return encode_inlined_to(val, writer);
+  }
+
+  #[cfg(not(feature = "alloc"))]
+  return Err(EmbeddedIoError::MessageTooLarge(encoded_len));
+
+  #[cfg(feature = "alloc")]
+  {
+    let mut buf = ::alloc::vec![0u8; encoded_len];
+    let len = val.encode(&mut buf).map_err(EmbeddedIoError::Transform)?;
+    writer
+      .write_all(&buf)
+      .map(|_| len)
+      .map_err(EmbeddedIoError::Io)
+  }
+}
+
+#[cfg(feature = "embedded-io-async")]
+pub(crate) async fn encode_to_async<T, W>(
+  val: &T,
+  writer: &mut W,
+) -> Result<usize, EmbeddedIoError<W::Error, T::Error>>
+where
+  T: Transformable + ?Sized,
+  W: embedded_io_async::Write,
+{
+  let encoded_len = val.encoded_len();
+  if encoded_len <= MAX_INLINED_BYTES {
+    return encode_inlined_to_async(val, writer).await;
+  }
+
+  #[cfg(not(feature = "alloc"))]
+  return Err(EmbeddedIoError::MessageTooLarge(encoded_len));
+
+  #[cfg(feature = "alloc")]
+  {
+    let mut buf = ::alloc::vec![0u8; encoded_len];
+    let len = val.encode(&mut buf).map_err(EmbeddedIoError::Transform)?;
+    writer
+      .write_all(&buf)
+      .await
+      .map(|_| len)
+      .map_err(EmbeddedIoError::Io)
+  }
+}
+
+#[cfg(feature = "embedded-io")]
+pub(crate) fn decode_from<T, R>(
+  reader: &mut R,
+) -> Result<(usize, T), EmbeddedIoError<R::Error, T::Error>>
+where
+  T: Transformable,
+  R: embedded_io::Read,
+{
+  let mut len = [0u8; MESSAGE_SIZE_LEN];
+  reader.read_exact(&mut len)?;
+  let msg_len = (NetworkEndian::read_u32(&len) as usize).max(MESSAGE_SIZE_LEN);
+
+  if msg_len <= MAX_INLINED_BYTES {
+    let mut buf = [0u8; MAX_INLINED_BYTES];
+    buf[..MESSAGE_SIZE_LEN].copy_from_slice(&len);
+    reader.read_exact(&mut buf[MESSAGE_SIZE_LEN..msg_len])?;
+    return T::decode(&buf[..msg_len]).map_err(EmbeddedIoError::Transform);
+  }
+
+  #[cfg(not(feature = "alloc"))]
+  return Err(EmbeddedIoError::MessageTooLarge(msg_len));
+
+  #[cfg(feature = "alloc")]
+  {
+    let mut buf = try_zeroed(msg_len)?;
+    buf[..MESSAGE_SIZE_LEN].copy_from_slice(&len);
+    reader.read_exact(&mut buf[MESSAGE_SIZE_LEN..])?;
+    T::decode(&buf).map_err(EmbeddedIoError::Transform)
+  }
+}
+
+#[cfg(feature = "embedded-io-async")]
+pub(crate) async fn decode_from_async<T, R>(
+  reader: &mut R,
+) -> Result<(usize, T), EmbeddedIoError<R::Error, T::Error>>
+where
+  T: Transformable,
+  R: embedded_io_async::Read,
+{
+  let mut len = [0u8; MESSAGE_SIZE_LEN];
+  reader.read_exact(&mut len).await?;
+  let msg_len = (NetworkEndian::read_u32(&len) as usize).max(MESSAGE_SIZE_LEN);
+
+  if msg_len <= MAX_INLINED_BYTES {
+    let mut buf = [0u8; MAX_INLINED_BYTES];
+    buf[..MESSAGE_SIZE_LEN].copy_from_slice(&len);
+    reader
+      .read_exact(&mut buf[MESSAGE_SIZE_LEN..msg_len])
+      .await?;
+    return T::decode(&buf[..msg_len]).map_err(EmbeddedIoError::Transform);
+  }
+
+  #[cfg(not(feature = "alloc"))]
+  return Err(EmbeddedIoError::MessageTooLarge(msg_len));
+
+  #[cfg(feature = "alloc")]
+  {
+    let mut buf = try_zeroed(msg_len)?;
+    buf[..MESSAGE_SIZE_LEN].copy_from_slice(&len);
+    reader.read_exact(&mut buf[MESSAGE_SIZE_LEN..]).await?;
+    T::decode(&buf).map_err(EmbeddedIoError::Transform)
+  }
+}
+
+/// Writes the length prefixed bytes to the given writer.
+#[cfg(all(feature = "embedded-io", feature = "alloc"))]
+pub(crate) fn encode_bytes_to<W: embedded_io::Write, E>(
+  src: &[u8],
+  writer: &mut W,
+) -> Result<usize, EmbeddedIoError<W::Error, E>> {
+  let len = src.len();
+  let mut header = [0u8; MESSAGE_SIZE_LEN];
+  NetworkEndian::write_u32(&mut header, len as u32);
+  writer.write_all(&header).map_err(EmbeddedIoError::Io)?;
+  writer
+    .write_all(src)
+    .map(|_| MESSAGE_SIZE_LEN + len)
+    .map_err(EmbeddedIoError::Io)
+}
+
+/// Async version of [`encode_bytes_to`].
+#[cfg(all(feature = "embedded-io-async", feature = "alloc"))]
+pub(crate) async fn encode_bytes_to_async<W: embedded_io_async::Write, E>(
+  src: &[u8],
+  writer: &mut W,
+) -> Result<usize, EmbeddedIoError<W::Error, E>> {
+  let len = src.len();
+  let mut header = [0u8; MESSAGE_SIZE_LEN];
+  NetworkEndian::write_u32(&mut header, len as u32);
+  writer
+    .write_all(&header)
+    .await
+    .map_err(EmbeddedIoError::Io)?;
+  writer
+    .write_all(src)
+    .await
+    .map(|_| MESSAGE_SIZE_LEN + len)
+    .map_err(EmbeddedIoError::Io)
+}
+
+/// Reads the length prefixed bytes from the given reader.
+#[cfg(all(feature = "embedded-io", feature = "alloc"))]
+pub(crate) fn decode_bytes_from<R: embedded_io::Read, E>(
+  reader: &mut R,
+) -> Result<(usize, Vec<u8>), EmbeddedIoError<R::Error, E>> {
+  decode_bytes_from_limited(reader, &mut DecodeBudget::unlimited())
+}
+
+/// Reads the length prefixed bytes from the given reader, checking their length against the
+/// budget before they are allocated.
+#[cfg(all(feature = "embedded-io", feature = "alloc"))]
+pub(crate) fn decode_bytes_from_limited<R: embedded_io::Read, E>(
+  reader: &mut R,
+  budget: &mut DecodeBudget,
+) -> Result<(usize, Vec<u8>), EmbeddedIoError<R::Error, E>> {
+  let mut header = [0u8; MESSAGE_SIZE_LEN];
+  reader.read_exact(&mut header)?;
+  let len = NetworkEndian::read_u32(&header) as usize;
+  budget
+    .charge_bytes(MESSAGE_SIZE_LEN, len)
+    .map_err(EmbeddedIoError::Limit)?;
+  let mut buf = try_zeroed(len)?;
+  reader.read_exact(&mut buf)?;
+  Ok((MESSAGE_SIZE_LEN + len, buf))
+}
+
+/// Async version of [`decode_bytes_from`].
+#[cfg(all(feature = "embedded-io-async", feature = "alloc"))]
+pub(crate) async fn decode_bytes_from_async<R: embedded_io_async::Read, E>(
+  reader: &mut R,
+) -> Result<(usize, Vec<u8>), EmbeddedIoError<R::Error, E>> {
+  decode_bytes_from_async_limited(reader, &mut DecodeBudget::unlimited()).await
+}
+
+/// Async version of [`decode_bytes_from_limited`].
+#[cfg(all(feature = "embedded-io-async", feature = "alloc"))]
+pub(crate) async fn decode_bytes_from_async_limited<R: embedded_io_async::Read, E>(
+  reader: &mut R,
+  budget: &mut DecodeBudget,
+) -> Result<(usize, Vec<u8>), EmbeddedIoError<R::Error, E>> {
+  let mut header = [0u8; MESSAGE_SIZE_LEN];
+  reader.read_exact(&mut header).await?;
+  let len = NetworkEndian::read_u32(&header) as usize;
+  budget
+    .charge_bytes(MESSAGE_SIZE_LEN, len)
+    .map_err(EmbeddedIoError::Limit)?;
+  let mut buf = try_zeroed(len)?;
+  reader.read_exact(&mut buf).await?;
+  Ok((MESSAGE_SIZE_LEN + len, buf))
+}
@@ -0,0 +1,48 @@
+//! Byte orders for [`Endian`](crate::Endian) values.
+//!
+//! Numbers, durations, system times and ports are encoded in big-endian (network) byte order
+//! by default, [`Endian`](crate::Endian) allows to pick a different one, e.g. [`Little`] to
+//! interoperate with a little-endian protocol, or [`Native`] to avoid byte swapping for
+//! same-host IPC.
+
+mod sealed {
+  pub trait Sealed {}
+
+  impl Sealed for super::Big {}
+  impl Sealed for super::Little {}
+  impl Sealed for super::Native {}
+}
+
+/// A byte order of [`Endian`](crate::Endian) values.
+///
+/// This trait is sealed and can not be implemented outside of this crate.
+pub trait ByteOrder: sealed::Sealed + Send + Sync + 'static {
+  #[doc(hidden)]
+  type Inner: byteorder::ByteOrder;
+}
+
+/// Big-endian (network) byte order, which is the default byte order of this crate.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Big;
+
+impl ByteOrder for Big {
+  type Inner = byteorder::BigEndian;
+}
+
+/// Little-endian byte order.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Little;
+
+impl ByteOrder for Little {
+  type Inner = byteorder::LittleEndian;
+}
+
+/// The byte order of the target platform.
+///
+/// Values encoded with this byte order are only portable between hosts with the same byte order.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Native;
+
+impl ByteOrder for Native {
+  type Inner = byteorder::NativeEndian;
+}
@@ -0,0 +1,515 @@
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use ::alloc::{boxed::Box, vec::Vec};
+
+use crate::LimitError;
+
+/// The kind of a [`TransformError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+  /// The buffer is too small to encode the value.
+  BufferTooSmall,
+  /// The input ends before the value is fully decoded.
+  UnexpectedEof,
+  /// The input is not a valid encoding of the value.
+  InvalidValue,
+  /// Decoding the value exceeds a [`DecodeLimits`](crate::DecodeLimits), or fails to allocate.
+  LimitExceeded,
+  /// An error of a user type.
+  Custom,
+}
+
+impl ErrorKind {
+  /// Returns a short description of the kind.
+  pub const fn as_str(&self) -> &'static str {
+    match self {
+      Self::BufferTooSmall => "buffer is too small, use `Transformable::encoded_len` to pre-allocate a buffer with enough space",
+      Self::UnexpectedEof => "not enough bytes to decode",
+      Self::InvalidValue => "invalid value",
+      Self::LimitExceeded => "decode limit exceeded",
+      Self::Custom => "custom error",
+    }
+  }
+}
+
+impl core::fmt::Display for ErrorKind {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+/// The bounds of the errors a [`TransformError`] can wrap.
+#[cfg(feature = "std")]
+pub(crate) trait Source: std::error::Error + Send + Sync + 'static {}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + Send + Sync + 'static> Source for E {}
+
+/// The bounds of the errors a [`TransformError`] can wrap.
+#[cfg(not(feature = "std"))]
+pub(crate) trait Source:
+  core::fmt::Debug + core::fmt::Display + Send + Sync + 'static
+{
+}
+
+#[cfg(not(feature = "std"))]
+impl<E: core::fmt::Debug + core::fmt::Display + Send + Sync + 'static> Source for E {}
+
+#[derive(Debug)]
+enum Repr {
+  Simple,
+  Message(&'static str),
+  Limit(LimitError),
+  #[cfg(feature = "std")]
+  Custom(std::boxed::Box<dyn std::error::Error + Send + Sync>),
+  #[cfg(all(feature = "alloc", not(feature = "std")))]
+  Custom(Box<dyn Source>),
+}
+
+/// A step of the path to the field whose decoding failed, see `TransformError::path`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+  /// A field of a struct.
+  Field(&'static str),
+  /// An element of a sequence.
+  Index(usize),
+}
+
+/// Records where an error happened, as it moves out of the fields of a composite value.
+///
+/// The methods do nothing by default, so any error type can opt in with an empty impl, e.g. to be
+/// used with [`impl_tagged!`](crate::impl_tagged). [`TransformError`] records the context, and
+/// `Result`s forward it to their error.
+///
+/// The errors of the built-in types, e.g. numbers, strings, addresses or durations, opt in with
+/// an empty impl, as they have no room for the context. The context of a field of such a type
+/// ends at the field: a truncated port of a `SocketAddr` in the `addr` field is reported as
+/// `addr`, at the offset where the address starts. The errors of the built-in wrappers, e.g.
+/// `Compressed` or `Versioned`, forward the context to the error of the wrapped value.
+pub trait DecodeContext: Sized {
+  /// Records that the error happened in the field with the given name.
+  fn in_field(self, name: &'static str) -> Self {
+    let _ = name;
+    self
+  }
+
+  /// Records that the error happened in the element with the given index.
+  fn at_index(self, index: usize) -> Self {
+    let _ = index;
+    self
+  }
+
+  /// Records that the value which failed starts at the given offset in the value it is decoded
+  /// from.
+  fn at_offset(self, offset: usize) -> Self {
+    let _ = offset;
+    self
+  }
+}
+
+impl DecodeContext for TransformError {
+  #[inline]
+  fn in_field(self, name: &'static str) -> Self {
+    TransformError::in_field(self, name)
+  }
+
+  #[inline]
+  fn at_index(self, index: usize) -> Self {
+    TransformError::at_index(self, index)
+  }
+
+  #[inline]
+  fn at_offset(self, offset: usize) -> Self {
+    TransformError::at_offset(self, offset)
+  }
+}
+
+impl<T, E: DecodeContext> DecodeContext for Result<T, E> {
+  #[inline]
+  fn in_field(self, name: &'static str) -> Self {
+    self.map_err(|e| e.in_field(name))
+  }
+
+  #[inline]
+  fn at_index(self, index: usize) -> Self {
+    self.map_err(|e| e.at_index(index))
+  }
+
+  #[inline]
+  fn at_offset(self, offset: usize) -> Self {
+    self.map_err(|e| e.at_offset(offset))
+  }
+}
+
+/// An error type shared by all the types, which can be told apart by its [`kind`](TransformError::kind).
+///
+/// All the error types of this crate convert into it, so a composite type can use it as its
+/// [`Error`](crate::Transformable::Error) and forward the errors of its fields with `?`.
+/// The original error is kept as the source of the `TransformError`,
+/// unless the `alloc` feature is disabled.
+///
+/// Composite types and wrappers record where the error happened with [`DecodeContext`] as the
+/// error moves out of their fields: the [byte offset](TransformError::offset) where decoding
+/// failed, and the path to the field which failed, e.g. `members[3].addr`. Both are shown by
+/// `Display`. The context stops at the fields of the built-in types, see [`DecodeContext`].
+#[derive(Debug)]
+pub struct TransformError {
+  kind: ErrorKind,
+  repr: Repr,
+  offset: Option<usize>,
+  #[cfg(feature = "alloc")]
+  path: Vec<PathSegment>,
+}
+
+impl TransformError {
+  #[inline]
+  const fn from_repr(kind: ErrorKind, repr: Repr) -> Self {
+    Self {
+      kind,
+      repr,
+      offset: None,
+      #[cfg(feature = "alloc")]
+      path: Vec::new(),
+    }
+  }
+
+  /// Creates an error of the given kind, described by the kind.
+  #[inline]
+  pub const fn from_kind(kind: ErrorKind) -> Self {
+    Self::from_repr(kind, Repr::Simple)
+  }
+
+  /// Creates an error of the given kind, with the given message.
+  #[inline]
+  pub const fn with_message(kind: ErrorKind, msg: &'static str) -> Self {
+    Self::from_repr(kind, Repr::Message(msg))
+  }
+
+  /// Creates an error of the given kind, which wraps the given error.
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+  #[inline]
+  pub fn new<E: std::error::Error + Send + Sync + 'static>(kind: ErrorKind, err: E) -> Self {
+    Self::from_repr(kind, Repr::Custom(std::boxed::Box::new(err)))
+  }
+
+  /// Creates an error of the given kind, which wraps the given error.
+  #[cfg(all(feature = "alloc", not(feature = "std")))]
+  #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+  #[inline]
+  pub fn new<E: core::fmt::Debug + core::fmt::Display + Send + Sync + 'static>(
+    kind: ErrorKind,
+    err: E,
+  ) -> Self {
+    Self::from_repr(kind, Repr::Custom(Box::new(err)))
+  }
+
+  /// Creates an error of the [`Custom`](ErrorKind::Custom) kind, which wraps the given error.
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+  #[inline]
+  pub fn custom<E: std::error::Error + Send + Sync + 'static>(err: E) -> Self {
+    Self::new(ErrorKind::Custom, err)
+  }
+
+  /// Creates an error of the [`Custom`](ErrorKind::Custom) kind, which wraps the given error.
+  #[cfg(all(feature = "alloc", not(feature = "std")))]
+  #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+  #[inline]
+  pub fn custom<E: core::fmt::Debug + core::fmt::Display + Send + Sync + 'static>(err: E) -> Self {
+    Self::new(ErrorKind::Custom, err)
+  }
+
+  /// Creates an error of the given kind, which wraps the given error of this crate if the `alloc`
+  /// feature is enabled.
+  #[inline]
+  pub(crate) fn wrap<E: Source>(kind: ErrorKind, err: E) -> Self {
+    #[cfg(feature = "alloc")]
+    {
+      Self::new(kind, err)
+    }
+
+    #[cfg(not(feature = "alloc"))]
+    {
+      let _ = err;
+      Self::from_kind(kind)
+    }
+  }
+
+  /// Returns the kind of the error.
+  #[inline]
+  pub const fn kind(&self) -> ErrorKind {
+    self.kind
+  }
+
+  /// Returns the offset, in bytes from the start of the outermost value, of the byte where
+  /// decoding failed, if it is recorded.
+  ///
+  /// It is as precise as the innermost decoder which records it: a value which does not know where
+  /// in its bytes it failed is reported at its start. The offsets of values inside compressed or
+  /// encrypted values are relative to their decompressed or decrypted bytes.
+  #[inline]
+  pub const fn offset(&self) -> Option<usize> {
+    self.offset
+  }
+
+  /// Returns the path to the field whose decoding failed, from the outermost value.
+  #[cfg(feature = "alloc")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+  #[inline]
+  pub fn path(&self) -> &[PathSegment] {
+    &self.path
+  }
+
+  /// Records that the error happened in the field with the given name.
+  #[inline]
+  pub fn in_field(self, name: &'static str) -> Self {
+    self.push(PathSegment::Field(name))
+  }
+
+  /// Records that the error happened in the element with the given index.
+  #[inline]
+  pub fn at_index(self, index: usize) -> Self {
+    self.push(PathSegment::Index(index))
+  }
+
+  /// Records that the value which failed starts at the given offset in the value it is decoded
+  /// from. The offsets recorded by every level add up.
+  #[inline]
+  pub fn at_offset(mut self, offset: usize) -> Self {
+    self.offset = Some(self.offset.unwrap_or(0) + offset);
+    self
+  }
+
+  #[cfg(feature = "alloc")]
+  #[inline]
+  fn push(mut self, segment: PathSegment) -> Self {
+    self.path.insert(0, segment);
+    self
+  }
+
+  #[cfg(not(feature = "alloc"))]
+  #[inline]
+  fn push(self, segment: PathSegment) -> Self {
+    let _ = segment;
+    self
+  }
+
+  /// Returns a reference to the wrapped error, if any.
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  pub fn get_ref(&self) -> Option<&(dyn std::error::Error + Send + Sync + 'static)> {
+    match &self.repr {
+      Repr::Limit(e) => Some(e),
+      Repr::Custom(e) => Some(e.as_ref()),
+      _ => None,
+    }
+  }
+
+  /// Returns a reference to the wrapped error, if it is of type `E`.
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  pub fn downcast_ref<E: std::error::Error + 'static>(&self) -> Option<&E> {
+    self.get_ref().and_then(|e| e.downcast_ref())
+  }
+}
+
+impl From<ErrorKind> for TransformError {
+  #[inline]
+  fn from(kind: ErrorKind) -> Self {
+    Self::from_kind(kind)
+  }
+}
+
+impl From<LimitError> for TransformError {
+  #[inline]
+  fn from(err: LimitError) -> Self {
+    Self::from_repr(ErrorKind::LimitExceeded, Repr::Limit(err))
+  }
+}
+
+impl From<core::convert::Infallible> for TransformError {
+  #[inline]
+  fn from(err: core::convert::Infallible) -> Self {
+    match err {}
+  }
+}
+
+impl core::fmt::Display for TransformError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    #[cfg(feature = "alloc")]
+    let has_path = !self.path.is_empty();
+    #[cfg(not(feature = "alloc"))]
+    let has_path = false;
+
+    #[cfg(feature = "alloc")]
+    if has_path {
+      f.write_str("decoding ")?;
+      for (idx, segment) in self.path.iter().enumerate() {
+        match segment {
+          PathSegment::Field(name) if idx == 0 => f.write_str(name)?,
+          PathSegment::Field(name) => write!(f, ".{name}")?,
+          PathSegment::Index(index) => write!(f, "[{index}]")?,
+        }
+      }
+    }
+    match self.offset {
+      Some(offset) if has_path => write!(f, " at byte {offset}: ")?,
+      Some(offset) => write!(f, "at byte {offset}: ")?,
+      None if has_path => f.write_str(": ")?,
+      None => {}
+    }
+
+    match &self.repr {
+      Repr::Simple => write!(f, "{}", self.kind),
+      Repr::Message(msg) => f.write_str(msg),
+      Repr::Limit(e) => write!(f, "{e}"),
+      #[cfg(feature = "alloc")]
+      Repr::Custom(e) => write!(f, "{e}"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TransformError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match &self.repr {
+      Repr::Limit(e) => Some(e),
+      Repr::Custom(e) => Some(e.as_ref()),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use super::*;
+  use crate::{NumberTransformError, Transformable};
+  use std::string::ToString;
+
+  /// A composite type which forwards the errors of its fields.
+  #[derive(Debug, Clone, PartialEq, Eq)]
+  struct Member {
+    id: u64,
+    name: std::string::String,
+  }
+
+  impl Transformable for Member {
+    type Error = TransformError;
+
+    fn encode(&self, dst: &mut [u8]) -> Result<usize, Self::Error> {
+      if dst.len() < self.encoded_len() {
+        return Err(ErrorKind::BufferTooSmall.into());
+      }
+      let len = Transformable::encode(&self.id, dst)?;
+      Ok(len + Transformable::encode(&self.name, &mut dst[len..])?)
+    }
+
+    fn encoded_len(&self) -> usize {
+      Transformable::encoded_len(&self.id) + Transformable::encoded_len(&self.name)
+    }
+
+    fn decode(src: &[u8]) -> Result<(usize, Self), Self::Error> {
+      let (readed, id) = <u64 as Transformable>::decode(src)?;
+      let (n, name) = <std::string::String as Transformable>::decode(&src[readed..])?;
+      if name.is_empty() {
+        return Err(TransformError::with_message(
+          ErrorKind::InvalidValue,
+          "empty member name",
+        ));
+      }
+      Ok((readed + n, Self { id, name }))
+    }
+  }
+
+  #[test]
+  fn test_kinds() {
+    let member = Member {
+      id: 7,
+      name: std::string::String::from("node-7"),
+    };
+    let encoded = Transformable::encode_to_vec(&member).unwrap();
+    assert_eq!(
+      <Member as Transformable>::decode(&encoded).unwrap(),
+      (encoded.len(), member.clone())
+    );
+    let err = Transformable::encode(&member, &mut [0u8; 4]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::BufferTooSmall);
+    let err = <Member as Transformable>::decode(&encoded[..6]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    assert_eq!(err.to_string(), "not enough bytes to decode");
+    #[cfg(feature = "std")]
+    assert!(<Member as Transformable>::is_incomplete(&err));
+
+    let mut invalid = encoded.clone();
+    invalid[12] = 0xff;
+    let err = <Member as Transformable>::decode(&invalid).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidValue);
+    #[cfg(feature = "std")]
+    assert!(!<Member as Transformable>::is_incomplete(&err));
+    #[cfg(feature = "std")]
+    assert!(matches!(
+      err.downcast_ref::<crate::StringTransformError>(),
+      Some(crate::StringTransformError::Utf8Error(_))
+    ));
+
+    let mut empty = Transformable::encode_to_vec(&7u64).unwrap();
+    empty.extend(Transformable::encode_to_vec(&std::string::String::new()).unwrap());
+    let err = <Member as Transformable>::decode(&empty).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidValue);
+    assert_eq!(err.to_string(), "empty member name");
+
+    let err = TransformError::from(LimitError::DepthLimitExceeded { limit: 1 });
+    assert_eq!(err.kind(), ErrorKind::LimitExceeded);
+    assert_eq!(
+      TransformError::from(NumberTransformError::NotEnoughBytes).kind(),
+      ErrorKind::UnexpectedEof
+    );
+  }
+
+  #[test]
+  fn test_context() {
+    let err = TransformError::from(NumberTransformError::NotEnoughBytes);
+    assert_eq!(err.offset(), None);
+    assert!(err.path().is_empty());
+
+    let err = err.at_offset(2).in_field("port").at_offset(16);
+    assert_eq!(err.offset(), Some(18));
+    assert_eq!(
+      err.to_string(),
+      "decoding port at byte 18: not enough bytes to decode"
+    );
+
+    let res: Result<(), _> = Err(err);
+    let err = DecodeContext::in_field(res, "addr")
+      .at_index(3)
+      .in_field("members")
+      .at_offset(100)
+      .unwrap_err();
+    assert_eq!(err.offset(), Some(118));
+    assert_eq!(
+      err.path(),
+      &[
+        PathSegment::Field("members"),
+        PathSegment::Index(3),
+        PathSegment::Field("addr"),
+        PathSegment::Field("port")
+      ]
+    );
+    assert_eq!(
+      err.to_string(),
+      "decoding members[3].addr.port at byte 118: not enough bytes to decode"
+    );
+    assert_eq!(
+      TransformError::from_kind(ErrorKind::UnexpectedEof)
+        .in_field("id")
+        .to_string(),
+      "decoding id: not enough bytes to decode"
+    );
+    assert_eq!(
+      TransformError::from_kind(ErrorKind::UnexpectedEof)
+        .at_offset(4)
+        .to_string(),
+      "at byte 4: not enough bytes to decode"
+    );
+  }
+}
@@ -0,0 +1,253 @@
+use crate::{ErrorKind, TransformError};
+
+/// The error for a value which does not consume all of its input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TrailingBytes {
+  /// The number of bytes consumed by the value.
+  pub consumed: usize,
+  /// The total number of bytes of the input.
+  pub total: usize,
+}
+
+impl TrailingBytes {
+  /// Returns an error if `consumed` is less than `total`.
+  #[inline]
+  pub(crate) const fn check(consumed: usize, total: usize) -> Result<(), Self> {
+    if consumed < total {
+      return Err(Self { consumed, total });
+    }
+    Ok(())
+  }
+}
+
+impl core::fmt::Display for TrailingBytes {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(
+      f,
+      "{} trailing bytes after the value, which consumed {} of {} bytes",
+      self.total - self.consumed,
+      self.consumed,
+      self.total
+    )
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TrailingBytes {}
+
+impl From<TrailingBytes> for TransformError {
+  fn from(err: TrailingBytes) -> Self {
+    Self::wrap(ErrorKind::InvalidValue, err)
+  }
+}
+
+/// The error type for errors that get returned by [`decode_exact`](crate::Transformable::decode_exact).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeExactError<E> {
+  /// Returned when decoding the value fails.
+  Decode(E),
+  /// Returned when the value does not consume the whole buffer.
+  TrailingBytes(TrailingBytes),
+}
+
+impl<E: Into<TransformError>> From<DecodeExactError<E>> for TransformError {
+  fn from(err: DecodeExactError<E>) -> Self {
+    match err {
+      DecodeExactError::Decode(e) => e.into(),
+      DecodeExactError::TrailingBytes(e) => e.into(),
+    }
+  }
+}
+
+impl<E> From<TrailingBytes> for DecodeExactError<E> {
+  #[inline]
+  fn from(err: TrailingBytes) -> Self {
+    Self::TrailingBytes(err)
+  }
+}
+
+impl<E: core::fmt::Display> core::fmt::Display for DecodeExactError<E> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::Decode(err) => write!(f, "{err}"),
+      Self::TrailingBytes(err) => write!(f, "{err}"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for DecodeExactError<E> {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Decode(err) => Some(err),
+      Self::TrailingBytes(err) => Some(err),
+    }
+  }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+  use super::*;
+  use crate::Transformable;
+
+  #[test]
+  fn test_decode_exact() {
+    let mut encoded = Transformable::encode_to_vec(&std::string::String::from("hello")).unwrap();
+    assert_eq!(
+      <std::string::String as Transformable>::decode_exact(&encoded).unwrap(),
+      "hello"
+    );
+
+    encoded.extend_from_slice(b"garbage");
+    assert_eq!(
+      <std::string::String as Transformable>::decode_exact(&encoded)
+        .unwrap_err()
+        .to_string(),
+      "7 trailing bytes after the value, which consumed 9 of 16 bytes"
+    );
+    assert!(matches!(
+      <u32 as Transformable>::decode_exact(&encoded[..2]),
+      Err(DecodeExactError::Decode(_))
+    ));
+  }
+
+  /// A value which starts with its total length, but ignores what follows its id.
+  #[derive(Debug, PartialEq, Eq)]
+  struct Ping(u64);
+
+  impl Transformable for Ping {
+    type Error = TransformError;
+
+    fn encode(&self, dst: &mut [u8]) -> Result<usize, Self::Error> {
+      if dst.len() < 12 {
+        return Err(ErrorKind::BufferTooSmall.into());
+      }
+      dst[..4].copy_from_slice(&12u32.to_be_bytes());
+      dst[4..12].copy_from_slice(&self.0.to_be_bytes());
+      Ok(12)
+    }
+
+    fn encoded_len(&self) -> usize {
+      12
+    }
+
+    fn decode(src: &[u8]) -> Result<(usize, Self), Self::Error> {
+      if src.len() < 12 {
+        return Err(ErrorKind::UnexpectedEof.into());
+      }
+      Ok((12, Self(u64::from_be_bytes(src[4..12].try_into().unwrap()))))
+    }
+  }
+
+  /// Encodes a ping whose frame declares `len` bytes, followed by another ping.
+  fn frames(len: u32) -> std::vec::Vec<u8> {
+    let mut encoded = Transformable::encode_to_vec(&Ping(42)).unwrap();
+    encoded[..4].copy_from_slice(&len.to_be_bytes());
+    encoded.resize((len as usize).max(12), 0);
+    encoded.extend(Transformable::encode_to_vec(&Ping(7)).unwrap());
+    encoded
+  }
+
+  #[test]
+  fn test_decode_from_reader_exact() {
+    let encoded = frames(12);
+    let mut reader = encoded.as_slice();
+    assert_eq!(
+      <Ping as Transformable>::decode_from_reader_exact(&mut reader).unwrap(),
+      (12, Ping(42))
+    );
+    // the next frame is left in the reader
+    assert_eq!(reader.len(), 12);
+
+    let encoded = frames(13);
+    let err =
+      <Ping as Transformable>::decode_from_reader_exact(&mut encoded.as_slice()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(
+      *err
+        .into_inner()
+        .unwrap()
+        .downcast::<TrailingBytes>()
+        .unwrap(),
+      TrailingBytes {
+        consumed: 12,
+        total: 13
+      }
+    );
+
+    let encoded = frames(3);
+    let err =
+      <Ping as Transformable>::decode_from_reader_exact(&mut encoded.as_slice()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+  }
+
+  #[test]
+  fn test_decode_from_reader_exact_builtin() {
+    use std::{string::String, vec::Vec};
+
+    // values are decoded in their own format, and the following bytes stay in the reader
+    let mut encoded = Transformable::encode_to_vec(&String::from("hello")).unwrap();
+    encoded.extend_from_slice(b"next");
+    let mut reader = encoded.as_slice();
+    assert_eq!(
+      <String as Transformable>::decode_from_reader_exact(&mut reader).unwrap(),
+      (9, String::from("hello"))
+    );
+    assert_eq!(reader, b"next");
+
+    for val in [1u64, 0x0000_0010_0000_0001] {
+      let mut encoded = Transformable::encode_to_vec(&val).unwrap();
+      encoded.extend_from_slice(&[0; 8]);
+      let mut reader = encoded.as_slice();
+      assert_eq!(
+        <u64 as Transformable>::decode_from_reader_exact(&mut reader).unwrap(),
+        (8, val)
+      );
+      assert_eq!(reader.len(), 8);
+    }
+
+    let encoded = Transformable::encode_to_vec(&std::vec![1u8, 2, 3]).unwrap();
+    assert_eq!(
+      <Vec<u8> as Transformable>::decode_from_reader_exact(&mut encoded.as_slice()).unwrap(),
+      (7, std::vec![1, 2, 3])
+    );
+
+    let err = <String as Transformable>::decode_from_reader_exact(&mut &encoded[..5]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+  }
+
+  #[cfg(feature = "async")]
+  #[tokio::test]
+  async fn test_decode_from_async_reader_exact() {
+    use futures_util::io::Cursor;
+
+    let mut reader = Cursor::new(frames(12));
+    assert_eq!(
+      <Ping as Transformable>::decode_from_async_reader_exact(&mut reader)
+        .await
+        .unwrap(),
+      (12, Ping(42))
+    );
+    assert_eq!(reader.position(), 12);
+
+    let err = <Ping as Transformable>::decode_from_async_reader_exact(&mut Cursor::new(frames(13)))
+      .await
+      .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let mut encoded = Transformable::encode_to_vec(&std::string::String::from("hello")).unwrap();
+    encoded.extend(Transformable::encode_to_vec(&1u64).unwrap());
+    let mut reader = Cursor::new(encoded);
+    assert_eq!(
+      <std::string::String as Transformable>::decode_from_async_reader_exact(&mut reader)
+        .await
+        .unwrap(),
+      (9, std::string::String::from("hello"))
+    );
+    assert_eq!(
+      <u64 as Transformable>::decode_from_async_reader_exact(&mut reader)
+        .await
+        .unwrap(),
+      (8, 1)
+    );
+  }
+}
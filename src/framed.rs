@@ -0,0 +1,523 @@
+use super::*;
+
+use byteorder::{ByteOrder, NetworkEndian};
+use core::{
+  marker::PhantomData,
+  pin::Pin,
+  task::{Context, Poll},
+};
+use futures_util::{
+  io::{AsyncRead, AsyncWrite},
+  Sink, Stream,
+};
+use std::io;
+
+use crate::utils::invalid_data;
+
+const DEFAULT_READ_CHUNK: usize = 8 * 1024;
+const DEFAULT_BACKPRESSURE_BOUNDARY: usize = 8 * 1024;
+
+/// A [`Stream`] of [`Transformable`] values decoded from an [`AsyncRead`].
+///
+/// Reads back the frames written by [`TransformableSink`]: every value is preceded by the length
+/// of its encoding, as a 4 bytes big-endian integer, so values of any type, e.g. integers,
+/// strings or tagged structs, are found one by one whatever their own format is. The length is
+/// checked against the max message size of the [limits](TransformableStream::with_limits) before
+/// the value is buffered, and the value is decoded within them.
+///
+/// The stream ends with `None` when the reader is closed on a frame boundary. If the reader is
+/// closed in the middle of a frame, the stream yields an [`io::ErrorKind::UnexpectedEof`] error
+/// before it ends. A value which fails to decode yields an [`io::ErrorKind::InvalidData`] error
+/// and the stream goes on with the next frame, but the stream ends after a length which exceeds
+/// the limits, as the next frame can not be found.
+///
+/// By default, bytes at the end of a frame which are not consumed by the value are ignored,
+/// see [`with_strict`](TransformableStream::with_strict) to reject them.
+#[derive(Debug)]
+pub struct TransformableStream<R, T> {
+  reader: R,
+  buf: Vec<u8>,
+  pos: usize,
+  read_chunk: usize,
+  limits: DecodeLimits,
+  strict: bool,
+  eof: bool,
+  _marker: PhantomData<fn() -> T>,
+}
+
+impl<R, T> TransformableStream<R, T> {
+  /// Creates a new stream which decodes values from the given reader.
+  #[inline]
+  pub fn new(reader: R) -> Self {
+    Self {
+      reader,
+      buf: Vec::new(),
+      pos: 0,
+      read_chunk: DEFAULT_READ_CHUNK,
+      limits: DecodeLimits::default(),
+      strict: false,
+      eof: false,
+      _marker: PhantomData,
+    }
+  }
+
+  /// Sets the number of bytes the stream tries to read from the reader at once.
+  ///
+  /// Default is 8 KiB.
+  #[inline]
+  pub fn with_read_chunk(mut self, read_chunk: usize) -> Self {
+    self.read_chunk = read_chunk.max(1);
+    self
+  }
+
+  /// Sets the limits every value is decoded within, the max message size bounds the length of
+  /// a frame.
+  ///
+  /// Default is [`DecodeLimits::default`].
+  #[inline]
+  pub fn with_limits(mut self, limits: DecodeLimits) -> Self {
+    self.limits = limits;
+    self
+  }
+
+  /// Sets whether the stream verifies that every value consumes its whole frame.
+  ///
+  /// In strict mode, a frame whose declared length does not match what the value consumed
+  /// yields an [`io::ErrorKind::InvalidData`] error, which wraps a [`TrailingBytes`].
+  ///
+  /// Default is `false`.
+  #[inline]
+  pub fn with_strict(mut self, strict: bool) -> Self {
+    self.strict = strict;
+    self
+  }
+
+  /// Returns a reference to the inner reader.
+  #[inline]
+  pub const fn get_ref(&self) -> &R {
+    &self.reader
+  }
+
+  /// Returns a mutable reference to the inner reader.
+  #[inline]
+  pub fn get_mut(&mut self) -> &mut R {
+    &mut self.reader
+  }
+
+  /// Returns the bytes which have been read from the reader, but not yet decoded.
+  #[inline]
+  pub fn read_buffer(&self) -> &[u8] {
+    &self.buf[self.pos..]
+  }
+
+  /// Consumes the stream, returning the inner reader.
+  ///
+  /// Bytes which have been read from the reader but not yet decoded are lost.
+  #[inline]
+  pub fn into_inner(self) -> R {
+    self.reader
+  }
+
+  /// Ends the stream with the given error, as the next frame can not be found.
+  fn fail(&mut self, err: io::Error) -> Option<io::Result<T>> {
+    self.buf.clear();
+    self.pos = 0;
+    self.eof = true;
+    Some(Err(err))
+  }
+
+  /// Decodes a value if a full frame is buffered.
+  fn decode_frame(&mut self) -> Option<io::Result<T>>
+  where
+    T: Transformable,
+  {
+    let buffered = &self.buf[self.pos..];
+    if buffered.len() < MESSAGE_SIZE_LEN {
+      return None;
+    }
+
+    let len = NetworkEndian::read_u32(&buffered[..MESSAGE_SIZE_LEN]) as usize;
+    let mut budget = DecodeBudget::new(self.limits);
+    if let Err(e) = budget.check_message_size(len) {
+      return self.fail(invalid_data(e));
+    }
+    let Some(end) = len.checked_add(MESSAGE_SIZE_LEN) else {
+      return self.fail(invalid_data(LimitError::MessageTooLarge {
+        limit: self.limits.max_message_size(),
+      }));
+    };
+    if buffered.len() < end {
+      return None;
+    }
+
+    let frame = &buffered[MESSAGE_SIZE_LEN..end];
+    let res = match T::decode_limited(frame, &mut budget) {
+      Ok((readed, val)) if self.strict => TrailingBytes::check(readed, frame.len())
+        .map(|_| val)
+        .map_err(invalid_data),
+      Ok((_, val)) => Ok(val),
+      Err(e) => Err(invalid_data(e)),
+    };
+    self.pos += end;
+    if self.pos == self.buf.len() {
+      self.buf.clear();
+      self.pos = 0;
+    }
+    Some(res)
+  }
+
+  /// Moves the bytes which are not decoded yet to the front of the buffer, once they are less
+  /// than the bytes which are decoded already, so the buffer does not grow without bound.
+  fn compact(&mut self) {
+    if self.pos > 0 && self.pos >= self.buf.len() - self.pos {
+      self.buf.copy_within(self.pos.., 0);
+      self.buf.truncate(self.buf.len() - self.pos);
+      self.pos = 0;
+    }
+  }
+}
+
+impl<R, T> Stream for TransformableStream<R, T>
+where
+  R: AsyncRead + Unpin,
+  T: Transformable,
+{
+  type Item = io::Result<T>;
+
+  fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    let this = self.get_mut();
+    loop {
+      if let Some(res) = this.decode_frame() {
+        return Poll::Ready(Some(res));
+      }
+
+      if this.eof {
+        return Poll::Ready(None);
+      }
+
+      this.compact();
+      let filled = this.buf.len();
+      this.buf.resize(filled + this.read_chunk, 0);
+      let res = Pin::new(&mut this.reader).poll_read(cx, &mut this.buf[filled..]);
+      match res {
+        Poll::Ready(Ok(0)) => {
+          this.buf.truncate(filled);
+          this.eof = true;
+          if this.pos < this.buf.len() {
+            this.buf.clear();
+            this.pos = 0;
+            return Poll::Ready(Some(Err(io::Error::new(
+              io::ErrorKind::UnexpectedEof,
+              "stream closed in the middle of a frame",
+            ))));
+          }
+        }
+        Poll::Ready(Ok(n)) => this.buf.truncate(filled + n),
+        Poll::Ready(Err(e)) => {
+          this.buf.truncate(filled);
+          if e.kind() != io::ErrorKind::Interrupted {
+            return Poll::Ready(Some(Err(e)));
+          }
+        }
+        Poll::Pending => {
+          this.buf.truncate(filled);
+          return Poll::Pending;
+        }
+      }
+    }
+  }
+}
+
+/// Controls when [`TransformableSink`] flushes the underlying writer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FlushPolicy {
+  /// Buffered items are written out when the buffer exceeds the backpressure boundary,
+  /// and the underlying writer is flushed only when the sink is flushed or closed.
+  #[default]
+  Buffered,
+  /// The underlying writer is flushed after every item, before the next item is accepted.
+  EveryItem,
+}
+
+/// A [`Sink`] which encodes [`Transformable`] values into an [`AsyncWrite`].
+///
+/// Every item is written as the length of its encoding, as a 4 bytes big-endian integer,
+/// followed by the encoding, which [`TransformableStream`] reads back. Items whose encoding is
+/// longer than `u32::MAX` bytes are rejected with an [`io::ErrorKind::InvalidInput`] error.
+///
+/// The sink applies backpressure through [`Sink::poll_ready`], which does not accept new items
+/// until the buffered bytes are written below the backpressure boundary.
+#[derive(Debug)]
+pub struct TransformableSink<W, T> {
+  writer: W,
+  buf: Vec<u8>,
+  written: usize,
+  backpressure_boundary: usize,
+  flush_policy: FlushPolicy,
+  _marker: PhantomData<fn(T)>,
+}
+
+impl<W, T> TransformableSink<W, T> {
+  /// Creates a new sink which encodes values into the given writer.
+  #[inline]
+  pub fn new(writer: W) -> Self {
+    Self {
+      writer,
+      buf: Vec::new(),
+      written: 0,
+      backpressure_boundary: DEFAULT_BACKPRESSURE_BOUNDARY,
+      flush_policy: FlushPolicy::default(),
+      _marker: PhantomData,
+    }
+  }
+
+  /// Sets the number of buffered bytes above which the sink stops accepting new items
+  /// until the buffer is written out.
+  ///
+  /// Default is 8 KiB.
+  #[inline]
+  pub fn with_backpressure_boundary(mut self, boundary: usize) -> Self {
+    self.backpressure_boundary = boundary;
+    self
+  }
+
+  /// Sets the [`FlushPolicy`] of the sink.
+  ///
+  /// Default is [`FlushPolicy::Buffered`].
+  #[inline]
+  pub fn with_flush_policy(mut self, policy: FlushPolicy) -> Self {
+    self.flush_policy = policy;
+    self
+  }
+
+  /// Returns a reference to the inner writer.
+  #[inline]
+  pub const fn get_ref(&self) -> &W {
+    &self.writer
+  }
+
+  /// Returns a mutable reference to the inner writer.
+  #[inline]
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.writer
+  }
+
+  /// Returns the number of encoded bytes which have not been written to the writer yet.
+  #[inline]
+  pub fn buffered(&self) -> usize {
+    self.buf.len() - self.written
+  }
+
+  /// Consumes the sink, returning the inner writer.
+  ///
+  /// Buffered bytes which have not been written to the writer yet are lost.
+  #[inline]
+  pub fn into_inner(self) -> W {
+    self.writer
+  }
+
+  /// Writes the buffered bytes until fewer than `target` of them are left.
+  fn poll_write_buf(&mut self, cx: &mut Context<'_>, target: usize) -> Poll<io::Result<()>>
+  where
+    W: AsyncWrite + Unpin,
+  {
+    while self.buffered() > target {
+      match Pin::new(&mut self.writer).poll_write(cx, &self.buf[self.written..]) {
+        Poll::Ready(Ok(0)) => {
+          return Poll::Ready(Err(io::Error::new(
+            io::ErrorKind::WriteZero,
+            "failed to write frame to the writer",
+          )))
+        }
+        Poll::Ready(Ok(n)) => self.written += n,
+        Poll::Ready(Err(e)) if e.kind() == io::ErrorKind::Interrupted => {}
+        Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+        Poll::Pending => return Poll::Pending,
+      }
+    }
+
+    if self.written == self.buf.len() {
+      self.buf.clear();
+      self.written = 0;
+    }
+    Poll::Ready(Ok(()))
+  }
+}
+
+impl<W, T> Sink<T> for TransformableSink<W, T>
+where
+  W: AsyncWrite + Unpin,
+  T: Transformable,
+{
+  type Error = io::Error;
+
+  fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    let this = self.get_mut();
+    match this.flush_policy {
+      FlushPolicy::Buffered => {
+        if this.buffered() < this.backpressure_boundary {
+          return Poll::Ready(Ok(()));
+        }
+        this.poll_write_buf(cx, this.backpressure_boundary.saturating_sub(1))
+      }
+      FlushPolicy::EveryItem => Pin::new(this).poll_flush(cx),
+    }
+  }
+
+  fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+    let this = self.get_mut();
+    let encoded_len = item.encoded_len();
+    if encoded_len > u32::MAX as usize {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "item is too large to be framed",
+      ));
+    }
+
+    let start = this.buf.len();
+    let payload = start + MESSAGE_SIZE_LEN;
+    this.buf.resize(payload + encoded_len, 0);
+    match item.encode(&mut this.buf[payload..]) {
+      Ok(len) => {
+        NetworkEndian::write_u32(&mut this.buf[start..payload], len as u32);
+        this.buf.truncate(payload + len);
+        Ok(())
+      }
+      Err(e) => {
+        this.buf.truncate(start);
+        Err(invalid_data(e))
+      }
+    }
+  }
+
+  fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    let this = self.get_mut();
+    futures_util::ready!(this.poll_write_buf(cx, 0))?;
+    Pin::new(&mut this.writer).poll_flush(cx)
+  }
+
+  fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    let this = self.get_mut();
+    futures_util::ready!(this.poll_write_buf(cx, 0))?;
+    Pin::new(&mut this.writer).poll_close(cx)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use futures_util::{SinkExt, StreamExt};
+  use std::string::String;
+
+  /// Returns the frames of the given values, as the sink writes them.
+  async fn frames<T: Transformable>(vals: impl IntoIterator<Item = T>) -> Vec<u8> {
+    let mut sink = TransformableSink::new(Vec::new()).with_backpressure_boundary(16);
+    for val in vals {
+      sink.feed(val).await.unwrap();
+    }
+    sink.close().await.unwrap();
+    sink.into_inner()
+  }
+
+  async fn round_trip<T: Transformable + Clone + PartialEq + core::fmt::Debug>(vals: Vec<T>) {
+    let buf = frames(vals.clone()).await;
+    let stream = TransformableStream::<_, T>::new(buf.as_slice()).with_read_chunk(7);
+    let decoded = stream.map(Result::unwrap).collect::<Vec<_>>().await;
+    assert_eq!(decoded, vals);
+  }
+
+  #[tokio::test]
+  async fn test_sink_stream() {
+    round_trip((0..100u64).collect()).await;
+    round_trip(
+      (0..100)
+        .map(|i| std::format!("message {i}"))
+        .collect::<Vec<String>>(),
+    )
+    .await;
+    round_trip(
+      (0..100u8)
+        .map(|i| std::vec![i; i as usize])
+        .collect::<Vec<Vec<u8>>>(),
+    )
+    .await;
+
+    // every item is preceded by the length of its encoding
+    let buf = frames([1u64]).await;
+    assert_eq!(buf, [0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 1]);
+  }
+
+  #[tokio::test]
+  async fn test_stream_truncated_frame() {
+    let mut sink = TransformableSink::new(Vec::new()).with_flush_policy(FlushPolicy::EveryItem);
+    sink.send(String::from("first")).await.unwrap();
+    sink.send(String::from("second")).await.unwrap();
+    let buf = sink.into_inner();
+
+    let mut stream = TransformableStream::<_, String>::new(&buf[..buf.len() - 1]);
+    assert_eq!(stream.next().await.unwrap().unwrap(), "first");
+    let err = stream.next().await.unwrap().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    assert!(stream.next().await.is_none());
+
+    let mut stream = TransformableStream::<_, String>::new(buf.as_slice());
+    assert_eq!(stream.next().await.unwrap().unwrap(), "first");
+    assert_eq!(stream.next().await.unwrap().unwrap(), "second");
+    assert!(stream.next().await.is_none());
+  }
+
+  #[tokio::test]
+  async fn test_stream_invalid_frame() {
+    // a frame which is too short for the value, then a valid one
+    let mut buf = std::vec![0, 0, 0, 2, 0, 0];
+    buf.extend(frames([7u64]).await);
+
+    let mut stream = TransformableStream::<_, u64>::new(buf.as_slice());
+    let err = stream.next().await.unwrap().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(stream.next().await.unwrap().unwrap(), 7);
+    assert!(stream.next().await.is_none());
+
+    // the next frame can not be found after a frame which is too large
+    let buf = frames([7u64, 8]).await;
+    let mut stream = TransformableStream::<_, u64>::new(buf.as_slice())
+      .with_limits(DecodeLimits::default().with_max_message_size(3));
+    let err = stream.next().await.unwrap().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(matches!(
+      err
+        .into_inner()
+        .unwrap()
+        .downcast::<LimitError>()
+        .as_deref(),
+      Ok(LimitError::MessageTooLarge { limit: 3 })
+    ));
+    assert!(stream.next().await.is_none());
+  }
+
+  #[tokio::test]
+  async fn test_stream_strict() {
+    // a frame of 9 bytes, but the value consumes 8 of them
+    let mut buf = std::vec![0, 0, 0, 9];
+    buf.extend(Transformable::encode_to_vec(&42u64).unwrap());
+    buf.push(0);
+
+    let mut stream = TransformableStream::<_, u64>::new(buf.as_slice());
+    assert_eq!(stream.next().await.unwrap().unwrap(), 42);
+
+    let mut stream = TransformableStream::<_, u64>::new(buf.as_slice()).with_strict(true);
+    let err = stream.next().await.unwrap().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(
+      *err
+        .into_inner()
+        .unwrap()
+        .downcast::<TrailingBytes>()
+        .unwrap(),
+      TrailingBytes {
+        consumed: 8,
+        total: 9
+      }
+    );
+  }
+}
@@ -11,6 +11,34 @@ pub use string::*;
 #[cfg(any(feature = "alloc", feature = "std"))]
 mod vec;
 
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod boxed;
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use boxed::*;
+
+mod checksummed;
+pub use checksummed::*;
+
+#[cfg(any(feature = "lz4", feature = "snappy", feature = "zstd"))]
+mod compressed;
+#[cfg(any(feature = "lz4", feature = "snappy", feature = "zstd"))]
+pub use compressed::*;
+
+#[cfg(feature = "hmac")]
+mod authenticated;
+#[cfg(feature = "hmac")]
+pub use authenticated::*;
+
+#[cfg(feature = "aead")]
+mod sealed;
+#[cfg(feature = "aead")]
+pub use sealed::*;
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod prefixed;
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use prefixed::*;
+
 #[cfg(feature = "std")]
 mod net;
 #[cfg(feature = "std")]
@@ -22,6 +50,15 @@ pub use time::*;
 mod numbers;
 pub use numbers::*;
 
+mod varint;
+pub use varint::*;
+
+mod versioned;
+pub use versioned::*;
+
+mod byte_order;
+pub use byte_order::*;
+
 #[cfg(feature = "smallvec")]
 mod smallvec;
 
@@ -91,6 +128,37 @@ pub enum BytesTransformError {
   /// Returned when the bytes are corrupted.
   #[cfg_attr(feature = "std", error("not enough bytes to decode"))]
   NotEnoughBytes,
+  /// Returned when a [`DecodeLimits`] is exceeded.
+  #[cfg_attr(feature = "std", error("{0}"))]
+  Limit(#[cfg_attr(feature = "std", from)] LimitError),
+  /// Returned when the memory for the decoded value can not be allocated.
+  #[cfg_attr(feature = "std", error("failed to allocate {requested} bytes"))]
+  AllocationFailed {
+    /// The number of bytes which failed to be allocated.
+    requested: usize,
+  },
+}
+
+impl crate::DecodeContext for BytesTransformError {}
+
+impl From<BytesTransformError> for TransformError {
+  fn from(err: BytesTransformError) -> Self {
+    match err {
+      BytesTransformError::EncodeBufferTooSmall => Self::from_kind(ErrorKind::BufferTooSmall),
+      BytesTransformError::NotEnoughBytes => Self::from_kind(ErrorKind::UnexpectedEof),
+      BytesTransformError::Limit(e) => e.into(),
+      err @ BytesTransformError::AllocationFailed { .. } => {
+        Self::wrap(ErrorKind::LimitExceeded, err)
+      }
+    }
+  }
+}
+
+#[cfg(not(feature = "std"))]
+impl core::convert::From<LimitError> for BytesTransformError {
+  fn from(err: LimitError) -> Self {
+    Self::Limit(err)
+  }
 }
 
 #[cfg(not(feature = "std"))]
@@ -102,6 +170,8 @@ impl core::fmt::Display for BytesTransformError {
         "buffer is too small, use `Transformable::encoded_len` to pre-allocate a buffer with enough space"
       ),
       Self::NotEnoughBytes => write!(f, "not enough bytes to decode"),
+      Self::Limit(val) => write!(f, "{val}"),
+      Self::AllocationFailed { requested } => write!(f, "failed to allocate {requested} bytes"),
     }
   }
 }
@@ -109,47 +179,88 @@ impl core::fmt::Display for BytesTransformError {
 #[cfg(all(feature = "std", feature = "async"))]
 async fn decode_bytes_from_async<R: futures_util::io::AsyncRead + Unpin>(
   src: &mut R,
+) -> std::io::Result<(usize, Vec<u8>)> {
+  decode_bytes_from_async_limited(src, &mut DecodeBudget::unlimited()).await
+}
+
+#[cfg(all(feature = "std", feature = "async"))]
+async fn decode_bytes_from_async_limited<R: futures_util::io::AsyncRead + Unpin>(
+  src: &mut R,
+  budget: &mut DecodeBudget,
 ) -> std::io::Result<(usize, Vec<u8>)> {
   use futures_util::io::AsyncReadExt;
 
   let mut len_buf = [0u8; MESSAGE_SIZE_LEN];
   src.read_exact(&mut len_buf).await?;
   let len = u32::from_network_endian(&len_buf) as usize;
-  let mut buf = vec![0u8; len];
-  src
-    .read_exact(&mut buf)
+  budget
+    .charge_bytes(MESSAGE_SIZE_LEN, len)
+    .map_err(crate::utils::invalid_data)?;
+  let mut buf = Vec::new();
+  crate::utils::read_exact_to_vec_async(src, &mut buf, len)
     .await
     .map(|_| (len + MESSAGE_SIZE_LEN, buf))
 }
 
 #[cfg(feature = "std")]
 fn decode_bytes_from<R: std::io::Read>(src: &mut R) -> std::io::Result<(usize, Vec<u8>)> {
+  decode_bytes_from_limited(src, &mut DecodeBudget::unlimited())
+}
+
+#[cfg(feature = "std")]
+fn decode_bytes_from_limited<R: std::io::Read>(
+  src: &mut R,
+  budget: &mut DecodeBudget,
+) -> std::io::Result<(usize, Vec<u8>)> {
   let mut len_buf = [0u8; MESSAGE_SIZE_LEN];
   src.read_exact(&mut len_buf)?;
   let len = u32::from_network_endian(&len_buf) as usize;
-  let mut buf = vec![0u8; len];
-  src
-    .read_exact(&mut buf)
-    .map(|_| (MESSAGE_SIZE_LEN + len, buf))
+  budget
+    .charge_bytes(MESSAGE_SIZE_LEN, len)
+    .map_err(crate::utils::invalid_data)?;
+  let mut buf = Vec::new();
+  crate::utils::read_exact_to_vec(src, &mut buf, len).map(|_| (MESSAGE_SIZE_LEN + len, buf))
+}
+
+/// Checks the length prefix of the encoded bytes in `src` against the budget,
+/// before the bytes are copied out of it.
+#[cfg(any(feature = "alloc", feature = "std"))]
+fn charge_encoded_bytes(src: &[u8], budget: &mut DecodeBudget) -> Result<(), LimitError> {
+  if src.len() < MESSAGE_SIZE_LEN {
+    return Ok(());
+  }
+
+  let len = u32::from_network_endian(&src[..MESSAGE_SIZE_LEN]) as usize;
+  budget.charge_bytes(MESSAGE_SIZE_LEN, len)
 }
 
 #[cfg(any(feature = "alloc", feature = "std"))]
-fn decode_bytes(src: &[u8]) -> Result<(usize, Vec<u8>), ()> {
+fn decode_bytes(src: &[u8]) -> Result<(usize, Vec<u8>), BytesTransformError> {
   let len = src.len();
   if len < MESSAGE_SIZE_LEN {
-    return Err(());
+    return Err(BytesTransformError::NotEnoughBytes);
   }
 
   let data_len = u32::from_network_endian(&src[..MESSAGE_SIZE_LEN]) as usize;
   if data_len > len - MESSAGE_SIZE_LEN {
-    return Err(());
+    return Err(BytesTransformError::NotEnoughBytes);
   }
 
   let total_len = MESSAGE_SIZE_LEN + data_len;
-  Ok((
-    total_len,
-    src[MESSAGE_SIZE_LEN..MESSAGE_SIZE_LEN + data_len].to_vec(),
-  ))
+  try_to_vec(&src[MESSAGE_SIZE_LEN..total_len]).map(|buf| (total_len, buf))
+}
+
+/// Copies the bytes into a new vec, failing instead of aborting if the memory can not be allocated.
+#[cfg(any(feature = "alloc", feature = "std"))]
+fn try_to_vec(src: &[u8]) -> Result<Vec<u8>, BytesTransformError> {
+  let mut buf = Vec::new();
+  buf
+    .try_reserve_exact(src.len())
+    .map_err(|_| BytesTransformError::AllocationFailed {
+      requested: src.len(),
+    })?;
+  buf.extend_from_slice(src);
+  Ok(buf)
 }
 
 #[cfg(any(feature = "alloc", feature = "std"))]
@@ -175,10 +286,9 @@ fn encode_bytes_to<W: std::io::Write>(src: &[u8], dst: &mut W) -> std::io::Resul
       .write_all(&buf[..MESSAGE_SIZE_LEN + len])
       .map(|_| MESSAGE_SIZE_LEN + len)
   } else {
-    let mut buf = std::vec![0; MESSAGE_SIZE_LEN + len];
-    NetworkEndian::write_u32(&mut buf[..MESSAGE_SIZE_LEN], len as u32);
-    buf[MESSAGE_SIZE_LEN..].copy_from_slice(src);
-    dst.write_all(&buf).map(|_| MESSAGE_SIZE_LEN + len)
+    let mut header = [0u8; MESSAGE_SIZE_LEN];
+    NetworkEndian::write_u32(&mut header, len as u32);
+    crate::vectored::write_all_vectored(dst, &[&header, src]).map(|_| MESSAGE_SIZE_LEN + len)
   }
 }
 
@@ -199,13 +309,23 @@ async fn encode_bytes_to_async<W: futures_util::io::AsyncWrite + Unpin>(
       .await
       .map(|_| MESSAGE_SIZE_LEN + len)
   } else {
-    let mut buf = std::vec![0; MESSAGE_SIZE_LEN + len];
-    NetworkEndian::write_u32(&mut buf[..MESSAGE_SIZE_LEN], len as u32);
-    buf[MESSAGE_SIZE_LEN..].copy_from_slice(src);
-    dst.write_all(&buf).await.map(|_| MESSAGE_SIZE_LEN + len)
+    let mut header = [0u8; MESSAGE_SIZE_LEN];
+    NetworkEndian::write_u32(&mut header, len as u32);
+    crate::vectored::write_all_vectored_async(dst, &[&header, src])
+      .await
+      .map(|_| MESSAGE_SIZE_LEN + len)
   }
 }
 
+#[cfg(feature = "std")]
+fn bytes_as_io_slices<'a>(src: &'a [u8], slices: &mut GatherList<'a>) -> bool {
+  let mut header = [0u8; MESSAGE_SIZE_LEN];
+  NetworkEndian::write_u32(&mut header, src.len() as u32);
+  slices.push_copied(&header);
+  slices.push(src);
+  true
+}
+
 #[cfg(any(feature = "alloc", feature = "std"))]
 fn encoded_bytes_len(src: &[u8]) -> usize {
   MESSAGE_SIZE_LEN + src.len()
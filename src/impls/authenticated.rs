@@ -0,0 +1,272 @@
+use super::*;
+
+use hmac::Mac;
+
+use crate::keyring::{HmacSha256, Keyring};
+
+/// The length of the HMAC-SHA256 tag.
+const TAG_LEN: usize = 32;
+
+/// The length of the trailer: the key id and the tag.
+const TRAILER_LEN: usize = 1 + TAG_LEN;
+
+/// The error type for errors that get returned when encoding or verifying [`Authenticated`] fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthenticatedTransformError<E> {
+  /// Returned when the buffer is too small to encode.
+  EncodeBufferTooSmall,
+  /// Returned when the buffer does not contain the tag.
+  NotEnoughBytes,
+  /// Returned when encoding or decoding the value fails.
+  Transform(E),
+  /// Returned when the value is authenticated with a key which is not in the keyring.
+  UnknownKey(u8),
+  /// Returned when the value, or its key id, has been tampered with, or is authenticated with a
+  /// different key with the same id.
+  AuthenticationFailed,
+}
+
+forward_decode_context!(AuthenticatedTransformError);
+
+impl<E: Into<TransformError>> From<AuthenticatedTransformError<E>> for TransformError {
+  fn from(err: AuthenticatedTransformError<E>) -> Self {
+    match err {
+      AuthenticatedTransformError::EncodeBufferTooSmall => {
+        Self::from_kind(ErrorKind::BufferTooSmall)
+      }
+      AuthenticatedTransformError::NotEnoughBytes => Self::from_kind(ErrorKind::UnexpectedEof),
+      AuthenticatedTransformError::Transform(e) => e.into(),
+      AuthenticatedTransformError::UnknownKey(id) => Self::wrap(
+        ErrorKind::InvalidValue,
+        AuthenticatedTransformError::<core::convert::Infallible>::UnknownKey(id),
+      ),
+      AuthenticatedTransformError::AuthenticationFailed => Self::wrap(
+        ErrorKind::InvalidValue,
+        AuthenticatedTransformError::<core::convert::Infallible>::AuthenticationFailed,
+      ),
+    }
+  }
+}
+
+impl<E: core::fmt::Display> core::fmt::Display for AuthenticatedTransformError<E> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::EncodeBufferTooSmall => write!(
+        f,
+        "buffer is too small, use `TransformableWith::encoded_len_with` to pre-allocate a buffer with enough space"
+      ),
+      Self::NotEnoughBytes => write!(f, "not enough bytes to decode"),
+      Self::Transform(e) => write!(f, "{e}"),
+      Self::UnknownKey(id) => write!(f, "value is authenticated with the unknown key {id}"),
+      Self::AuthenticationFailed => write!(f, "authentication failed"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for AuthenticatedTransformError<E> {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Transform(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+/// A value followed by an HMAC-SHA256 tag of its encoding, which is verified on decode.
+///
+/// Unlike `Sealed`, the value is not encrypted: the encoding starts with the
+/// plain encoding of `T`, so tools which do not know the keys can still read it. It is followed
+/// by the id of the key and the 32 bytes tag, which covers both the value and the key id.
+///
+/// The keys come from the [`Keyring`] the value is encoded and decoded with, so `Authenticated`
+/// is [`TransformableWith`] the keyring, rather than [`Transformable`](crate::Transformable).
+/// Values are authenticated with the [primary](Keyring::primary) key, and verified with whatever
+/// key of the keyring has the id sent along, which allows to rotate keys. Tags are compared in
+/// constant time.
+///
+/// `T` is decoded before the tag is verified, as its length is only known once it is decoded,
+/// so its decoder must not trust the bytes it decodes, as is the case for plain `T` values.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Authenticated<T> {
+  value: T,
+}
+
+impl<T> Authenticated<T> {
+  /// Wraps the given value.
+  #[inline]
+  pub const fn new(value: T) -> Self {
+    Self { value }
+  }
+
+  /// Returns a reference to the value.
+  #[inline]
+  pub const fn get(&self) -> &T {
+    &self.value
+  }
+
+  /// Consumes the wrapper, returning the value.
+  #[inline]
+  pub fn into_inner(self) -> T {
+    self.value
+  }
+}
+
+impl<T> From<T> for Authenticated<T> {
+  #[inline]
+  fn from(value: T) -> Self {
+    Self::new(value)
+  }
+}
+
+impl<T> core::ops::Deref for Authenticated<T> {
+  type Target = T;
+
+  #[inline]
+  fn deref(&self) -> &T {
+    &self.value
+  }
+}
+
+impl<T, K> TransformableWith<K> for Authenticated<T>
+where
+  K: Keyring<Key = HmacSha256> + ?Sized,
+  T: TransformableWith<K>,
+{
+  type Error = AuthenticatedTransformError<T::Error>;
+
+  fn encode_with(&self, ctx: &K, dst: &mut [u8]) -> Result<usize, Self::Error> {
+    if dst.len() < self.encoded_len_with(ctx) {
+      return Err(Self::Error::EncodeBufferTooSmall);
+    }
+
+    let (id, key) = ctx.primary();
+    let len = self
+      .value
+      .encode_with(ctx, dst)
+      .map_err(AuthenticatedTransformError::Transform)?;
+    dst[len] = id;
+    let mut mac = key.clone();
+    mac.update(&dst[..len + 1]);
+    dst[len + 1..len + TRAILER_LEN].copy_from_slice(&mac.finalize().into_bytes());
+    Ok(len + TRAILER_LEN)
+  }
+
+  fn encoded_len_with(&self, ctx: &K) -> usize {
+    self.value.encoded_len_with(ctx) + TRAILER_LEN
+  }
+
+  fn decode_with(ctx: &K, src: &[u8]) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized,
+  {
+    let (readed, val) = T::decode_with(ctx, src).map_err(AuthenticatedTransformError::Transform)?;
+    let trailer = src
+      .get(readed..readed + TRAILER_LEN)
+      .ok_or(Self::Error::NotEnoughBytes)?;
+    let id = trailer[0];
+    let mut mac = ctx.get(id).ok_or(Self::Error::UnknownKey(id))?.clone();
+    mac.update(&src[..readed + 1]);
+    mac
+      .verify_slice(&trailer[1..])
+      .map_err(|_| Self::Error::AuthenticationFailed)?;
+    Ok((readed + TRAILER_LEN, Self::new(val)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::keyring::Keys;
+  use std::string::String;
+
+  fn key(secret: &[u8]) -> HmacSha256 {
+    HmacSha256::new_from_slice(secret).unwrap()
+  }
+
+  #[test]
+  fn test_authenticated() {
+    let keyring = Keys::new(1, key(b"secret"));
+    let val = Authenticated::new(String::from("hello world"));
+    let encoded = val.encode_to_vec_with(&keyring).unwrap();
+    assert_eq!(encoded.len(), 4 + 11 + 1 + 32);
+    assert_eq!(encoded.len(), val.encoded_len_with(&keyring));
+
+    // the payload stays readable
+    assert_eq!(
+      <String as Transformable>::decode(&encoded).unwrap(),
+      (15, String::from("hello world"))
+    );
+    assert_eq!(encoded[15], 1);
+    assert_eq!(
+      Authenticated::<String>::decode_with(&keyring, &encoded).unwrap(),
+      (encoded.len(), val)
+    );
+
+    assert!(matches!(
+      Authenticated::<String>::decode_with(&keyring, &encoded[..encoded.len() - 1]),
+      Err(AuthenticatedTransformError::NotEnoughBytes)
+    ));
+  }
+
+  #[test]
+  fn test_tampered() {
+    let keyring = Keys::new(1, key(b"secret"));
+    let encoded = Authenticated::new(String::from("hello world"))
+      .encode_to_vec_with(&keyring)
+      .unwrap();
+
+    // the value and the tag
+    for idx in [6, encoded.len() - 1] {
+      let mut tampered = encoded.clone();
+      tampered[idx] ^= 0x01;
+      assert!(matches!(
+        Authenticated::<String>::decode_with(&keyring, &tampered),
+        Err(AuthenticatedTransformError::AuthenticationFailed)
+      ));
+    }
+
+    // the key id
+    let mut tampered = encoded.clone();
+    tampered[15] = 2;
+    assert!(matches!(
+      Authenticated::<String>::decode_with(&keyring, &tampered),
+      Err(AuthenticatedTransformError::UnknownKey(2))
+    ));
+
+    // a different key with the same id
+    let other = Keys::new(1, key(b"other"));
+    assert!(matches!(
+      Authenticated::<String>::decode_with(&other, &encoded),
+      Err(AuthenticatedTransformError::AuthenticationFailed)
+    ));
+  }
+
+  #[test]
+  fn test_rotation() {
+    let mut keyring = Keys::new(1, key(b"old"));
+    let old = Authenticated::new(7u64)
+      .encode_to_vec_with(&keyring)
+      .unwrap();
+
+    keyring.rotate(2, key(b"new"));
+    let new = Authenticated::new(8u64)
+      .encode_to_vec_with(&keyring)
+      .unwrap();
+    assert_eq!(new[8], 2);
+    assert_eq!(
+      Authenticated::<u64>::decode_with(&keyring, &old).unwrap().1,
+      Authenticated::new(7)
+    );
+
+    keyring.remove(1);
+    assert!(matches!(
+      Authenticated::<u64>::decode_with(&keyring, &old),
+      Err(AuthenticatedTransformError::UnknownKey(1))
+    ));
+    assert_eq!(
+      Authenticated::<u64>::decode_with(&keyring, &new).unwrap().1,
+      Authenticated::new(8)
+    );
+  }
+}
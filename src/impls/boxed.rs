@@ -0,0 +1,505 @@
+use super::*;
+
+#[cfg(not(feature = "std"))]
+use ::alloc::boxed::Box;
+
+#[cfg(feature = "std")]
+use crate::utils::invalid_data;
+
+/// The error type for errors that get returned when encoding or decoding a [`Box`] fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoxTransformError<E> {
+  /// Returned when the box is nested deeper than the max depth.
+  Limit(LimitError),
+  /// Returned when encoding or decoding the value fails.
+  Transform(E),
+}
+
+forward_decode_context!(BoxTransformError);
+
+impl<E> From<LimitError> for BoxTransformError<E> {
+  #[inline]
+  fn from(err: LimitError) -> Self {
+    Self::Limit(err)
+  }
+}
+
+impl<E: Into<TransformError>> From<BoxTransformError<E>> for TransformError {
+  fn from(err: BoxTransformError<E>) -> Self {
+    match err {
+      BoxTransformError::Limit(e) => e.into(),
+      BoxTransformError::Transform(e) => e.into(),
+    }
+  }
+}
+
+impl<E: core::fmt::Display> core::fmt::Display for BoxTransformError<E> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::Limit(e) => write!(f, "{e}"),
+      Self::Transform(e) => write!(f, "{e}"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for BoxTransformError<E> {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Limit(e) => Some(e),
+      Self::Transform(e) => Some(e),
+    }
+  }
+}
+
+#[cfg(feature = "embedded-io")]
+fn embedded_err<IO, E>(err: EmbeddedIoError<IO, E>) -> EmbeddedIoError<IO, BoxTransformError<E>> {
+  match err {
+    EmbeddedIoError::Io(e) => EmbeddedIoError::Io(e),
+    EmbeddedIoError::UnexpectedEof => EmbeddedIoError::UnexpectedEof,
+    EmbeddedIoError::MessageTooLarge(len) => EmbeddedIoError::MessageTooLarge(len),
+    EmbeddedIoError::AllocationFailed { requested } => {
+      EmbeddedIoError::AllocationFailed { requested }
+    }
+    EmbeddedIoError::Limit(e) => EmbeddedIoError::Limit(e),
+    EmbeddedIoError::Transform(e) => EmbeddedIoError::Transform(BoxTransformError::Transform(e)),
+  }
+}
+
+/// Boxed values are encoded the same way as the value itself.
+///
+/// Decoding a box enters a new level of nesting in the [`DecodeBudget`], so recursive types,
+/// e.g. trees, can not blow the stack when decoding a crafted message. The methods which do not
+/// take a budget decode with the default [`DecodeLimits`], and fail with
+/// [`LimitError::DepthLimitExceeded`] once the boxes are nested deeper than
+/// [`DEFAULT_MAX_DEPTH`](DecodeLimits::DEFAULT_MAX_DEPTH).
+impl<T: Transformable> Transformable for Box<T> {
+  type Error = BoxTransformError<T::Error>;
+
+  fn encode(&self, dst: &mut [u8]) -> Result<usize, Self::Error> {
+    T::encode(self, dst).map_err(BoxTransformError::Transform)
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn encode_to_writer<W: std::io::Write>(&self, dst: &mut W) -> std::io::Result<usize> {
+    T::encode_to_writer(self, dst)
+  }
+
+  #[cfg(feature = "async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+  async fn encode_to_async_writer<W: futures_util::io::AsyncWrite + Send + Unpin>(
+    &self,
+    dst: &mut W,
+  ) -> std::io::Result<usize> {
+    T::encode_to_async_writer(self, dst).await
+  }
+
+  fn encoded_len(&self) -> usize {
+    T::encoded_len(self)
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn as_io_slices<'a>(&'a self, slices: &mut GatherList<'a>) -> bool {
+    T::as_io_slices(self, slices)
+  }
+
+  fn decode(src: &[u8]) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized,
+  {
+    <Self as Transformable>::decode_limited(src, &mut DecodeBudget::default())
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn is_incomplete(err: &Self::Error) -> bool
+  where
+    Self: Sized,
+  {
+    match err {
+      BoxTransformError::Limit(_) => false,
+      BoxTransformError::Transform(e) => T::is_incomplete(e),
+    }
+  }
+
+  fn decode_limited(src: &[u8], budget: &mut DecodeBudget) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized,
+  {
+    budget.enter()?;
+    let res = T::decode_limited(src, budget);
+    budget.exit();
+    res
+      .map(|(readed, val)| (readed, Box::new(val)))
+      .map_err(BoxTransformError::Transform)
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn decode_from_reader<R: std::io::Read>(src: &mut R) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    <Self as Transformable>::decode_from_reader_limited(src, &mut DecodeBudget::default())
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn decode_from_reader_limited<R: std::io::Read>(
+    src: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    budget.enter().map_err(invalid_data)?;
+    let res = T::decode_from_reader_limited(src, budget);
+    budget.exit();
+    res.map(|(readed, val)| (readed, Box::new(val)))
+  }
+
+  #[cfg(feature = "async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+  async fn decode_from_async_reader<R: futures_util::io::AsyncRead + Send + Unpin>(
+    src: &mut R,
+  ) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    <Self as Transformable>::decode_from_async_reader_limited(src, &mut DecodeBudget::default())
+      .await
+  }
+
+  #[cfg(feature = "async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+  async fn decode_from_async_reader_limited<R: futures_util::io::AsyncRead + Send + Unpin>(
+    src: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    budget.enter().map_err(invalid_data)?;
+    let res = T::decode_from_async_reader_limited(src, budget).await;
+    budget.exit();
+    res.map(|(readed, val)| (readed, Box::new(val)))
+  }
+
+  #[cfg(feature = "owned-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+  async fn decode_from_owned_reader<R: crate::OwnedRead>(
+    reader: &mut R,
+    buf: Vec<u8>,
+  ) -> (std::io::Result<(usize, Self)>, Vec<u8>)
+  where
+    Self: Sized,
+  {
+    <Self as Transformable>::decode_from_owned_reader_limited(
+      reader,
+      buf,
+      &mut DecodeBudget::default(),
+    )
+    .await
+  }
+
+  #[cfg(feature = "owned-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+  async fn decode_from_owned_reader_limited<R: crate::OwnedRead>(
+    reader: &mut R,
+    buf: Vec<u8>,
+    budget: &mut DecodeBudget,
+  ) -> (std::io::Result<(usize, Self)>, Vec<u8>)
+  where
+    Self: Sized,
+  {
+    if let Err(e) = budget.enter() {
+      return (Err(invalid_data(e)), buf);
+    }
+    let (res, buf) = T::decode_from_owned_reader_limited(reader, buf, budget).await;
+    budget.exit();
+    (res.map(|(readed, val)| (readed, Box::new(val))), buf)
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn encode_to_embedded_writer<W: embedded_io::Write>(
+    &self,
+    dst: &mut W,
+  ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+    T::encode_to_embedded_writer(self, dst).map_err(embedded_err)
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn encode_to_embedded_async_writer<W: embedded_io_async::Write>(
+    &self,
+    dst: &mut W,
+  ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+    T::encode_to_embedded_async_writer(self, dst)
+      .await
+      .map_err(embedded_err)
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn decode_from_embedded_reader<R: embedded_io::Read>(
+    src: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    <Self as Transformable>::decode_from_embedded_reader_limited(src, &mut DecodeBudget::default())
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn decode_from_embedded_reader_limited<R: embedded_io::Read>(
+    src: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    budget
+      .enter()
+      .map_err(|e| EmbeddedIoError::Transform(e.into()))?;
+    let res = T::decode_from_embedded_reader_limited(src, budget);
+    budget.exit();
+    res
+      .map(|(readed, val)| (readed, Box::new(val)))
+      .map_err(embedded_err)
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn decode_from_embedded_async_reader<R: embedded_io_async::Read>(
+    src: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    <Self as Transformable>::decode_from_embedded_async_reader_limited(
+      src,
+      &mut DecodeBudget::default(),
+    )
+    .await
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn decode_from_embedded_async_reader_limited<R: embedded_io_async::Read>(
+    src: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    budget
+      .enter()
+      .map_err(|e| EmbeddedIoError::Transform(e.into()))?;
+    let res = T::decode_from_embedded_async_reader_limited(src, budget).await;
+    budget.exit();
+    res
+      .map(|(readed, val)| (readed, Box::new(val)))
+      .map_err(embedded_err)
+  }
+}
+
+/// [`Box`] is a fundamental type, so it can not pass a context down to the value without
+/// conflicting with the [`ContextFree`] impls of other crates. A box of a [`ContextFree`] value is
+/// [`ContextFree`] too.
+impl<T: ContextFree> ContextFree for Box<T> {}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+  use super::*;
+
+  test_transformable!(Box<std::string::String> => test_box_string_transformable(Box::new(std::string::String::from("hello world"))));
+  test_transformable!(Box<Box<Vec<u8>>> => test_box_box_vec_transformable(Box::new(Box::new(std::vec![1, 2, 3]))));
+  test_transformable!(Box<u32> => test_box_u32_transformable(Box::new(42)));
+  test_transformable!(Box<core::time::Duration> => test_box_duration_transformable(Box::new(core::time::Duration::from_millis(42))));
+
+  /// A recursive type, encoded as a `1` byte for every link, followed by a `0` byte.
+  #[derive(Debug, PartialEq, Eq)]
+  struct Chain(Option<Box<Chain>>);
+
+  impl Chain {
+    fn new(len: usize) -> Self {
+      (0..len).fold(Self(None), |next, _| Self(Some(Box::new(next))))
+    }
+  }
+
+  fn flatten(err: BoxTransformError<BytesTransformError>) -> BytesTransformError {
+    match err {
+      BoxTransformError::Limit(e) => e.into(),
+      BoxTransformError::Transform(e) => e,
+    }
+  }
+
+  impl Transformable for Chain {
+    type Error = BytesTransformError;
+
+    fn encode(&self, dst: &mut [u8]) -> Result<usize, Self::Error> {
+      if dst.is_empty() {
+        return Err(Self::Error::EncodeBufferTooSmall);
+      }
+
+      match &self.0 {
+        Some(next) => {
+          dst[0] = 1;
+          Transformable::encode(next, &mut dst[1..])
+            .map(|written| written + 1)
+            .map_err(flatten)
+        }
+        None => {
+          dst[0] = 0;
+          Ok(1)
+        }
+      }
+    }
+
+    fn encoded_len(&self) -> usize {
+      1 + self.0.as_ref().map_or(0, Transformable::encoded_len)
+    }
+
+    fn decode(src: &[u8]) -> Result<(usize, Self), Self::Error> {
+      <Self as Transformable>::decode_limited(src, &mut DecodeBudget::default())
+    }
+
+    fn decode_limited(src: &[u8], budget: &mut DecodeBudget) -> Result<(usize, Self), Self::Error> {
+      match src.first() {
+        Some(0) => Ok((1, Self(None))),
+        Some(_) => <Box<Self> as Transformable>::decode_limited(&src[1..], budget)
+          .map(|(readed, next)| (readed + 1, Self(Some(next))))
+          .map_err(flatten),
+        None => Err(Self::Error::NotEnoughBytes),
+      }
+    }
+
+    fn decode_from_reader<R: std::io::Read>(src: &mut R) -> std::io::Result<(usize, Self)> {
+      <Self as Transformable>::decode_from_reader_limited(src, &mut DecodeBudget::default())
+    }
+
+    fn decode_from_reader_limited<R: std::io::Read>(
+      src: &mut R,
+      budget: &mut DecodeBudget,
+    ) -> std::io::Result<(usize, Self)> {
+      let mut tag = [0u8; 1];
+      src.read_exact(&mut tag)?;
+      match tag[0] {
+        0 => Ok((1, Self(None))),
+        _ => <Box<Self> as Transformable>::decode_from_reader_limited(src, budget)
+          .map(|(readed, next)| (readed + 1, Self(Some(next)))),
+      }
+    }
+
+    #[cfg(feature = "embedded-io")]
+    fn decode_from_embedded_reader<R: embedded_io::Read>(
+      src: &mut R,
+    ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>> {
+      <Self as Transformable>::decode_from_embedded_reader_limited(
+        src,
+        &mut DecodeBudget::default(),
+      )
+    }
+
+    #[cfg(feature = "embedded-io")]
+    fn decode_from_embedded_reader_limited<R: embedded_io::Read>(
+      src: &mut R,
+      budget: &mut DecodeBudget,
+    ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>> {
+      let mut tag = [0u8; 1];
+      src.read_exact(&mut tag)?;
+      match tag[0] {
+        0 => Ok((1, Self(None))),
+        _ => <Box<Self> as Transformable>::decode_from_embedded_reader_limited(src, budget)
+          .map(|(readed, next)| (readed + 1, Self(Some(next))))
+          .map_err(|e| match e {
+            EmbeddedIoError::Io(e) => EmbeddedIoError::Io(e),
+            EmbeddedIoError::UnexpectedEof => EmbeddedIoError::UnexpectedEof,
+            EmbeddedIoError::MessageTooLarge(len) => EmbeddedIoError::MessageTooLarge(len),
+            EmbeddedIoError::AllocationFailed { requested } => {
+              EmbeddedIoError::AllocationFailed { requested }
+            }
+            EmbeddedIoError::Limit(e) => EmbeddedIoError::Limit(e),
+            EmbeddedIoError::Transform(e) => EmbeddedIoError::Transform(flatten(e)),
+          }),
+      }
+    }
+  }
+
+  #[test]
+  fn test_depth_limit() {
+    let chain = Chain::new(DecodeLimits::DEFAULT_MAX_DEPTH);
+    let encoded = Transformable::encode_to_vec(&chain).unwrap();
+    assert_eq!(
+      <Chain as Transformable>::decode(&encoded).unwrap(),
+      (encoded.len(), chain)
+    );
+
+    // a crafted message, which would overflow the stack without a depth limit
+    let mut crafted = std::vec![1u8; 1_000_000];
+    crafted.push(0);
+    let err = <Chain as Transformable>::decode(&crafted).unwrap_err();
+    assert!(matches!(
+      err,
+      BytesTransformError::Limit(LimitError::DepthLimitExceeded {
+        limit: DecodeLimits::DEFAULT_MAX_DEPTH
+      })
+    ));
+
+    let err = <Chain as Transformable>::decode_from_reader(&mut &crafted[..]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    let mut budget = DecodeBudget::new(DecodeLimits::default().with_max_depth(3));
+    let encoded = Transformable::encode_to_vec(&Chain::new(3)).unwrap();
+    <Chain as Transformable>::decode_from_reader_limited(&mut &encoded[..], &mut budget).unwrap();
+    assert_eq!(budget.depth(), 0);
+
+    let encoded = Transformable::encode_to_vec(&Chain::new(4)).unwrap();
+    let err = <Chain as Transformable>::decode_limited(&encoded, &mut budget).unwrap_err();
+    assert!(matches!(
+      err,
+      BytesTransformError::Limit(LimitError::DepthLimitExceeded { limit: 3 })
+    ));
+    assert_eq!(budget.depth(), 0);
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[test]
+  fn test_embedded_depth_limit() {
+    let chain = Chain::new(DecodeLimits::DEFAULT_MAX_DEPTH);
+    let encoded = Transformable::encode_to_vec(&chain).unwrap();
+    let (readed, decoded) =
+      <Chain as Transformable>::decode_from_embedded_reader(&mut encoded.as_slice()).unwrap();
+    assert_eq!((readed, decoded), (encoded.len(), chain));
+
+    let mut crafted = std::vec![1u8; 1_000_000];
+    crafted.push(0);
+    let err =
+      <Chain as Transformable>::decode_from_embedded_reader(&mut crafted.as_slice()).unwrap_err();
+    assert!(matches!(
+      err,
+      EmbeddedIoError::Transform(BytesTransformError::Limit(LimitError::DepthLimitExceeded {
+        limit: DecodeLimits::DEFAULT_MAX_DEPTH
+      }))
+    ));
+
+    let mut budget = DecodeBudget::new(DecodeLimits::default().with_max_depth(3));
+    let encoded = Transformable::encode_to_vec(&Chain::new(4)).unwrap();
+    let err = <Chain as Transformable>::decode_from_embedded_reader_limited(
+      &mut encoded.as_slice(),
+      &mut budget,
+    )
+    .unwrap_err();
+    assert!(matches!(
+      err,
+      EmbeddedIoError::Transform(BytesTransformError::Limit(LimitError::DepthLimitExceeded {
+        limit: 3
+      }))
+    ));
+    assert_eq!(budget.depth(), 0);
+  }
+}
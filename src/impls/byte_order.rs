@@ -0,0 +1,445 @@
+use super::*;
+
+use core::{marker::PhantomData, time::Duration};
+
+use crate::endian::{Big, ByteOrder, Little, Native};
+#[cfg(feature = "std")]
+use crate::utils::invalid_data;
+
+/// The max encoded length of the types supported by [`Endian`].
+#[cfg(any(feature = "std", feature = "embedded-io"))]
+const MAX_ENCODED_LEN: usize = 19;
+
+/// A value encoded in the byte order `O`.
+///
+/// Numbers, [`Duration`], [`SystemTime`](std::time::SystemTime) and the port of
+/// [`SocketAddr`](std::net::SocketAddr) are encoded in big-endian byte order by default.
+/// `Endian` keeps the same layout, but encodes the multi-byte fields in the byte order `O`.
+///
+/// `Endian<T, Big>` has the same wire format as `T`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Endian<T, O = Big> {
+  value: T,
+  _order: PhantomData<O>,
+}
+
+/// A value encoded in big-endian byte order, which has the same wire format as `T`.
+pub type BigEndian<T> = Endian<T, Big>;
+
+/// A value encoded in little-endian byte order.
+pub type LittleEndian<T> = Endian<T, Little>;
+
+/// A value encoded in the byte order of the target platform.
+pub type NativeEndian<T> = Endian<T, Native>;
+
+impl<T, O> Endian<T, O> {
+  /// Wraps the given value.
+  #[inline]
+  pub const fn new(value: T) -> Self {
+    Self {
+      value,
+      _order: PhantomData,
+    }
+  }
+
+  /// Returns a reference to the value.
+  #[inline]
+  pub const fn get(&self) -> &T {
+    &self.value
+  }
+
+  /// Consumes the wrapper, returning the value.
+  #[inline]
+  pub fn into_inner(self) -> T {
+    self.value
+  }
+}
+
+impl<T, O> From<T> for Endian<T, O> {
+  #[inline]
+  fn from(value: T) -> Self {
+    Self::new(value)
+  }
+}
+
+impl<T, O> core::ops::Deref for Endian<T, O> {
+  type Target = T;
+
+  #[inline]
+  fn deref(&self) -> &T {
+    &self.value
+  }
+}
+
+/// A type which can be encoded in any byte order.
+trait EndianCodec: Transformable + Sized {
+  /// Returns the length of the encoded value which starts with the given byte.
+  #[cfg_attr(not(any(feature = "std", feature = "embedded-io")), allow(dead_code))]
+  fn frame_len(first: u8) -> Result<usize, Self::Error>;
+
+  fn encode_endian<B: byteorder::ByteOrder>(&self, dst: &mut [u8]) -> Result<usize, Self::Error>;
+
+  fn decode_endian<B: byteorder::ByteOrder>(src: &[u8]) -> Result<(usize, Self), Self::Error>;
+}
+
+macro_rules! impl_number_codec {
+  ($($ty:ident), + $(,)?) => {
+    $(
+      impl EndianCodec for $ty {
+        fn frame_len(_: u8) -> Result<usize, Self::Error> {
+          Ok(core::mem::size_of::<$ty>())
+        }
+
+        fn encode_endian<B: byteorder::ByteOrder>(&self, dst: &mut [u8]) -> Result<usize, Self::Error> {
+          const SIZE: usize = core::mem::size_of::<$ty>();
+
+          if dst.len() < SIZE {
+            return Err(Self::Error::EncodeBufferTooSmall);
+          }
+
+          paste::paste! {
+            B::[< write_ $ty >](&mut dst[..SIZE], *self);
+          }
+          Ok(SIZE)
+        }
+
+        fn decode_endian<B: byteorder::ByteOrder>(src: &[u8]) -> Result<(usize, Self), Self::Error> {
+          const SIZE: usize = core::mem::size_of::<$ty>();
+
+          if src.len() < SIZE {
+            return Err(Self::Error::NotEnoughBytes);
+          }
+
+          paste::paste! {
+            Ok((SIZE, B::[< read_ $ty >](&src[..SIZE])))
+          }
+        }
+      }
+    )*
+  };
+}
+
+impl_number_codec!(u16, u32, u64, u128, i16, i32, i64, i128);
+
+macro_rules! impl_byte_codec {
+  ($($ty:ident), + $(,)?) => {
+    $(
+      impl EndianCodec for $ty {
+        fn frame_len(_: u8) -> Result<usize, Self::Error> {
+          Ok(1)
+        }
+
+        fn encode_endian<B: byteorder::ByteOrder>(&self, dst: &mut [u8]) -> Result<usize, Self::Error> {
+          Transformable::encode(self, dst)
+        }
+
+        fn decode_endian<B: byteorder::ByteOrder>(src: &[u8]) -> Result<(usize, Self), Self::Error> {
+          <Self as Transformable>::decode(src)
+        }
+      }
+    )*
+  };
+}
+
+impl_byte_codec!(u8, i8);
+
+const DURATION_ENCODED_LEN: usize = core::mem::size_of::<u64>() + core::mem::size_of::<u32>();
+
+impl EndianCodec for Duration {
+  fn frame_len(_: u8) -> Result<usize, Self::Error> {
+    Ok(DURATION_ENCODED_LEN)
+  }
+
+  fn encode_endian<B: byteorder::ByteOrder>(&self, dst: &mut [u8]) -> Result<usize, Self::Error> {
+    if dst.len() < DURATION_ENCODED_LEN {
+      return Err(Self::Error::EncodeBufferTooSmall);
+    }
+
+    B::write_u64(&mut dst[..8], self.as_secs());
+    B::write_u32(&mut dst[8..DURATION_ENCODED_LEN], self.subsec_nanos());
+    Ok(DURATION_ENCODED_LEN)
+  }
+
+  fn decode_endian<B: byteorder::ByteOrder>(src: &[u8]) -> Result<(usize, Self), Self::Error> {
+    if src.len() < DURATION_ENCODED_LEN {
+      return Err(Self::Error::NotEnoughBytes);
+    }
+
+    let secs = B::read_u64(&src[..8]);
+    let nanos = B::read_u32(&src[8..DURATION_ENCODED_LEN]);
+    Ok((DURATION_ENCODED_LEN, Duration::new(secs, nanos)))
+  }
+}
+
+#[cfg(feature = "std")]
+impl EndianCodec for std::time::SystemTime {
+  fn frame_len(_: u8) -> Result<usize, Self::Error> {
+    Ok(DURATION_ENCODED_LEN)
+  }
+
+  fn encode_endian<B: byteorder::ByteOrder>(&self, dst: &mut [u8]) -> Result<usize, Self::Error> {
+    let dur = self
+      .duration_since(std::time::UNIX_EPOCH)
+      .map_err(Self::Error::InvalidSystemTime)?;
+    dur.encode_endian::<B>(dst).map_err(|e| match e {
+      DurationTransformError::EncodeBufferTooSmall => Self::Error::EncodeBufferTooSmall,
+      DurationTransformError::NotEnoughBytes => Self::Error::NotEnoughBytes,
+    })
+  }
+
+  fn decode_endian<B: byteorder::ByteOrder>(src: &[u8]) -> Result<(usize, Self), Self::Error> {
+    Duration::decode_endian::<B>(src)
+      .map(|(readed, dur)| (readed, std::time::UNIX_EPOCH + dur))
+      .map_err(|e| match e {
+        DurationTransformError::EncodeBufferTooSmall => Self::Error::EncodeBufferTooSmall,
+        DurationTransformError::NotEnoughBytes => Self::Error::NotEnoughBytes,
+      })
+  }
+}
+
+#[cfg(feature = "std")]
+impl EndianCodec for std::net::SocketAddr {
+  fn frame_len(first: u8) -> Result<usize, Self::Error> {
+    match first {
+      4 => Ok(7),
+      6 => Ok(19),
+      val => Err(Self::Error::UnknownAddressFamily(val)),
+    }
+  }
+
+  fn encode_endian<B: byteorder::ByteOrder>(&self, dst: &mut [u8]) -> Result<usize, Self::Error> {
+    use std::net::SocketAddr;
+
+    let encoded_len = Transformable::encoded_len(self);
+    if dst.len() < encoded_len {
+      return Err(Self::Error::EncodeBufferTooSmall);
+    }
+
+    match self {
+      SocketAddr::V4(addr) => {
+        dst[0] = 4;
+        dst[1..5].copy_from_slice(&addr.ip().octets());
+        B::write_u16(&mut dst[5..7], addr.port());
+      }
+      SocketAddr::V6(addr) => {
+        dst[0] = 6;
+        dst[1..17].copy_from_slice(&addr.ip().octets());
+        B::write_u16(&mut dst[17..19], addr.port());
+      }
+    }
+    Ok(encoded_len)
+  }
+
+  fn decode_endian<B: byteorder::ByteOrder>(src: &[u8]) -> Result<(usize, Self), Self::Error> {
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    let first = *src.first().ok_or(Self::Error::NotEnoughBytes)?;
+    let len = Self::frame_len(first)?;
+    if src.len() < len {
+      return Err(Self::Error::NotEnoughBytes);
+    }
+
+    let addr = if first == 4 {
+      let ip = Ipv4Addr::new(src[1], src[2], src[3], src[4]);
+      SocketAddr::from((ip, B::read_u16(&src[5..7])))
+    } else {
+      let mut octets = [0u8; 16];
+      octets.copy_from_slice(&src[1..17]);
+      SocketAddr::from((Ipv6Addr::from(octets), B::read_u16(&src[17..19])))
+    };
+    Ok((len, addr))
+  }
+}
+
+/// Reads the value which starts with the first byte with the given read expression, which fills
+/// `$buf`.
+///
+/// Evaluates to the read bytes and the length of the value.
+#[cfg(any(feature = "std", feature = "embedded-io"))]
+macro_rules! read_frame {
+  (|$buf:ident| $read:expr, $frame_len:expr) => {{
+    let mut bytes = [0u8; MAX_ENCODED_LEN];
+    {
+      let $buf = &mut bytes[..1];
+      $read;
+    }
+    let len = $frame_len(bytes[0])?;
+    {
+      let $buf = &mut bytes[1..len];
+      $read;
+    }
+    (bytes, len)
+  }};
+}
+
+#[allow(private_bounds)]
+impl<T: EndianCodec, O: ByteOrder> Transformable for Endian<T, O> {
+  type Error = T::Error;
+
+  fn encode(&self, dst: &mut [u8]) -> Result<usize, Self::Error> {
+    self.value.encode_endian::<O::Inner>(dst)
+  }
+
+  fn encoded_len(&self) -> usize {
+    Transformable::encoded_len(&self.value)
+  }
+
+  fn decode(src: &[u8]) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized,
+  {
+    T::decode_endian::<O::Inner>(src).map(|(readed, val)| (readed, Self::new(val)))
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn is_incomplete(err: &Self::Error) -> bool
+  where
+    Self: Sized,
+  {
+    T::is_incomplete(err)
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn decode_from_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    let (bytes, len) = read_frame!(|buf| reader.read_exact(buf)?, |first| T::frame_len(first)
+      .map_err(invalid_data));
+    <Self as Transformable>::decode(&bytes[..len]).map_err(invalid_data)
+  }
+
+  #[cfg(feature = "async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+  async fn decode_from_async_reader<R: futures_util::io::AsyncRead + Send + Unpin>(
+    reader: &mut R,
+  ) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    use futures_util::AsyncReadExt;
+
+    let (bytes, len) = read_frame!(|buf| reader.read_exact(buf).await?, |first| T::frame_len(
+      first
+    )
+    .map_err(invalid_data));
+    <Self as Transformable>::decode(&bytes[..len]).map_err(invalid_data)
+  }
+
+  #[cfg(feature = "owned-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+  async fn decode_from_owned_reader<R: crate::OwnedRead>(
+    reader: &mut R,
+    mut buf: Vec<u8>,
+  ) -> (std::io::Result<(usize, Self)>, Vec<u8>)
+  where
+    Self: Sized,
+  {
+    buf.clear();
+    let res;
+    (res, buf) = reader.read_exact_owned(buf, 1).await;
+    if let Err(e) = res {
+      return (Err(e), buf);
+    }
+
+    let len = match T::frame_len(buf[0]) {
+      Ok(len) => len,
+      Err(e) => return (Err(invalid_data(e)), buf),
+    };
+    let (res, buf) = reader.read_exact_owned(buf, len - 1).await;
+    if let Err(e) = res {
+      return (Err(e), buf);
+    }
+
+    let res = <Self as Transformable>::decode(&buf).map_err(invalid_data);
+    (res, buf)
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn decode_from_embedded_reader<R: embedded_io::Read>(
+    reader: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    let (bytes, len) = read_frame!(|buf| reader.read_exact(buf)?, |first| T::frame_len(first)
+      .map_err(EmbeddedIoError::Transform));
+    <Self as Transformable>::decode(&bytes[..len]).map_err(EmbeddedIoError::Transform)
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn decode_from_embedded_async_reader<R: embedded_io_async::Read>(
+    reader: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    let (bytes, len) = read_frame!(|buf| reader.read_exact(buf).await?, |first| T::frame_len(
+      first
+    )
+    .map_err(EmbeddedIoError::Transform));
+    <Self as Transformable>::decode(&bytes[..len]).map_err(EmbeddedIoError::Transform)
+  }
+}
+
+#[allow(private_bounds)]
+impl<T: EndianCodec, O: ByteOrder> ContextFree for Endian<T, O> {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  test_transformable!(LittleEndian<u32> => test_little_endian_u32_transformable(LittleEndian::new(rand::random())));
+  test_transformable!(LittleEndian<i128> => test_little_endian_i128_transformable(LittleEndian::new(rand::random())));
+  test_transformable!(NativeEndian<u64> => test_native_endian_u64_transformable(NativeEndian::new(rand::random())));
+  test_transformable!(LittleEndian<u8> => test_little_endian_u8_transformable(LittleEndian::new(rand::random())));
+  test_transformable!(LittleEndian<Duration> => test_little_endian_duration_transformable(LittleEndian::new(Duration::new(10, 1080))));
+
+  #[cfg(feature = "std")]
+  test_transformable!(LittleEndian<std::time::SystemTime> => test_little_endian_systemtime_transformable(LittleEndian::new(std::time::SystemTime::now())));
+  #[cfg(feature = "std")]
+  test_transformable!(LittleEndian<std::net::SocketAddr> => test_little_endian_socket_addr_v4_transformable(LittleEndian::new("127.0.0.1:8080".parse().unwrap())));
+  #[cfg(feature = "std")]
+  test_transformable!(LittleEndian<std::net::SocketAddr> => test_little_endian_socket_addr_v6_transformable(LittleEndian::new("[::1]:8080".parse().unwrap())));
+
+  fn encoded<T: Transformable>(val: T) -> std::vec::Vec<u8>
+  where
+    T::Error: core::fmt::Debug,
+  {
+    let mut buf = std::vec![0; Transformable::encoded_len(&val)];
+    Transformable::encode(&val, &mut buf).unwrap();
+    buf
+  }
+
+  #[test]
+  fn test_byte_order() {
+    assert_eq!(encoded(LittleEndian::new(0x01020304u32)), [4, 3, 2, 1]);
+    assert_eq!(
+      encoded(BigEndian::new(0x01020304u32)),
+      encoded(0x01020304u32)
+    );
+    assert_eq!(
+      encoded(NativeEndian::new(0x01020304u32)),
+      0x01020304u32.to_ne_bytes()
+    );
+
+    let dur = Duration::new(1, 2);
+    assert_eq!(
+      encoded(LittleEndian::new(dur)),
+      [1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0]
+    );
+    assert_eq!(encoded(BigEndian::new(dur)), encoded(dur));
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_socket_addr_byte_order() {
+    let addr: std::net::SocketAddr = "127.0.0.1:258".parse().unwrap();
+    assert_eq!(encoded(LittleEndian::new(addr)), [4, 127, 0, 0, 1, 2, 1]);
+    assert_eq!(encoded(BigEndian::new(addr)), encoded(addr));
+  }
+}
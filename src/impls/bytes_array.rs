@@ -48,6 +48,15 @@ impl<const N: usize> Transformable for [u8; N] {
     Ok((N, buf))
   }
 
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn is_incomplete(err: &Self::Error) -> bool
+  where
+    Self: Sized,
+  {
+    matches!(err, BytesTransformError::NotEnoughBytes)
+  }
+
   #[cfg(feature = "std")]
   #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
   fn decode_from_reader<R: std::io::Read>(src: &mut R) -> std::io::Result<(usize, Self)>
@@ -71,4 +80,66 @@ impl<const N: usize> Transformable for [u8; N] {
     let mut buf = [0u8; N];
     src.read_exact(&mut buf).await.map(|_| (N, buf))
   }
+
+  #[cfg(feature = "owned-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+  async fn decode_from_owned_reader<R: crate::OwnedRead>(
+    reader: &mut R,
+    buf: Vec<u8>,
+  ) -> (std::io::Result<(usize, Self)>, Vec<u8>)
+  where
+    Self: Sized,
+  {
+    crate::owned::decode_fixed_from::<_, _, N>(reader, buf).await
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn encode_to_embedded_writer<W: embedded_io::Write>(
+    &self,
+    dst: &mut W,
+  ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+    dst.write_all(self).map(|_| N).map_err(EmbeddedIoError::Io)
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn encode_to_embedded_async_writer<W: embedded_io_async::Write>(
+    &self,
+    dst: &mut W,
+  ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+    dst
+      .write_all(self)
+      .await
+      .map(|_| N)
+      .map_err(EmbeddedIoError::Io)
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn decode_from_embedded_reader<R: embedded_io::Read>(
+    src: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    let mut buf = [0u8; N];
+    src.read_exact(&mut buf)?;
+    Ok((N, buf))
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn decode_from_embedded_async_reader<R: embedded_io_async::Read>(
+    src: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    let mut buf = [0u8; N];
+    src.read_exact(&mut buf).await?;
+    Ok((N, buf))
+  }
 }
+
+impl<const N: usize> ContextFree for [u8; N] {}
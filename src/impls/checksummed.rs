@@ -0,0 +1,515 @@
+use super::*;
+
+use core::marker::PhantomData;
+
+use crate::checksum::{checksum_of, read_checksum, write_checksum, Checksum, Crc32c};
+#[cfg(any(feature = "std", feature = "embedded-io"))]
+use crate::checksum::{ChecksumReader, MAX_SIZE};
+#[cfg(feature = "std")]
+use crate::{checksum::ChecksumWriter, utils::invalid_data};
+
+/// The error type for errors that get returned when encoding or decoding [`Checksummed`] fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChecksummedTransformError<E> {
+  /// Returned when the buffer is too small to encode.
+  EncodeBufferTooSmall,
+  /// Returned when the buffer does not contain the checksum.
+  NotEnoughBytes,
+  /// Returned when encoding or decoding the value fails.
+  Transform(E),
+  /// Returned when the checksum of the received bytes does not match the one sent along.
+  ChecksumMismatch {
+    /// The checksum sent along with the value.
+    expected: u64,
+    /// The checksum of the received bytes.
+    actual: u64,
+  },
+}
+
+forward_decode_context!(ChecksummedTransformError);
+
+impl<E: Into<TransformError>> From<ChecksummedTransformError<E>> for TransformError {
+  fn from(err: ChecksummedTransformError<E>) -> Self {
+    match err {
+      ChecksummedTransformError::EncodeBufferTooSmall => Self::from_kind(ErrorKind::BufferTooSmall),
+      ChecksummedTransformError::NotEnoughBytes => Self::from_kind(ErrorKind::UnexpectedEof),
+      ChecksummedTransformError::Transform(e) => e.into(),
+      ChecksummedTransformError::ChecksumMismatch { expected, actual } => Self::wrap(
+        ErrorKind::InvalidValue,
+        ChecksummedTransformError::<core::convert::Infallible>::ChecksumMismatch {
+          expected,
+          actual,
+        },
+      ),
+    }
+  }
+}
+
+impl<E: core::fmt::Display> core::fmt::Display for ChecksummedTransformError<E> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::EncodeBufferTooSmall => write!(
+        f,
+        "buffer is too small, use `Transformable::encoded_len` to pre-allocate a buffer with enough space"
+      ),
+      Self::NotEnoughBytes => write!(f, "not enough bytes to decode"),
+      Self::Transform(e) => write!(f, "{e}"),
+      Self::ChecksumMismatch { expected, actual } => write!(
+        f,
+        "checksum mismatch, expected {expected:#x}, but the received bytes have {actual:#x}"
+      ),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for ChecksummedTransformError<E> {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Transform(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+/// A value followed by the checksum `C` of its encoding, which is verified on decode.
+///
+/// Catches corruption which slipped through the transport, e.g. on a flaky link. The checksum is
+/// computed while the value is written to or read from a stream, so the message is not buffered.
+///
+/// The checksum is encoded in big-endian byte order, in [`C::SIZE`](Checksum::SIZE) bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Checksummed<T, C = Crc32c> {
+  value: T,
+  _checksum: PhantomData<C>,
+}
+
+impl<T, C> Checksummed<T, C> {
+  /// Wraps the given value.
+  #[inline]
+  pub const fn new(value: T) -> Self {
+    Self {
+      value,
+      _checksum: PhantomData,
+    }
+  }
+
+  /// Returns a reference to the value.
+  #[inline]
+  pub const fn get(&self) -> &T {
+    &self.value
+  }
+
+  /// Consumes the wrapper, returning the value.
+  #[inline]
+  pub fn into_inner(self) -> T {
+    self.value
+  }
+}
+
+impl<T, C> From<T> for Checksummed<T, C> {
+  #[inline]
+  fn from(value: T) -> Self {
+    Self::new(value)
+  }
+}
+
+impl<T, C> core::ops::Deref for Checksummed<T, C> {
+  type Target = T;
+
+  #[inline]
+  fn deref(&self) -> &T {
+    &self.value
+  }
+}
+
+impl<T, C: Checksum> Checksummed<T, C> {
+  /// Verifies the checksum which follows the `readed` bytes of the value in `src`.
+  fn verify<E>(
+    src: &[u8],
+    readed: usize,
+    val: T,
+  ) -> Result<(usize, Self), ChecksummedTransformError<E>> {
+    let stored = src
+      .get(readed..readed + C::SIZE)
+      .ok_or(ChecksummedTransformError::NotEnoughBytes)?;
+    check::<_>(read_checksum::<C>(stored), checksum_of::<C>(&src[..readed]))
+      .map(|_| (readed + C::SIZE, Self::new(val)))
+  }
+}
+
+#[inline]
+fn check<E>(expected: u64, actual: u64) -> Result<(), ChecksummedTransformError<E>> {
+  if expected != actual {
+    return Err(ChecksummedTransformError::ChecksumMismatch { expected, actual });
+  }
+  Ok(())
+}
+
+#[cfg(feature = "std")]
+fn read_and_check<
+  R: std::io::Read + ?Sized,
+  C: Checksum,
+  E: std::error::Error + Send + Sync + 'static,
+>(
+  reader: ChecksumReader<'_, R, C>,
+) -> std::io::Result<()> {
+  let (actual, reader) = reader.finish();
+  let mut stored = [0u8; MAX_SIZE];
+  reader.read_exact(&mut stored[..C::SIZE])?;
+  check::<E>(read_checksum::<C>(&stored), actual).map_err(invalid_data)
+}
+
+#[cfg(feature = "async")]
+async fn read_and_check_async<R, C, E>(reader: ChecksumReader<'_, R, C>) -> std::io::Result<()>
+where
+  R: futures_util::io::AsyncRead + Unpin + ?Sized,
+  C: Checksum,
+  E: std::error::Error + Send + Sync + 'static,
+{
+  use futures_util::io::AsyncReadExt;
+
+  let (actual, reader) = reader.finish();
+  let mut stored = [0u8; MAX_SIZE];
+  reader.read_exact(&mut stored[..C::SIZE]).await?;
+  check::<E>(read_checksum::<C>(&stored), actual).map_err(invalid_data)
+}
+
+/// Wraps the error of the value, returned by the embedded reader.
+#[cfg(feature = "embedded-io")]
+fn embedded_error<IO, E>(
+  err: EmbeddedIoError<IO, E>,
+) -> EmbeddedIoError<IO, ChecksummedTransformError<E>> {
+  match err {
+    EmbeddedIoError::Io(e) => EmbeddedIoError::Io(e),
+    EmbeddedIoError::UnexpectedEof => EmbeddedIoError::UnexpectedEof,
+    EmbeddedIoError::MessageTooLarge(len) => EmbeddedIoError::MessageTooLarge(len),
+    EmbeddedIoError::AllocationFailed { requested } => {
+      EmbeddedIoError::AllocationFailed { requested }
+    }
+    EmbeddedIoError::Limit(e) => EmbeddedIoError::Limit(e),
+    EmbeddedIoError::Transform(e) => {
+      EmbeddedIoError::Transform(ChecksummedTransformError::Transform(e))
+    }
+  }
+}
+
+impl<T: Transformable, C: Checksum> Transformable for Checksummed<T, C> {
+  type Error = ChecksummedTransformError<T::Error>;
+
+  fn encode(&self, dst: &mut [u8]) -> Result<usize, Self::Error> {
+    if dst.len() < Transformable::encoded_len(self) {
+      return Err(Self::Error::EncodeBufferTooSmall);
+    }
+
+    let len =
+      Transformable::encode(&self.value, dst).map_err(ChecksummedTransformError::Transform)?;
+    let checksum = checksum_of::<C>(&dst[..len]);
+    write_checksum::<C>(checksum, &mut dst[len..len + C::SIZE]);
+    Ok(len + C::SIZE)
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn encode_to_writer<W: std::io::Write>(&self, dst: &mut W) -> std::io::Result<usize> {
+    let mut writer = ChecksumWriter::<_, C>::new(dst);
+    let len = Transformable::encode_to_writer(&self.value, &mut writer)?;
+    let (checksum, dst) = writer.finish();
+    let mut buf = [0u8; MAX_SIZE];
+    write_checksum::<C>(checksum, &mut buf);
+    dst.write_all(&buf[..C::SIZE]).map(|_| len + C::SIZE)
+  }
+
+  #[cfg(feature = "async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+  async fn encode_to_async_writer<W: futures_util::io::AsyncWrite + Send + Unpin>(
+    &self,
+    dst: &mut W,
+  ) -> std::io::Result<usize> {
+    use futures_util::io::AsyncWriteExt;
+
+    let mut writer = ChecksumWriter::<_, C>::new(dst);
+    let len = Transformable::encode_to_async_writer(&self.value, &mut writer).await?;
+    let (checksum, dst) = writer.finish();
+    let mut buf = [0u8; MAX_SIZE];
+    write_checksum::<C>(checksum, &mut buf);
+    dst.write_all(&buf[..C::SIZE]).await.map(|_| len + C::SIZE)
+  }
+
+  fn encoded_len(&self) -> usize {
+    Transformable::encoded_len(&self.value) + C::SIZE
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn as_io_slices<'a>(&'a self, slices: &mut GatherList<'a>) -> bool {
+    let mut skip = slices.len();
+    if !Transformable::as_io_slices(&self.value, slices) {
+      return false;
+    }
+
+    let mut state = C::State::default();
+    for segment in slices.iter() {
+      if skip >= segment.len() {
+        skip -= segment.len();
+        continue;
+      }
+
+      C::update(&mut state, &segment[skip..]);
+      skip = 0;
+    }
+
+    let mut buf = [0u8; MAX_SIZE];
+    write_checksum::<C>(crate::checksum::finish::<C>(&state), &mut buf);
+    slices.push_copied(&buf[..C::SIZE]);
+    true
+  }
+
+  fn decode(src: &[u8]) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized,
+  {
+    let (readed, val) = T::decode(src).map_err(ChecksummedTransformError::Transform)?;
+    Self::verify(src, readed, val)
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn is_incomplete(err: &Self::Error) -> bool
+  where
+    Self: Sized,
+  {
+    match err {
+      ChecksummedTransformError::NotEnoughBytes => true,
+      ChecksummedTransformError::Transform(e) => T::is_incomplete(e),
+      _ => false,
+    }
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn decode_from_reader<R: std::io::Read>(src: &mut R) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    let mut reader = ChecksumReader::<_, C>::new(src);
+    let (readed, val) = T::decode_from_reader(&mut reader)?;
+    read_and_check::<_, C, T::Error>(reader)?;
+    Ok((readed + C::SIZE, Self::new(val)))
+  }
+
+  #[cfg(feature = "async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+  async fn decode_from_async_reader<R: futures_util::io::AsyncRead + Send + Unpin>(
+    src: &mut R,
+  ) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    let mut reader = ChecksumReader::<_, C>::new(src);
+    let (readed, val) = T::decode_from_async_reader(&mut reader).await?;
+    read_and_check_async::<_, C, T::Error>(reader).await?;
+    Ok((readed + C::SIZE, Self::new(val)))
+  }
+
+  fn decode_limited(src: &[u8], budget: &mut DecodeBudget) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized,
+  {
+    let (readed, val) =
+      T::decode_limited(src, budget).map_err(ChecksummedTransformError::Transform)?;
+    Self::verify(src, readed, val)
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn decode_from_reader_limited<R: std::io::Read>(
+    src: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    let mut reader = ChecksumReader::<_, C>::new(src);
+    let (readed, val) = T::decode_from_reader_limited(&mut reader, budget)?;
+    read_and_check::<_, C, T::Error>(reader)?;
+    Ok((readed + C::SIZE, Self::new(val)))
+  }
+
+  #[cfg(feature = "async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+  async fn decode_from_async_reader_limited<R: futures_util::io::AsyncRead + Send + Unpin>(
+    src: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    let mut reader = ChecksumReader::<_, C>::new(src);
+    let (readed, val) = T::decode_from_async_reader_limited(&mut reader, budget).await?;
+    read_and_check_async::<_, C, T::Error>(reader).await?;
+    Ok((readed + C::SIZE, Self::new(val)))
+  }
+
+  #[cfg(feature = "owned-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+  async fn decode_from_owned_reader<R: crate::OwnedRead>(
+    reader: &mut R,
+    buf: Vec<u8>,
+  ) -> (std::io::Result<(usize, Self)>, Vec<u8>)
+  where
+    Self: Sized,
+  {
+    let mut checksummed = ChecksumReader::<_, C>::new(reader);
+    let (res, mut buf) = T::decode_from_owned_reader(&mut checksummed, buf).await;
+    let (readed, val) = match res {
+      Ok(res) => res,
+      Err(e) => return (Err(e), buf),
+    };
+
+    let (actual, reader) = checksummed.finish();
+    buf.clear();
+    let res;
+    (res, buf) = reader.read_exact_owned(buf, C::SIZE).await;
+    let res = res.and_then(|_| {
+      check::<T::Error>(read_checksum::<C>(&buf), actual)
+        .map(|_| (readed + C::SIZE, Self::new(val)))
+        .map_err(invalid_data)
+    });
+    (res, buf)
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn decode_from_embedded_reader<R: embedded_io::Read>(
+    src: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    let mut reader = ChecksumReader::<_, C>::new(src);
+    let (readed, val) = T::decode_from_embedded_reader(&mut reader).map_err(embedded_error)?;
+    let (actual, src) = reader.finish();
+    let mut stored = [0u8; MAX_SIZE];
+    src.read_exact(&mut stored[..C::SIZE])?;
+    check::<T::Error>(read_checksum::<C>(&stored), actual)
+      .map(|_| (readed + C::SIZE, Self::new(val)))
+      .map_err(EmbeddedIoError::Transform)
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn decode_from_embedded_async_reader<R: embedded_io_async::Read>(
+    src: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    let mut reader = ChecksumReader::<_, C>::new(src);
+    let (readed, val) = T::decode_from_embedded_async_reader(&mut reader)
+      .await
+      .map_err(embedded_error)?;
+    let (actual, src) = reader.finish();
+    let mut stored = [0u8; MAX_SIZE];
+    src.read_exact(&mut stored[..C::SIZE]).await?;
+    check::<T::Error>(read_checksum::<C>(&stored), actual)
+      .map(|_| (readed + C::SIZE, Self::new(val)))
+      .map_err(EmbeddedIoError::Transform)
+  }
+}
+
+/// Checksummed values pass the context down to the value.
+impl<Cx: ?Sized, T: TransformableWith<Cx>, C: Checksum> TransformableWith<Cx>
+  for Checksummed<T, C>
+{
+  type Error = ChecksummedTransformError<T::Error>;
+
+  fn encode_with(&self, ctx: &Cx, dst: &mut [u8]) -> Result<usize, Self::Error> {
+    if dst.len() < self.encoded_len_with(ctx) {
+      return Err(Self::Error::EncodeBufferTooSmall);
+    }
+
+    let len = self
+      .value
+      .encode_with(ctx, dst)
+      .map_err(ChecksummedTransformError::Transform)?;
+    let checksum = checksum_of::<C>(&dst[..len]);
+    write_checksum::<C>(checksum, &mut dst[len..len + C::SIZE]);
+    Ok(len + C::SIZE)
+  }
+
+  fn encoded_len_with(&self, ctx: &Cx) -> usize {
+    self.value.encoded_len_with(ctx) + C::SIZE
+  }
+
+  fn decode_with(ctx: &Cx, src: &[u8]) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized,
+  {
+    let (readed, val) = T::decode_with(ctx, src).map_err(ChecksummedTransformError::Transform)?;
+    Self::verify(src, readed, val)
+  }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use super::*;
+  use std::string::String;
+
+  test_transformable!(Checksummed<u64> => test_checksummed_u64_transformable(Checksummed::new(rand::random())));
+  test_transformable!(Checksummed<Vec<u8>> => test_checksummed_vec_transformable(Checksummed::new(std::vec![7; 1000])));
+  test_transformable!(Checksummed<Checksummed<String>> => test_checksummed_nested_transformable(Checksummed::new(Checksummed::new(String::from("hello world")))));
+
+  #[cfg(feature = "xxhash64")]
+  test_transformable!(Checksummed<Vec<u8>, crate::checksum::XxHash64> => test_checksummed_xxhash64_transformable(Checksummed::new(std::vec![7; 1000])));
+
+  #[test]
+  fn test_checksum_mismatch() {
+    let val = Checksummed::<String>::new(String::from("hello world"));
+    let mut encoded = Transformable::encode_to_vec(&val).unwrap();
+    assert_eq!(encoded.len(), 4 + 11 + 4);
+    assert_eq!(
+      <Checksummed<String> as Transformable>::decode(&encoded).unwrap(),
+      (encoded.len(), val)
+    );
+
+    // flip a bit of the payload
+    encoded[6] ^= 0x10;
+    let Err(ChecksummedTransformError::ChecksumMismatch { expected, actual }) =
+      <Checksummed<String> as Transformable>::decode(&encoded)
+    else {
+      panic!("corruption is not detected");
+    };
+    assert_ne!(expected, actual);
+
+    assert!(matches!(
+      <Checksummed<String> as Transformable>::decode(&encoded[..encoded.len() - 1]),
+      Err(ChecksummedTransformError::NotEnoughBytes)
+    ));
+
+    #[cfg(feature = "std")]
+    {
+      let err = <Checksummed<String> as Transformable>::decode_from_reader(&mut encoded.as_slice())
+        .unwrap_err();
+      assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+      assert!(matches!(
+        err
+          .into_inner()
+          .unwrap()
+          .downcast::<ChecksummedTransformError<StringTransformError>>()
+          .as_deref(),
+        Ok(ChecksummedTransformError::ChecksumMismatch { .. })
+      ));
+    }
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_streaming_writer() {
+    let val = Checksummed::<Vec<u8>>::new((0..=255).collect());
+    let mut writer = crate::vectored::tests::Trickle(std::vec::Vec::new());
+    let len = Transformable::encode_to_writer(&val, &mut writer).unwrap();
+    assert_eq!(len, Transformable::encoded_len(&val));
+    assert_eq!(writer.0, Transformable::encode_to_vec(&val).unwrap());
+  }
+}
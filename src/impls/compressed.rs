@@ -0,0 +1,829 @@
+use super::*;
+
+use core::marker::PhantomData;
+
+use byteorder::{ByteOrder, NetworkEndian};
+
+use crate::compression::{self, Algorithm, CompressionError, STORED};
+#[cfg(feature = "std")]
+use crate::utils::invalid_data;
+use crate::TrailingBytes;
+
+/// The length of the header: the algorithm id, the decompressed length and the payload length.
+const HEADER_LEN: usize = 1 + 4 + 4;
+
+/// The error type for errors that get returned when encoding or decoding [`Compressed`] fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompressedTransformError<E> {
+  /// Returned when the buffer is too small to encode.
+  EncodeBufferTooSmall,
+  /// Returned when the buffer does not contain the whole payload.
+  NotEnoughBytes,
+  /// Returned when the encoded value is longer than `u32::MAX` bytes.
+  TooLarge(usize),
+  /// Returned when encoding the value fails.
+  Transform(E),
+  /// Returned when decoding the value fails.
+  Decode {
+    /// The offset of the value in the message, or `0` if the value is decoded from decompressed
+    /// bytes, whose offsets are relative to them.
+    offset: usize,
+    /// The error of the value.
+    source: E,
+  },
+  /// Returned when compressing or decompressing the payload fails.
+  Compression(CompressionError),
+  /// Returned when the decompressed value does not consume all of the decompressed bytes.
+  TrailingBytes(TrailingBytes),
+  /// Returned when a [`DecodeLimits`] is exceeded.
+  Limit(LimitError),
+  /// Returned when the memory for the decompressed bytes can not be allocated.
+  AllocationFailed {
+    /// The number of bytes which failed to be allocated.
+    requested: usize,
+  },
+}
+
+forward_decode_context!(CompressedTransformError, Decode);
+
+impl<E: Into<TransformError>> From<CompressedTransformError<E>> for TransformError {
+  fn from(err: CompressedTransformError<E>) -> Self {
+    match err {
+      CompressedTransformError::EncodeBufferTooSmall => Self::from_kind(ErrorKind::BufferTooSmall),
+      CompressedTransformError::NotEnoughBytes => Self::from_kind(ErrorKind::UnexpectedEof),
+      CompressedTransformError::TooLarge(len) => Self::wrap(
+        ErrorKind::InvalidValue,
+        CompressedTransformError::<core::convert::Infallible>::TooLarge(len),
+      ),
+      CompressedTransformError::Transform(e) => e.into(),
+      CompressedTransformError::Decode { offset, source } => source.into().at_offset(offset),
+      CompressedTransformError::Compression(e) => e.into(),
+      CompressedTransformError::TrailingBytes(e) => e.into(),
+      CompressedTransformError::Limit(e) => e.into(),
+      CompressedTransformError::AllocationFailed { requested } => Self::wrap(
+        ErrorKind::LimitExceeded,
+        CompressedTransformError::<core::convert::Infallible>::AllocationFailed { requested },
+      ),
+    }
+  }
+}
+
+impl<E> From<CompressionError> for CompressedTransformError<E> {
+  #[inline]
+  fn from(err: CompressionError) -> Self {
+    Self::Compression(err)
+  }
+}
+
+impl<E> From<TrailingBytes> for CompressedTransformError<E> {
+  #[inline]
+  fn from(err: TrailingBytes) -> Self {
+    Self::TrailingBytes(err)
+  }
+}
+
+impl<E> From<LimitError> for CompressedTransformError<E> {
+  #[inline]
+  fn from(err: LimitError) -> Self {
+    Self::Limit(err)
+  }
+}
+
+impl<E: core::fmt::Display> core::fmt::Display for CompressedTransformError<E> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::EncodeBufferTooSmall => write!(
+        f,
+        "buffer is too small, use `Transformable::encoded_len` to pre-allocate a buffer with enough space"
+      ),
+      Self::NotEnoughBytes => write!(f, "not enough bytes to decode"),
+      Self::TooLarge(len) => write!(f, "value of {len} bytes is too large to be compressed"),
+      Self::Transform(e) => write!(f, "{e}"),
+      Self::Decode { source, .. } => write!(f, "{source}"),
+      Self::Compression(e) => write!(f, "{e}"),
+      Self::TrailingBytes(e) => write!(f, "{e}"),
+      Self::Limit(e) => write!(f, "{e}"),
+      Self::AllocationFailed { requested } => write!(f, "failed to allocate {requested} bytes"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for CompressedTransformError<E> {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Transform(e) => Some(e),
+      Self::Decode { source, .. } => Some(source),
+      Self::Compression(e) => Some(e),
+      Self::TrailingBytes(e) => Some(e),
+      Self::Limit(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+/// A value whose encoding is compressed with the algorithm `A`.
+///
+/// The payload is preceded by a header, which holds the id of the algorithm, so the decoding side
+/// accepts whatever enabled [`Algorithm`] the sender picked, e.g. while a cluster migrates from
+/// one algorithm to another. Values whose encoding is shorter than `MIN_SIZE` bytes, or which do
+/// not shrink when compressed, are stored uncompressed.
+///
+/// Decoding fails before allocating anything if the decompressed value would be longer than
+/// [`DecodeLimits::max_decompressed_len`], which protects against zip bombs. The plain decoding
+/// methods use the [default limits](DecodeLimits::default).
+///
+/// The value is compressed to compute its [`encoded_len`](Transformable::encoded_len), prefer
+/// [`encode_to_vec`](Transformable::encode_to_vec) or
+/// [`encode_to_writer`](Transformable::encode_to_writer), which compress it only once.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Compressed<T, A, const MIN_SIZE: usize = 64> {
+  value: T,
+  _algorithm: PhantomData<A>,
+}
+
+impl<T, A, const MIN_SIZE: usize> Compressed<T, A, MIN_SIZE> {
+  /// Wraps the given value.
+  #[inline]
+  pub const fn new(value: T) -> Self {
+    Self {
+      value,
+      _algorithm: PhantomData,
+    }
+  }
+
+  /// Returns a reference to the value.
+  #[inline]
+  pub const fn get(&self) -> &T {
+    &self.value
+  }
+
+  /// Consumes the wrapper, returning the value.
+  #[inline]
+  pub fn into_inner(self) -> T {
+    self.value
+  }
+}
+
+impl<T, A, const MIN_SIZE: usize> From<T> for Compressed<T, A, MIN_SIZE> {
+  #[inline]
+  fn from(value: T) -> Self {
+    Self::new(value)
+  }
+}
+
+impl<T, A, const MIN_SIZE: usize> core::ops::Deref for Compressed<T, A, MIN_SIZE> {
+  type Target = T;
+
+  #[inline]
+  fn deref(&self) -> &T {
+    &self.value
+  }
+}
+
+/// The header which precedes the payload.
+struct Header {
+  algorithm: u8,
+  decompressed_len: usize,
+  payload_len: usize,
+}
+
+impl Header {
+  fn encode(&self, dst: &mut [u8]) {
+    dst[0] = self.algorithm;
+    NetworkEndian::write_u32(&mut dst[1..5], self.decompressed_len as u32);
+    NetworkEndian::write_u32(&mut dst[5..HEADER_LEN], self.payload_len as u32);
+  }
+
+  fn decode(src: &[u8]) -> Self {
+    Self {
+      algorithm: src[0],
+      decompressed_len: NetworkEndian::read_u32(&src[1..5]) as usize,
+      payload_len: NetworkEndian::read_u32(&src[5..HEADER_LEN]) as usize,
+    }
+  }
+}
+
+impl<T, A: Algorithm, const MIN_SIZE: usize> Compressed<T, A, MIN_SIZE> {
+  /// Fails to compile if the id of the algorithm is the id of stored payloads.
+  const VALID_ALGORITHM: () = assert!(
+    A::ID != STORED,
+    "the id of a compression algorithm must not be `compression::STORED`"
+  );
+
+  /// Compresses the encoded value, returning the header and the payload.
+  fn compress_encoded<E>(
+    encoded: Vec<u8>,
+  ) -> Result<(Header, Vec<u8>), CompressedTransformError<E>> {
+    #[allow(clippy::let_unit_value)]
+    let () = Self::VALID_ALGORITHM;
+    let len = encoded.len();
+    if len > u32::MAX as usize {
+      return Err(CompressedTransformError::TooLarge(len));
+    }
+
+    let mut header = Header {
+      algorithm: STORED,
+      decompressed_len: len,
+      payload_len: len,
+    };
+    if len >= MIN_SIZE {
+      let compressed = A::compress(&encoded)?;
+      if compressed.len() < len {
+        header.algorithm = A::ID;
+        header.payload_len = compressed.len();
+        return Ok((header, compressed));
+      }
+    }
+    Ok((header, encoded))
+  }
+
+  /// Encodes the value in the given context and compresses it, returning the header and the
+  /// payload.
+  fn compress_with<Cx: ?Sized>(
+    &self,
+    ctx: &Cx,
+  ) -> Result<(Header, Vec<u8>), CompressedTransformError<T::Error>>
+  where
+    T: TransformableWith<Cx>,
+  {
+    let encoded = self
+      .value
+      .encode_to_vec_with(ctx)
+      .map_err(CompressedTransformError::Transform)?;
+    Self::compress_encoded(encoded)
+  }
+
+  /// Decodes the message in `src`, decoding the value with `decode`.
+  fn decode_message<E>(
+    src: &[u8],
+    budget: &mut DecodeBudget,
+    decode: impl FnOnce(&[u8], &mut DecodeBudget) -> Result<(usize, T), E>,
+  ) -> Result<(usize, Self), CompressedTransformError<E>> {
+    if src.len() < HEADER_LEN {
+      return Err(CompressedTransformError::NotEnoughBytes);
+    }
+
+    let header = Header::decode(src);
+    let len = HEADER_LEN + header.payload_len;
+    budget.check_message_size(len)?;
+    if src.len() < len {
+      return Err(CompressedTransformError::NotEnoughBytes);
+    }
+
+    Self::decode_payload(&header, &src[HEADER_LEN..len], budget, decode).map(|val| (len, val))
+  }
+
+  /// Decompresses the payload and decodes the value from it with `decode`.
+  fn decode_payload<E>(
+    header: &Header,
+    payload: &[u8],
+    budget: &mut DecodeBudget,
+    decode: impl FnOnce(&[u8], &mut DecodeBudget) -> Result<(usize, T), E>,
+  ) -> Result<Self, CompressedTransformError<E>> {
+    #[allow(clippy::let_unit_value)]
+    let () = Self::VALID_ALGORITHM;
+    if header.algorithm == STORED {
+      return Self::decode_value(payload, HEADER_LEN, budget, decode);
+    }
+
+    let len = header.decompressed_len;
+    budget.check_decompressed_len(len)?;
+    budget.allocate(len)?;
+    let mut buf = Vec::new();
+    buf
+      .try_reserve_exact(len)
+      .map_err(|_| CompressedTransformError::AllocationFailed { requested: len })?;
+    buf.resize(len, 0);
+    compression::decompress::<A>(header.algorithm, payload, &mut buf)?;
+    Self::decode_value(&buf, 0, budget, decode)
+  }
+
+  /// Decodes the value from the given bytes, which start at `offset` in the message.
+  fn decode_value<E>(
+    src: &[u8],
+    offset: usize,
+    budget: &mut DecodeBudget,
+    decode: impl FnOnce(&[u8], &mut DecodeBudget) -> Result<(usize, T), E>,
+  ) -> Result<Self, CompressedTransformError<E>> {
+    let (readed, val) =
+      decode(src, budget).map_err(|source| CompressedTransformError::Decode { offset, source })?;
+    TrailingBytes::check(readed, src.len())?;
+    Ok(Self::new(val))
+  }
+}
+
+impl<T: Transformable, A: Algorithm, const MIN_SIZE: usize> Compressed<T, A, MIN_SIZE> {
+  /// Encodes the value and compresses it, returning the header and the payload.
+  fn compress(&self) -> Result<(Header, Vec<u8>), CompressedTransformError<T::Error>> {
+    let encoded =
+      Transformable::encode_to_vec(&self.value).map_err(CompressedTransformError::Transform)?;
+    Self::compress_encoded(encoded)
+  }
+}
+
+impl<T: Transformable, A: Algorithm, const MIN_SIZE: usize> Transformable
+  for Compressed<T, A, MIN_SIZE>
+{
+  type Error = CompressedTransformError<T::Error>;
+
+  fn encode(&self, dst: &mut [u8]) -> Result<usize, Self::Error> {
+    let (header, payload) = self.compress()?;
+    let len = HEADER_LEN + payload.len();
+    if dst.len() < len {
+      return Err(Self::Error::EncodeBufferTooSmall);
+    }
+
+    header.encode(dst);
+    dst[HEADER_LEN..len].copy_from_slice(&payload);
+    Ok(len)
+  }
+
+  fn encode_to_vec(&self) -> Result<Vec<u8>, Self::Error> {
+    let (header, payload) = self.compress()?;
+    let mut buf = Vec::with_capacity(HEADER_LEN + payload.len());
+    buf.resize(HEADER_LEN, 0);
+    header.encode(&mut buf);
+    buf.extend_from_slice(&payload);
+    Ok(buf)
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn encode_to_writer<W: std::io::Write>(&self, dst: &mut W) -> std::io::Result<usize> {
+    let (header, payload) = self.compress().map_err(invalid_data)?;
+    let mut buf = [0u8; HEADER_LEN];
+    header.encode(&mut buf);
+    dst.write_all(&buf)?;
+    dst.write_all(&payload).map(|_| HEADER_LEN + payload.len())
+  }
+
+  #[cfg(feature = "async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+  async fn encode_to_async_writer<W: futures_util::io::AsyncWrite + Send + Unpin>(
+    &self,
+    dst: &mut W,
+  ) -> std::io::Result<usize> {
+    use futures_util::io::AsyncWriteExt;
+
+    let (header, payload) = self.compress().map_err(invalid_data)?;
+    let mut buf = [0u8; HEADER_LEN];
+    header.encode(&mut buf);
+    dst.write_all(&buf).await?;
+    dst
+      .write_all(&payload)
+      .await
+      .map(|_| HEADER_LEN + payload.len())
+  }
+
+  fn encoded_len(&self) -> usize {
+    match self.compress() {
+      Ok((_, payload)) => HEADER_LEN + payload.len(),
+      Err(_) => HEADER_LEN + Transformable::encoded_len(&self.value),
+    }
+  }
+
+  fn decode(src: &[u8]) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized,
+  {
+    <Self as Transformable>::decode_limited(src, &mut DecodeBudget::default())
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn is_incomplete(err: &Self::Error) -> bool
+  where
+    Self: Sized,
+  {
+    matches!(err, CompressedTransformError::NotEnoughBytes)
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn decode_from_reader<R: std::io::Read>(src: &mut R) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    <Self as Transformable>::decode_from_reader_limited(src, &mut DecodeBudget::default())
+  }
+
+  #[cfg(feature = "async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+  async fn decode_from_async_reader<R: futures_util::io::AsyncRead + Send + Unpin>(
+    src: &mut R,
+  ) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    <Self as Transformable>::decode_from_async_reader_limited(src, &mut DecodeBudget::default())
+      .await
+  }
+
+  fn decode_limited(src: &[u8], budget: &mut DecodeBudget) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized,
+  {
+    Self::decode_message(src, budget, T::decode_limited)
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn decode_from_reader_limited<R: std::io::Read>(
+    src: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    let mut buf = [0u8; HEADER_LEN];
+    src.read_exact(&mut buf)?;
+    let header = Header::decode(&buf);
+    let len = HEADER_LEN + header.payload_len;
+    budget.check_message_size(len).map_err(invalid_data)?;
+    budget.allocate(header.payload_len).map_err(invalid_data)?;
+
+    let mut payload = Vec::new();
+    crate::utils::read_exact_to_vec(src, &mut payload, header.payload_len)?;
+    Self::decode_payload(&header, &payload, budget, T::decode_limited)
+      .map(|val| (len, val))
+      .map_err(invalid_data)
+  }
+
+  #[cfg(feature = "async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+  async fn decode_from_async_reader_limited<R: futures_util::io::AsyncRead + Send + Unpin>(
+    src: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    use futures_util::io::AsyncReadExt;
+
+    let mut buf = [0u8; HEADER_LEN];
+    src.read_exact(&mut buf).await?;
+    let header = Header::decode(&buf);
+    let len = HEADER_LEN + header.payload_len;
+    budget.check_message_size(len).map_err(invalid_data)?;
+    budget.allocate(header.payload_len).map_err(invalid_data)?;
+
+    let mut payload = Vec::new();
+    crate::utils::read_exact_to_vec_async(src, &mut payload, header.payload_len).await?;
+    Self::decode_payload(&header, &payload, budget, T::decode_limited)
+      .map(|val| (len, val))
+      .map_err(invalid_data)
+  }
+
+  #[cfg(feature = "owned-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+  async fn decode_from_owned_reader<R: crate::OwnedRead>(
+    reader: &mut R,
+    mut buf: Vec<u8>,
+  ) -> (std::io::Result<(usize, Self)>, Vec<u8>)
+  where
+    Self: Sized,
+  {
+    buf.clear();
+    let res;
+    (res, buf) = reader.read_exact_owned(buf, HEADER_LEN).await;
+    if let Err(e) = res {
+      return (Err(e), buf);
+    }
+
+    let header = Header::decode(&buf);
+    let mut budget = DecodeBudget::default();
+    if let Err(e) = budget
+      .check_message_size(HEADER_LEN + header.payload_len)
+      .and_then(|_| budget.allocate(header.payload_len))
+    {
+      return (Err(invalid_data(e)), buf);
+    }
+
+    let res;
+    (res, buf) = reader.read_exact_owned(buf, header.payload_len).await;
+    if let Err(e) = res {
+      return (Err(e), buf);
+    }
+
+    let res = Self::decode_payload(&header, &buf[HEADER_LEN..], &mut budget, T::decode_limited)
+      .map(|val| (HEADER_LEN + header.payload_len, val))
+      .map_err(invalid_data);
+    (res, buf)
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn decode_from_embedded_reader<R: embedded_io::Read>(
+    src: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    let mut buf = [0u8; HEADER_LEN];
+    src.read_exact(&mut buf)?;
+    let header = Header::decode(&buf);
+    let mut budget = DecodeBudget::default();
+    budget
+      .check_message_size(HEADER_LEN + header.payload_len)
+      .and_then(|_| budget.allocate(header.payload_len))
+      .map_err(|e| EmbeddedIoError::Transform(e.into()))?;
+    let mut payload = crate::embedded::try_zeroed(header.payload_len)?;
+    src.read_exact(&mut payload)?;
+    Self::decode_payload(&header, &payload, &mut budget, T::decode_limited)
+      .map(|val| (HEADER_LEN + header.payload_len, val))
+      .map_err(EmbeddedIoError::Transform)
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn decode_from_embedded_async_reader<R: embedded_io_async::Read>(
+    src: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    let mut buf = [0u8; HEADER_LEN];
+    src.read_exact(&mut buf).await?;
+    let header = Header::decode(&buf);
+    let mut budget = DecodeBudget::default();
+    budget
+      .check_message_size(HEADER_LEN + header.payload_len)
+      .and_then(|_| budget.allocate(header.payload_len))
+      .map_err(|e| EmbeddedIoError::Transform(e.into()))?;
+    let mut payload = crate::embedded::try_zeroed(header.payload_len)?;
+    src.read_exact(&mut payload).await?;
+    Self::decode_payload(&header, &payload, &mut budget, T::decode_limited)
+      .map(|val| (HEADER_LEN + header.payload_len, val))
+      .map_err(EmbeddedIoError::Transform)
+  }
+}
+
+/// Compressed values pass the context down to the value. They are decoded with the
+/// [default limits](DecodeLimits::default).
+impl<Cx: ?Sized, T: TransformableWith<Cx>, A: Algorithm, const MIN_SIZE: usize>
+  TransformableWith<Cx> for Compressed<T, A, MIN_SIZE>
+{
+  type Error = CompressedTransformError<T::Error>;
+
+  fn encode_with(&self, ctx: &Cx, dst: &mut [u8]) -> Result<usize, Self::Error> {
+    let (header, payload) = self.compress_with(ctx)?;
+    let len = HEADER_LEN + payload.len();
+    if dst.len() < len {
+      return Err(Self::Error::EncodeBufferTooSmall);
+    }
+
+    header.encode(dst);
+    dst[HEADER_LEN..len].copy_from_slice(&payload);
+    Ok(len)
+  }
+
+  fn encode_to_vec_with(&self, ctx: &Cx) -> Result<Vec<u8>, Self::Error> {
+    let (header, payload) = self.compress_with(ctx)?;
+    let mut buf = Vec::with_capacity(HEADER_LEN + payload.len());
+    buf.resize(HEADER_LEN, 0);
+    header.encode(&mut buf);
+    buf.extend_from_slice(&payload);
+    Ok(buf)
+  }
+
+  fn encoded_len_with(&self, ctx: &Cx) -> usize {
+    match self.compress_with(ctx) {
+      Ok((_, payload)) => HEADER_LEN + payload.len(),
+      Err(_) => HEADER_LEN + self.value.encoded_len_with(ctx),
+    }
+  }
+
+  fn decode_with(ctx: &Cx, src: &[u8]) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized,
+  {
+    Self::decode_message(src, &mut DecodeBudget::default(), |src, _| {
+      T::decode_with(ctx, src)
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn membership() -> Vec<u8> {
+    b"node-1:alive;".repeat(100)
+  }
+
+  #[cfg(feature = "lz4")]
+  test_transformable!(Compressed<Vec<u8>, crate::compression::Lz4> => test_compressed_lz4_transformable(Compressed::new(membership())));
+  #[cfg(feature = "zstd")]
+  test_transformable!(Compressed<Vec<u8>, crate::compression::Zstd> => test_compressed_zstd_transformable(Compressed::new(membership())));
+  #[cfg(feature = "snappy")]
+  test_transformable!(Compressed<Vec<u8>, crate::compression::Snappy> => test_compressed_snappy_transformable(Compressed::new(membership())));
+
+  /// A run-length encoding of the bytes, as `(count, byte)` pairs.
+  #[derive(Debug, PartialEq, Eq)]
+  struct Rle;
+
+  impl Algorithm for Rle {
+    const ID: u8 = 9;
+    const NAME: &'static str = "rle";
+
+    fn compress(src: &[u8]) -> Result<Vec<u8>, CompressionError> {
+      let mut dst = Vec::new();
+      let mut rest = src;
+      while let Some(&byte) = rest.first() {
+        let count = rest
+          .iter()
+          .take(u8::MAX as usize)
+          .take_while(|b| **b == byte)
+          .count();
+        dst.extend_from_slice(&[count as u8, byte]);
+        rest = &rest[count..];
+      }
+      Ok(dst)
+    }
+
+    fn decompress(src: &[u8], dst: &mut [u8]) -> Result<usize, CompressionError> {
+      let mut written = 0;
+      for pair in src.chunks(2) {
+        let [count, byte] = *pair else {
+          return Err(CompressionError::Decompress(Self::NAME));
+        };
+        let run = dst
+          .get_mut(written..written + count as usize)
+          .ok_or(CompressionError::Decompress(Self::NAME))?;
+        run.fill(byte);
+        written += run.len();
+      }
+      Ok(written)
+    }
+  }
+
+  test_transformable!(Compressed<Vec<u8>, Rle> => test_compressed_custom_transformable(Compressed::new(std::vec![7; 1024])));
+
+  #[test]
+  fn test_custom_algorithm() {
+    let val = Compressed::<Vec<u8>, Rle>::new(std::vec![7; 1024]);
+    let encoded = Transformable::encode_to_vec(&val).unwrap();
+    assert_eq!(encoded[0], Rle::ID);
+    assert!(encoded.len() < 64);
+    let (readed, decoded) = <Compressed<Vec<u8>, Rle> as Transformable>::decode(&encoded).unwrap();
+    assert_eq!(readed, encoded.len());
+    assert_eq!(decoded.into_inner(), std::vec![7; 1024]);
+
+    // the context is passed down to the value, which ignores it
+    assert_eq!(val.encode_to_vec_with(&()).unwrap(), encoded);
+    assert_eq!(val.encoded_len_with(&()), encoded.len());
+    let (readed, decoded) = Compressed::<Vec<u8>, Rle>::decode_with(&(), &encoded).unwrap();
+    assert_eq!((readed, decoded), (encoded.len(), val));
+  }
+
+  #[test]
+  fn test_hasher() {
+    /// Records the writes fed into it.
+    #[derive(Default)]
+    struct Recorder(Vec<Vec<u8>>);
+
+    impl core::hash::Hasher for Recorder {
+      fn finish(&self) -> u64 {
+        0
+      }
+
+      fn write(&mut self, bytes: &[u8]) {
+        self.0.push(bytes.to_vec());
+      }
+    }
+
+    // does not compress with RLE, so the value is stored
+    let val = Compressed::<Vec<u8>, Rle>::new((0..=255).cycle().take(1024).collect());
+    let encoded = Transformable::encode_to_vec(&val).unwrap();
+    assert!(encoded.len() > crate::MAX_INLINED_BYTES);
+
+    // the header and the payload are streamed into the hasher, without encoding them again
+    let mut hasher = Recorder::default();
+    assert_eq!(val.encode_to_hasher(&mut hasher).unwrap(), encoded.len());
+    assert_eq!(hasher.0.len(), 2);
+    assert_eq!(hasher.0.concat(), encoded);
+  }
+
+  #[test]
+  fn test_error_offset() {
+    let val = Compressed::<Vec<u8>, Rle>::new(std::vec![1, 2, 3]);
+    let mut encoded = Transformable::encode_to_vec(&val).unwrap();
+    assert_eq!(encoded[0], STORED);
+
+    // the stored value claims one byte more than it has
+    encoded[HEADER_LEN + 3] = 4;
+    let err = <Compressed<Vec<u8>, Rle> as Transformable>::decode(&encoded).unwrap_err();
+    assert!(matches!(
+      err,
+      CompressedTransformError::Decode {
+        offset: HEADER_LEN,
+        ..
+      }
+    ));
+    let err = TransformError::from(err);
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    assert_eq!(err.offset(), Some(HEADER_LEN));
+  }
+
+  #[cfg(feature = "lz4")]
+  mod lz4 {
+    use super::*;
+    use crate::compression::Lz4;
+    use std::string::String;
+
+    test_transformable!(Compressed<String, Lz4> => test_compressed_stored_transformable(Compressed::new(String::from("hello world"))));
+    test_transformable!(Compressed<u64, Lz4, 0> => test_compressed_incompressible_transformable(Compressed::new(rand::random())));
+
+    #[test]
+    fn test_threshold() {
+      let small = Compressed::<String, Lz4>::new(String::from("hello world"));
+      let encoded = Transformable::encode_to_vec(&small).unwrap();
+      assert_eq!(encoded[0], STORED);
+      assert_eq!(encoded.len(), HEADER_LEN + 4 + 11);
+
+      let large = Compressed::<Vec<u8>, Lz4>::new(membership());
+      let encoded = Transformable::encode_to_vec(&large).unwrap();
+      assert_eq!(encoded[0], Lz4::ID);
+      assert!(encoded.len() < Transformable::encoded_len(&membership()));
+      assert_eq!(Transformable::encoded_len(&large), encoded.len());
+    }
+
+    #[test]
+    fn test_zip_bomb() {
+      let bomb = Compressed::<Vec<u8>, Lz4>::new(std::vec![0; 1024 * 1024]);
+      let encoded = Transformable::encode_to_vec(&bomb).unwrap();
+      assert!(encoded.len() < 8 * 1024);
+
+      let mut budget =
+        DecodeBudget::new(DecodeLimits::unlimited().with_max_decompressed_len(64 * 1024));
+      assert!(matches!(
+        <Compressed<Vec<u8>, Lz4> as Transformable>::decode_limited(&encoded, &mut budget),
+        Err(CompressedTransformError::Limit(LimitError::DecompressedTooLong {
+          len,
+          limit: 65536,
+        })) if len == 1024 * 1024 + 4
+      ));
+
+      // a lying header is rejected before anything is allocated
+      let mut lying = encoded.clone();
+      lying[1..5].copy_from_slice(&u32::MAX.to_be_bytes());
+      assert!(matches!(
+        <Compressed<Vec<u8>, Lz4> as Transformable>::decode(&lying),
+        Err(CompressedTransformError::Limit(
+          LimitError::DecompressedTooLong { .. }
+        ))
+      ));
+
+      #[cfg(feature = "std")]
+      {
+        let err =
+          <Compressed<Vec<u8>, Lz4> as Transformable>::decode_from_reader(&mut lying.as_slice())
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+      }
+
+      // the decompressed length must match the payload
+      let mut short = encoded.clone();
+      short[1..5].copy_from_slice(&1024u32.to_be_bytes());
+      assert!(matches!(
+        <Compressed<Vec<u8>, Lz4> as Transformable>::decode(&short),
+        Err(CompressedTransformError::Compression(
+          CompressionError::Decompress("lz4")
+        ))
+      ));
+    }
+
+    #[test]
+    fn test_unknown_algorithm() {
+      let val = Compressed::<Vec<u8>, Lz4>::new(membership());
+      let mut encoded = Transformable::encode_to_vec(&val).unwrap();
+      encoded[0] = 0xff;
+      assert!(matches!(
+        <Compressed<Vec<u8>, Lz4> as Transformable>::decode(&encoded),
+        Err(CompressedTransformError::Compression(
+          CompressionError::UnknownAlgorithm(0xff)
+        ))
+      ));
+      assert!(matches!(
+        <Compressed<Vec<u8>, Lz4> as Transformable>::decode(&encoded[..HEADER_LEN - 1]),
+        Err(CompressedTransformError::NotEnoughBytes)
+      ));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_negotiation() {
+      use crate::compression::Zstd;
+
+      // the algorithm of the receiver only matters for encoding
+      let val = Compressed::<Vec<u8>, Zstd>::new(membership());
+      let encoded = Transformable::encode_to_vec(&val).unwrap();
+      assert_eq!(encoded[0], Zstd::ID);
+      let (readed, decoded) =
+        <Compressed<Vec<u8>, Lz4> as Transformable>::decode(&encoded).unwrap();
+      assert_eq!(readed, encoded.len());
+      assert_eq!(decoded.into_inner(), membership());
+    }
+  }
+}
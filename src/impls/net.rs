@@ -25,6 +25,17 @@ pub enum AddrTransformError {
   NotEnoughBytes,
 }
 
+impl crate::DecodeContext for AddrTransformError {}
+
+impl From<AddrTransformError> for TransformError {
+  fn from(err: AddrTransformError) -> Self {
+    match err {
+      AddrTransformError::EncodeBufferTooSmall => Self::from_kind(ErrorKind::BufferTooSmall),
+      AddrTransformError::NotEnoughBytes => Self::from_kind(ErrorKind::UnexpectedEof),
+    }
+  }
+}
+
 #[cfg(not(feature = "std"))]
 impl core::fmt::Display for AddrTransformError {
   fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
@@ -42,7 +53,10 @@ impl AddrTransformError {
   fn from_bytes_error(err: BytesTransformError) -> Self {
     match err {
       BytesTransformError::EncodeBufferTooSmall => Self::EncodeBufferTooSmall,
-      BytesTransformError::NotEnoughBytes => Self::NotEnoughBytes,
+      // octets are fixed-size arrays, which are neither allocated nor checked against decode limits
+      BytesTransformError::NotEnoughBytes
+      | BytesTransformError::Limit(_)
+      | BytesTransformError::AllocationFailed { .. } => Self::NotEnoughBytes,
     }
   }
 }
@@ -107,6 +121,15 @@ macro_rules! impl_socket_addr {
         Ok(($addr_size + PORT_SIZE, FromIP::from(ip, port)))
       }
 
+      #[cfg(feature = "std")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+      fn is_incomplete(err: &Self::Error) -> bool
+      where
+        Self: Sized,
+      {
+        matches!(err, AddrTransformError::NotEnoughBytes)
+      }
+
       #[cfg(feature = "std")]
       #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
       fn decode_from_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<(usize, Self)>
@@ -142,7 +165,61 @@ macro_rules! impl_socket_addr {
 
         Ok(($addr_size + PORT_SIZE, FromIP::from(ip, port)))
       }
+
+      #[cfg(feature = "owned-io")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+      async fn decode_from_owned_reader<R: crate::OwnedRead>(
+        reader: &mut R,
+        buf: Vec<u8>,
+      ) -> (std::io::Result<(usize, Self)>, Vec<u8>)
+      where
+        Self: Sized,
+      {
+        crate::owned::decode_fixed_from::<_, _, { $addr_size + PORT_SIZE }>(reader, buf).await
+      }
+
+      #[cfg(feature = "embedded-io")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+      fn encode_to_embedded_writer<W: embedded_io::Write>(
+        &self,
+        writer: &mut W,
+      ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+        crate::embedded::encode_inlined_to(self, writer)
+      }
+
+      #[cfg(feature = "embedded-io-async")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+      async fn encode_to_embedded_async_writer<W: embedded_io_async::Write>(
+        &self,
+        writer: &mut W,
+      ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+        crate::embedded::encode_inlined_to_async(self, writer).await
+      }
+
+      #[cfg(feature = "embedded-io")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+      fn decode_from_embedded_reader<R: embedded_io::Read>(
+        reader: &mut R,
+      ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+      where
+        Self: Sized,
+      {
+        crate::embedded::decode_fixed_from::<_, _, { $addr_size + PORT_SIZE }>(reader)
+      }
+
+      #[cfg(feature = "embedded-io-async")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+      async fn decode_from_embedded_async_reader<R: embedded_io_async::Read>(
+        reader: &mut R,
+      ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+      where
+        Self: Sized,
+      {
+        crate::embedded::decode_fixed_from_async::<_, _, { $addr_size + PORT_SIZE }>(reader).await
+      }
     }
+
+    impl ContextFree for $ty {}
   };
 }
 
@@ -210,6 +287,15 @@ macro_rules! impl_addr {
         Ok((len, Self::from(octets)))
       }
 
+      #[cfg(feature = "std")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+      fn is_incomplete(err: &Self::Error) -> bool
+      where
+        Self: Sized,
+      {
+        matches!(err, AddrTransformError::NotEnoughBytes)
+      }
+
       #[cfg(feature = "std")]
       #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
       fn decode_from_reader<R: std::io::Read>(src: &mut R) -> std::io::Result<(usize, Self)>
@@ -232,7 +318,61 @@ macro_rules! impl_addr {
           .await
           .map(|(len, octets)| (len, Self::from(octets)))
       }
+
+      #[cfg(feature = "owned-io")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+      async fn decode_from_owned_reader<R: crate::OwnedRead>(
+        reader: &mut R,
+        buf: Vec<u8>,
+      ) -> (std::io::Result<(usize, Self)>, Vec<u8>)
+      where
+        Self: Sized,
+      {
+        crate::owned::decode_fixed_from::<_, _, $addr_size>(reader, buf).await
+      }
+
+      #[cfg(feature = "embedded-io")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+      fn encode_to_embedded_writer<W: embedded_io::Write>(
+        &self,
+        writer: &mut W,
+      ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+        crate::embedded::encode_inlined_to(self, writer)
+      }
+
+      #[cfg(feature = "embedded-io-async")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+      async fn encode_to_embedded_async_writer<W: embedded_io_async::Write>(
+        &self,
+        writer: &mut W,
+      ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+        crate::embedded::encode_inlined_to_async(self, writer).await
+      }
+
+      #[cfg(feature = "embedded-io")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+      fn decode_from_embedded_reader<R: embedded_io::Read>(
+        reader: &mut R,
+      ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+      where
+        Self: Sized,
+      {
+        crate::embedded::decode_fixed_from::<_, _, $addr_size>(reader)
+      }
+
+      #[cfg(feature = "embedded-io-async")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+      async fn decode_from_embedded_async_reader<R: embedded_io_async::Read>(
+        reader: &mut R,
+      ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+      where
+        Self: Sized,
+      {
+        crate::embedded::decode_fixed_from_async::<_, _, $addr_size>(reader).await
+      }
     }
+
+    impl ContextFree for $ty {}
   };
 }
 
@@ -1,6 +1,9 @@
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 use super::Transformable;
+#[cfg(feature = "embedded-io")]
+use crate::EmbeddedIoError;
+use crate::{ContextFree, ErrorKind, TransformError};
 
 #[cfg(feature = "std")]
 use crate::utils::invalid_data;
@@ -28,6 +31,22 @@ pub enum IpAddrTransformError {
   NotEnoughBytes(&'static str),
 }
 
+impl crate::DecodeContext for IpAddrTransformError {}
+
+impl From<IpAddrTransformError> for TransformError {
+  fn from(err: IpAddrTransformError) -> Self {
+    match err {
+      IpAddrTransformError::EncodeBufferTooSmall => Self::from_kind(ErrorKind::BufferTooSmall),
+      IpAddrTransformError::NotEnoughBytes(msg) => {
+        Self::with_message(ErrorKind::UnexpectedEof, msg)
+      }
+      err @ IpAddrTransformError::UnknownAddressFamily(_) => {
+        Self::wrap(ErrorKind::InvalidValue, err)
+      }
+    }
+  }
+}
+
 const MIN_ENCODED_LEN: usize = TAG_SIZE + V4_SIZE;
 const V6_ENCODED_LEN: usize = TAG_SIZE + V6_SIZE;
 const V6_SIZE: usize = 16;
@@ -139,6 +158,15 @@ impl Transformable for IpAddr {
     }
   }
 
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn is_incomplete(err: &Self::Error) -> bool
+  where
+    Self: Sized,
+  {
+    matches!(err, IpAddrTransformError::NotEnoughBytes(_))
+  }
+
   #[cfg(feature = "std")]
   #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
   fn decode_from_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<(usize, Self)>
@@ -200,8 +228,126 @@ impl Transformable for IpAddr {
       ))),
     }
   }
+
+  #[cfg(feature = "owned-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+  async fn decode_from_owned_reader<R: crate::OwnedRead>(
+    reader: &mut R,
+    mut buf: Vec<u8>,
+  ) -> (std::io::Result<(usize, Self)>, Vec<u8>)
+  where
+    Self: Sized,
+  {
+    buf.clear();
+    let (res, buf) = reader.read_exact_owned(buf, MIN_ENCODED_LEN).await;
+    if let Err(e) = res {
+      return (Err(e), buf);
+    }
+
+    match buf[0] {
+      4 => {
+        let ip = Ipv4Addr::new(buf[1], buf[2], buf[3], buf[4]);
+        (Ok((MIN_ENCODED_LEN, IpAddr::from(ip))), buf)
+      }
+      6 => {
+        let (res, buf) = reader
+          .read_exact_owned(buf, V6_ENCODED_LEN - MIN_ENCODED_LEN)
+          .await;
+        if let Err(e) = res {
+          return (Err(e), buf);
+        }
+
+        let mut ipv6 = [0; V6_SIZE];
+        ipv6.copy_from_slice(&buf[TAG_SIZE..]);
+        (
+          Ok((V6_ENCODED_LEN, IpAddr::from(Ipv6Addr::from(ipv6)))),
+          buf,
+        )
+      }
+      val => (
+        Err(invalid_data(IpAddrTransformError::UnknownAddressFamily(
+          val,
+        ))),
+        buf,
+      ),
+    }
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn encode_to_embedded_writer<W: embedded_io::Write>(
+    &self,
+    writer: &mut W,
+  ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+    crate::embedded::encode_inlined_to(self, writer)
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn encode_to_embedded_async_writer<W: embedded_io_async::Write>(
+    &self,
+    writer: &mut W,
+  ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+    crate::embedded::encode_inlined_to_async(self, writer).await
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn decode_from_embedded_reader<R: embedded_io::Read>(
+    reader: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    let mut buf = [0; V6_ENCODED_LEN];
+    reader.read_exact(&mut buf[..MIN_ENCODED_LEN])?;
+    match buf[0] {
+      4 => {
+        let ip = Ipv4Addr::new(buf[1], buf[2], buf[3], buf[4]);
+        Ok((MIN_ENCODED_LEN, IpAddr::from(ip)))
+      }
+      6 => {
+        reader.read_exact(&mut buf[MIN_ENCODED_LEN..])?;
+        let mut ipv6 = [0; V6_SIZE];
+        ipv6.copy_from_slice(&buf[TAG_SIZE..]);
+        Ok((V6_ENCODED_LEN, IpAddr::from(Ipv6Addr::from(ipv6))))
+      }
+      val => Err(EmbeddedIoError::Transform(
+        IpAddrTransformError::UnknownAddressFamily(val),
+      )),
+    }
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn decode_from_embedded_async_reader<R: embedded_io_async::Read>(
+    reader: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    let mut buf = [0; V6_ENCODED_LEN];
+    reader.read_exact(&mut buf[..MIN_ENCODED_LEN]).await?;
+    match buf[0] {
+      4 => {
+        let ip = Ipv4Addr::new(buf[1], buf[2], buf[3], buf[4]);
+        Ok((MIN_ENCODED_LEN, IpAddr::from(ip)))
+      }
+      6 => {
+        reader.read_exact(&mut buf[MIN_ENCODED_LEN..]).await?;
+        let mut ipv6 = [0; V6_SIZE];
+        ipv6.copy_from_slice(&buf[TAG_SIZE..]);
+        Ok((V6_ENCODED_LEN, IpAddr::from(Ipv6Addr::from(ipv6))))
+      }
+      val => Err(EmbeddedIoError::Transform(
+        IpAddrTransformError::UnknownAddressFamily(val),
+      )),
+    }
+  }
 }
 
+impl ContextFree for IpAddr {}
+
 test_transformable!(IpAddr => test_socket_addr_v4_transformable(
   IpAddr::V4(
     Ipv4Addr::new(127, 0, 0, 1),
@@ -1,6 +1,9 @@
 use std::net::SocketAddr;
 
 use super::Transformable;
+#[cfg(feature = "embedded-io")]
+use crate::EmbeddedIoError;
+use crate::{ContextFree, ErrorKind, TransformError};
 
 #[cfg(feature = "std")]
 use crate::utils::invalid_data;
@@ -25,6 +28,20 @@ pub enum SocketAddrTransformError {
   NotEnoughBytes,
 }
 
+impl crate::DecodeContext for SocketAddrTransformError {}
+
+impl From<SocketAddrTransformError> for TransformError {
+  fn from(err: SocketAddrTransformError) -> Self {
+    match err {
+      SocketAddrTransformError::EncodeBufferTooSmall => Self::from_kind(ErrorKind::BufferTooSmall),
+      SocketAddrTransformError::NotEnoughBytes => Self::from_kind(ErrorKind::UnexpectedEof),
+      err @ SocketAddrTransformError::UnknownAddressFamily(_) => {
+        Self::wrap(ErrorKind::InvalidValue, err)
+      }
+    }
+  }
+}
+
 const MIN_ENCODED_LEN: usize = TAG_SIZE + V4_SIZE + PORT_SIZE;
 const V6_ENCODED_LEN: usize = TAG_SIZE + V6_SIZE + PORT_SIZE;
 const V6_SIZE: usize = 16;
@@ -141,6 +158,15 @@ impl Transformable for SocketAddr {
     }
   }
 
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn is_incomplete(err: &Self::Error) -> bool
+  where
+    Self: Sized,
+  {
+    matches!(err, SocketAddrTransformError::NotEnoughBytes)
+  }
+
   #[cfg(feature = "std")]
   #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
   fn decode_from_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<(usize, Self)>
@@ -215,8 +241,111 @@ impl Transformable for SocketAddr {
       )),
     }
   }
+
+  #[cfg(feature = "owned-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+  async fn decode_from_owned_reader<R: crate::OwnedRead>(
+    reader: &mut R,
+    mut buf: Vec<u8>,
+  ) -> (std::io::Result<(usize, Self)>, Vec<u8>)
+  where
+    Self: Sized,
+  {
+    buf.clear();
+    let (res, buf) = reader.read_exact_owned(buf, MIN_ENCODED_LEN).await;
+    if let Err(e) = res {
+      return (Err(e), buf);
+    }
+
+    let (res, buf) = match buf[0] {
+      4 => (Ok(()), buf),
+      6 => {
+        reader
+          .read_exact_owned(buf, V6_ENCODED_LEN - MIN_ENCODED_LEN)
+          .await
+      }
+      val => {
+        return (
+          Err(invalid_data(
+            SocketAddrTransformError::UnknownAddressFamily(val),
+          )),
+          buf,
+        )
+      }
+    };
+    if let Err(e) = res {
+      return (Err(e), buf);
+    }
+
+    let res = Self::decode(&buf).map_err(invalid_data);
+    (res, buf)
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn encode_to_embedded_writer<W: embedded_io::Write>(
+    &self,
+    writer: &mut W,
+  ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+    crate::embedded::encode_inlined_to(self, writer)
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn encode_to_embedded_async_writer<W: embedded_io_async::Write>(
+    &self,
+    writer: &mut W,
+  ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+    crate::embedded::encode_inlined_to_async(self, writer).await
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn decode_from_embedded_reader<R: embedded_io::Read>(
+    reader: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    let mut buf = [0; V6_ENCODED_LEN];
+    reader.read_exact(&mut buf[..MIN_ENCODED_LEN])?;
+    match buf[0] {
+      4 => {}
+      6 => reader.read_exact(&mut buf[MIN_ENCODED_LEN..])?,
+      val => {
+        return Err(EmbeddedIoError::Transform(
+          SocketAddrTransformError::UnknownAddressFamily(val),
+        ))
+      }
+    }
+    Self::decode(&buf).map_err(EmbeddedIoError::Transform)
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn decode_from_embedded_async_reader<R: embedded_io_async::Read>(
+    reader: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    let mut buf = [0; V6_ENCODED_LEN];
+    reader.read_exact(&mut buf[..MIN_ENCODED_LEN]).await?;
+    match buf[0] {
+      4 => {}
+      6 => reader.read_exact(&mut buf[MIN_ENCODED_LEN..]).await?,
+      val => {
+        return Err(EmbeddedIoError::Transform(
+          SocketAddrTransformError::UnknownAddressFamily(val),
+        ))
+      }
+    }
+    Self::decode(&buf).map_err(EmbeddedIoError::Transform)
+  }
 }
 
+impl ContextFree for SocketAddr {}
+
 test_transformable!(SocketAddr => test_socket_addr_v4_transformable(
   SocketAddr::V4(std::net::SocketAddrV4::new(
     std::net::Ipv4Addr::new(127, 0, 0, 1),
@@ -9,6 +9,17 @@ pub enum NumberTransformError {
   NotEnoughBytes,
 }
 
+impl crate::DecodeContext for NumberTransformError {}
+
+impl From<NumberTransformError> for TransformError {
+  fn from(err: NumberTransformError) -> Self {
+    match err {
+      NumberTransformError::EncodeBufferTooSmall => Self::from_kind(ErrorKind::BufferTooSmall),
+      NumberTransformError::NotEnoughBytes => Self::from_kind(ErrorKind::UnexpectedEof),
+    }
+  }
+}
+
 impl core::fmt::Display for NumberTransformError {
   fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     match self {
@@ -72,6 +83,12 @@ macro_rules! impl_number_based_id {
           Ok((SIZE, id))
         }
 
+        #[cfg(feature = "std")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+        fn is_incomplete(err: &Self::Error) -> bool where Self: Sized {
+          matches!(err, NumberTransformError::NotEnoughBytes)
+        }
+
         #[cfg(feature = "std")]
         #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
         fn decode_from_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<(usize, Self)> where Self: Sized {
@@ -100,8 +117,72 @@ macro_rules! impl_number_based_id {
           let id = <$ty>::from_network_endian(&buf);
           Ok((SIZE, id))
         }
+
+        #[cfg(feature = "owned-io")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+        async fn decode_from_owned_reader<R: crate::OwnedRead>(
+          reader: &mut R,
+          buf: Vec<u8>,
+        ) -> (std::io::Result<(usize, Self)>, Vec<u8>)
+        where
+          Self: Sized,
+        {
+          crate::owned::decode_fixed_from::<_, _, { core::mem::size_of::<$ty>() }>(reader, buf).await
+        }
+
+        #[cfg(feature = "embedded-io")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+        fn encode_to_embedded_writer<W: embedded_io::Write>(
+          &self,
+          writer: &mut W,
+        ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+          writer.write_all(self.to_network_endian().as_ref()).map(|_| core::mem::size_of::<$ty>()).map_err(EmbeddedIoError::Io)
+        }
+
+        #[cfg(feature = "embedded-io-async")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+        async fn encode_to_embedded_async_writer<W: embedded_io_async::Write>(
+          &self,
+          writer: &mut W,
+        ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+          writer.write_all(self.to_network_endian().as_ref()).await.map(|_| core::mem::size_of::<$ty>()).map_err(EmbeddedIoError::Io)
+        }
+
+        #[cfg(feature = "embedded-io")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+        fn decode_from_embedded_reader<R: embedded_io::Read>(
+          reader: &mut R,
+        ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+        where
+          Self: Sized,
+        {
+          const SIZE: usize = core::mem::size_of::<$ty>();
+
+          let mut buf = [0u8; SIZE];
+          reader.read_exact(&mut buf)?;
+          let id = <$ty>::from_network_endian(&buf);
+          Ok((SIZE, id))
+        }
+
+        #[cfg(feature = "embedded-io-async")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+        async fn decode_from_embedded_async_reader<R: embedded_io_async::Read>(
+          reader: &mut R,
+        ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+        where
+          Self: Sized,
+        {
+          const SIZE: usize = core::mem::size_of::<$ty>();
+
+          let mut buf = [0u8; SIZE];
+          reader.read_exact(&mut buf).await?;
+          let id = <$ty>::from_network_endian(&buf);
+          Ok((SIZE, id))
+        }
       }
 
+      impl ContextFree for $ty {}
+
       #[cfg(test)]
       paste::paste! {
         test_transformable!($ty => [< test _ $ty _ transformable >](rand::random()));
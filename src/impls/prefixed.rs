@@ -0,0 +1,483 @@
+use super::*;
+
+use ::alloc::sync::Arc;
+#[cfg(not(feature = "std"))]
+use ::alloc::{boxed::Box, string::String};
+
+use core::marker::PhantomData;
+
+#[cfg(any(feature = "std", feature = "embedded-io"))]
+use crate::prefix::MAX_PREFIX_LEN;
+use crate::prefix::{LengthPrefix, PrefixError, U32};
+#[cfg(feature = "std")]
+use crate::utils::invalid_data;
+
+/// The error type for errors that get returned when encoding or decoding [`Prefixed`] fails.
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum PrefixedTransformError {
+  /// Returned when the buffer is too small to encode.
+  #[cfg_attr(feature = "std", error(
+    "buffer is too small, use `Transformable::encoded_len` to pre-allocate a buffer with enough space"
+  ))]
+  EncodeBufferTooSmall,
+  /// Returned when the bytes are corrupted.
+  #[cfg_attr(feature = "std", error("not enough bytes to decode"))]
+  NotEnoughBytes,
+  /// Returned when encoding or decoding the length prefix fails.
+  #[cfg_attr(feature = "std", error("{0}"))]
+  Prefix(#[cfg_attr(feature = "std", from)] PrefixError),
+  /// Returned when the decoding meet utf8 error.
+  #[cfg_attr(feature = "std", error("{0}"))]
+  Utf8Error(#[cfg_attr(feature = "std", from)] core::str::Utf8Error),
+  /// Returned when a [`DecodeLimits`] is exceeded.
+  #[cfg_attr(feature = "std", error("{0}"))]
+  Limit(#[cfg_attr(feature = "std", from)] LimitError),
+  /// Returned when the memory for the decoded value can not be allocated.
+  #[cfg_attr(feature = "std", error("failed to allocate {requested} bytes"))]
+  AllocationFailed {
+    /// The number of bytes which failed to be allocated.
+    requested: usize,
+  },
+}
+
+impl crate::DecodeContext for PrefixedTransformError {}
+
+impl From<PrefixedTransformError> for TransformError {
+  fn from(err: PrefixedTransformError) -> Self {
+    match err {
+      PrefixedTransformError::EncodeBufferTooSmall => Self::from_kind(ErrorKind::BufferTooSmall),
+      PrefixedTransformError::NotEnoughBytes => Self::from_kind(ErrorKind::UnexpectedEof),
+      PrefixedTransformError::Prefix(e) => e.into(),
+      PrefixedTransformError::Limit(e) => e.into(),
+      err @ PrefixedTransformError::Utf8Error(_) => Self::wrap(ErrorKind::InvalidValue, err),
+      err @ PrefixedTransformError::AllocationFailed { .. } => {
+        Self::wrap(ErrorKind::LimitExceeded, err)
+      }
+    }
+  }
+}
+
+#[cfg(not(feature = "std"))]
+impl core::convert::From<PrefixError> for PrefixedTransformError {
+  fn from(err: PrefixError) -> Self {
+    Self::Prefix(err)
+  }
+}
+
+#[cfg(not(feature = "std"))]
+impl core::convert::From<core::str::Utf8Error> for PrefixedTransformError {
+  fn from(err: core::str::Utf8Error) -> Self {
+    Self::Utf8Error(err)
+  }
+}
+
+#[cfg(not(feature = "std"))]
+impl core::convert::From<LimitError> for PrefixedTransformError {
+  fn from(err: LimitError) -> Self {
+    Self::Limit(err)
+  }
+}
+
+#[cfg(not(feature = "std"))]
+impl core::fmt::Display for PrefixedTransformError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::EncodeBufferTooSmall => write!(
+        f,
+        "buffer is too small, use `Transformable::encoded_len` to pre-allocate a buffer with enough space"
+      ),
+      Self::NotEnoughBytes => write!(f, "not enough bytes to decode"),
+      Self::Prefix(val) => write!(f, "{val}"),
+      Self::Utf8Error(val) => write!(f, "{val}"),
+      Self::Limit(val) => write!(f, "{val}"),
+      Self::AllocationFailed { requested } => write!(f, "failed to allocate {requested} bytes"),
+    }
+  }
+}
+
+/// A byte or string type which can be wrapped in [`Prefixed`].
+pub trait Payload: Sized + Send + Sync + 'static {
+  /// Returns the bytes of the payload.
+  fn as_payload(&self) -> &[u8];
+
+  /// Creates the value from the bytes of the payload.
+  fn from_payload(payload: Vec<u8>) -> Result<Self, PrefixedTransformError>;
+}
+
+macro_rules! impl_bytes_payload {
+  ($($ty:ty), + $(,)?) => {
+    $(
+      impl Payload for $ty {
+        fn as_payload(&self) -> &[u8] {
+          self.as_ref()
+        }
+
+        fn from_payload(payload: Vec<u8>) -> Result<Self, PrefixedTransformError> {
+          Ok(payload.into())
+        }
+      }
+    )*
+  };
+}
+
+macro_rules! impl_string_payload {
+  ($($ty:ty), + $(,)?) => {
+    $(
+      impl Payload for $ty {
+        fn as_payload(&self) -> &[u8] {
+          let src: &str = self.as_ref();
+          src.as_bytes()
+        }
+
+        fn from_payload(payload: Vec<u8>) -> Result<Self, PrefixedTransformError> {
+          core::str::from_utf8(&payload)
+            .map(Self::from)
+            .map_err(Into::into)
+        }
+      }
+    )*
+  };
+}
+
+impl_bytes_payload!(Vec<u8>, Box<[u8]>, Arc<[u8]>);
+#[cfg(feature = "bytes")]
+impl_bytes_payload!(::bytes::Bytes);
+
+impl_string_payload!(Box<str>, Arc<str>);
+#[cfg(feature = "smol_str")]
+impl_string_payload!(smol_str::SmolStr);
+
+impl Payload for String {
+  fn as_payload(&self) -> &[u8] {
+    self.as_bytes()
+  }
+
+  fn from_payload(payload: Vec<u8>) -> Result<Self, PrefixedTransformError> {
+    String::from_utf8(payload).map_err(|e| e.utf8_error().into())
+  }
+}
+
+/// A byte or string payload encoded with the length prefix `P`.
+///
+/// Byte and string types are encoded with a 4 bytes big-endian length prefix ([`U32`]),
+/// `Prefixed` allows to pick a different one, e.g. a single byte [`Varint`](crate::prefix::Varint)
+/// prefix for short strings, or a [`U64`](crate::prefix::U64) prefix for payloads larger than 4 GiB.
+///
+/// `Prefixed<T, U32>` has the same wire format as `T`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Prefixed<T, P = U32> {
+  value: T,
+  _prefix: PhantomData<P>,
+}
+
+impl<T, P> Prefixed<T, P> {
+  /// Wraps the given payload.
+  #[inline]
+  pub const fn new(value: T) -> Self {
+    Self {
+      value,
+      _prefix: PhantomData,
+    }
+  }
+
+  /// Returns a reference to the payload.
+  #[inline]
+  pub const fn get(&self) -> &T {
+    &self.value
+  }
+
+  /// Consumes the wrapper, returning the payload.
+  #[inline]
+  pub fn into_inner(self) -> T {
+    self.value
+  }
+}
+
+impl<T, P> From<T> for Prefixed<T, P> {
+  #[inline]
+  fn from(value: T) -> Self {
+    Self::new(value)
+  }
+}
+
+impl<T, P> core::ops::Deref for Prefixed<T, P> {
+  type Target = T;
+
+  #[inline]
+  fn deref(&self) -> &T {
+    &self.value
+  }
+}
+
+impl<T: Payload, P: LengthPrefix> Prefixed<T, P> {
+  fn from_parts(readed: usize, payload: Vec<u8>) -> Result<(usize, Self), PrefixedTransformError> {
+    T::from_payload(payload).map(|val| (readed, Self::new(val)))
+  }
+}
+
+/// Converts the decoded length prefix to the payload length.
+#[inline]
+fn payload_len(len: u64) -> Result<usize, PrefixError> {
+  usize::try_from(len).map_err(|_| PrefixError::TooLarge(len))
+}
+
+/// Reads the length prefix `$p` with the given read expression, which fills `$buf`.
+///
+/// Evaluates to the decoded `(prefix length, payload length)`.
+#[cfg(any(feature = "std", feature = "embedded-io"))]
+macro_rules! read_prefix {
+  ($p:ty, |$buf:ident| $read:expr) => {{
+    let mut prefix = [0u8; MAX_PREFIX_LEN];
+    let mut filled = 0;
+    loop {
+      let more = <$p>::remaining(&prefix[..filled]);
+      if more == 0 || filled + more > MAX_PREFIX_LEN {
+        break;
+      }
+
+      let $buf = &mut prefix[filled..filled + more];
+      $read;
+      filled += more;
+    }
+    <$p>::decode(&prefix[..filled])
+      .and_then(|(readed, len)| payload_len(len).map(|len| (readed, len)))
+  }};
+}
+
+impl<T: Payload, P: LengthPrefix> Transformable for Prefixed<T, P> {
+  type Error = PrefixedTransformError;
+
+  fn encode(&self, dst: &mut [u8]) -> Result<usize, Self::Error> {
+    let payload = self.value.as_payload();
+    let len = payload.len();
+    let readed = P::encode(len, dst).map_err(|e| match e {
+      PrefixError::BufferTooSmall => Self::Error::EncodeBufferTooSmall,
+      e => e.into(),
+    })?;
+    if dst.len() - readed < len {
+      return Err(Self::Error::EncodeBufferTooSmall);
+    }
+
+    dst[readed..readed + len].copy_from_slice(payload);
+    Ok(readed + len)
+  }
+
+  fn encoded_len(&self) -> usize {
+    let len = self.value.as_payload().len();
+    P::encoded_len(len) + len
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn as_io_slices<'a>(&'a self, slices: &mut GatherList<'a>) -> bool {
+    let payload = self.value.as_payload();
+    let mut prefix = [0u8; MAX_PREFIX_LEN];
+    match P::encode(payload.len(), &mut prefix) {
+      Ok(len) => {
+        slices.push_copied(&prefix[..len]);
+        slices.push(payload);
+        true
+      }
+      Err(_) => false,
+    }
+  }
+
+  fn decode(src: &[u8]) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized,
+  {
+    let (readed, len) = P::decode(src)?;
+    let len = payload_len(len)?;
+    if src.len() - readed < len {
+      return Err(Self::Error::NotEnoughBytes);
+    }
+
+    let buf = try_to_vec(&src[readed..readed + len])
+      .map_err(|_| Self::Error::AllocationFailed { requested: len })?;
+    Self::from_parts(readed + len, buf)
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn is_incomplete(err: &Self::Error) -> bool
+  where
+    Self: Sized,
+  {
+    matches!(
+      err,
+      PrefixedTransformError::NotEnoughBytes
+        | PrefixedTransformError::Prefix(PrefixError::NotEnoughBytes)
+    )
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn decode_from_reader<R: std::io::Read>(src: &mut R) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    <Self as Transformable>::decode_from_reader_limited(src, &mut DecodeBudget::unlimited())
+  }
+
+  #[cfg(feature = "async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+  async fn decode_from_async_reader<R: futures_util::io::AsyncRead + Send + Unpin>(
+    src: &mut R,
+  ) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    <Self as Transformable>::decode_from_async_reader_limited(src, &mut DecodeBudget::unlimited())
+      .await
+  }
+
+  fn decode_limited(src: &[u8], budget: &mut DecodeBudget) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized,
+  {
+    let (readed, len) = P::decode(src)?;
+    budget.charge_bytes(readed, payload_len(len)?)?;
+    <Self as Transformable>::decode(src)
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn decode_from_reader_limited<R: std::io::Read>(
+    src: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    let (readed, len) = read_prefix!(P, |buf| src.read_exact(buf)?).map_err(invalid_data)?;
+    budget.charge_bytes(readed, len).map_err(invalid_data)?;
+    let mut buf = Vec::new();
+    crate::utils::read_exact_to_vec(src, &mut buf, len)?;
+    Self::from_parts(readed + len, buf).map_err(invalid_data)
+  }
+
+  #[cfg(feature = "async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+  async fn decode_from_async_reader_limited<R: futures_util::io::AsyncRead + Send + Unpin>(
+    src: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    use futures_util::io::AsyncReadExt;
+
+    let (readed, len) = read_prefix!(P, |buf| src.read_exact(buf).await?).map_err(invalid_data)?;
+    budget.charge_bytes(readed, len).map_err(invalid_data)?;
+    let mut buf = Vec::new();
+    crate::utils::read_exact_to_vec_async(src, &mut buf, len).await?;
+    Self::from_parts(readed + len, buf).map_err(invalid_data)
+  }
+
+  #[cfg(feature = "owned-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+  async fn decode_from_owned_reader<R: crate::OwnedRead>(
+    reader: &mut R,
+    mut buf: Vec<u8>,
+  ) -> (std::io::Result<(usize, Self)>, Vec<u8>)
+  where
+    Self: Sized,
+  {
+    buf.clear();
+    loop {
+      let more = P::remaining(&buf);
+      if more == 0 || buf.len() + more > MAX_PREFIX_LEN {
+        break;
+      }
+
+      let res;
+      (res, buf) = reader.read_exact_owned(buf, more).await;
+      if let Err(e) = res {
+        return (Err(e), buf);
+      }
+    }
+
+    let (readed, len) =
+      match P::decode(&buf).and_then(|(readed, len)| payload_len(len).map(|len| (readed, len))) {
+        Ok(res) => res,
+        Err(e) => return (Err(invalid_data(e)), buf),
+      };
+
+    buf.clear();
+    let (res, buf) = reader.read_exact_owned(buf, len).await;
+    if let Err(e) = res {
+      return (Err(e), buf);
+    }
+
+    let res = Self::from_parts(readed + len, buf.to_vec()).map_err(invalid_data);
+    (res, buf)
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn decode_from_embedded_reader<R: embedded_io::Read>(
+    src: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    let (readed, len) = read_prefix!(P, |buf| src.read_exact(buf)?)
+      .map_err(|e| EmbeddedIoError::Transform(e.into()))?;
+    let mut buf = crate::embedded::try_zeroed(len)?;
+    src.read_exact(&mut buf)?;
+    Self::from_parts(readed + len, buf).map_err(EmbeddedIoError::Transform)
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn decode_from_embedded_async_reader<R: embedded_io_async::Read>(
+    src: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    let (readed, len) = read_prefix!(P, |buf| src.read_exact(buf).await?)
+      .map_err(|e| EmbeddedIoError::Transform(e.into()))?;
+    let mut buf = crate::embedded::try_zeroed(len)?;
+    src.read_exact(&mut buf).await?;
+    Self::from_parts(readed + len, buf).map_err(EmbeddedIoError::Transform)
+  }
+}
+
+impl<T: Payload, P: LengthPrefix> ContextFree for Prefixed<T, P> {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::prefix::{Varint, U16, U64, U8};
+
+  test_transformable!(Prefixed<String, Varint> => test_prefixed_varint_string_transformable(Prefixed::new(String::from("hello world"))));
+  test_transformable!(Prefixed<Vec<u8>, U8> => test_prefixed_u8_vec_transformable(Prefixed::new(b"hello world".to_vec())));
+  test_transformable!(Prefixed<Box<str>, U16> => test_prefixed_u16_box_str_transformable(Prefixed::new(Box::from("hello world"))));
+  test_transformable!(Prefixed<Arc<[u8]>, U64> => test_prefixed_u64_arc_u8_transformable(Prefixed::new(Arc::from(b"hello world".to_vec()))));
+  test_transformable!(Prefixed<Vec<u8>, Varint> => test_prefixed_varint_large_vec_transformable(Prefixed::new(std::vec![7; 1000])));
+
+  #[test]
+  fn test_prefixed_u32_compatible() {
+    let val = String::from("hello world");
+    let prefixed = Prefixed::<_, U32>::new(val.clone());
+    assert_eq!(
+      Transformable::encode_to_vec(&prefixed).unwrap(),
+      Transformable::encode_to_vec(&val).unwrap()
+    );
+  }
+
+  #[test]
+  fn test_prefixed_too_large() {
+    let val = Prefixed::<_, U8>::new(std::vec![0u8; 256]);
+    let mut buf = std::vec![0; Transformable::encoded_len(&val)];
+    assert!(matches!(
+      Transformable::encode(&val, &mut buf),
+      Err(PrefixedTransformError::Prefix(PrefixError::TooLarge(256)))
+    ));
+
+    let val = Prefixed::<_, Varint>::new(String::from("hi"));
+    assert_eq!(Transformable::encoded_len(&val), 3);
+  }
+}
@@ -0,0 +1,347 @@
+use super::*;
+
+use aead::{
+  generic_array::typenum::Unsigned, rand_core::RngCore, AeadCore, AeadInPlace, Nonce, OsRng, Tag,
+};
+use byteorder::{ByteOrder, NetworkEndian};
+
+use crate::{keyring::Keyring, TrailingBytes, TransformableWith};
+
+/// The length of the header: the key id and the length of the ciphertext.
+const HEADER_LEN: usize = 1 + 4;
+
+/// The error type for errors that get returned when sealing or opening [`Sealed`] fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SealedTransformError<E> {
+  /// Returned when the buffer is too small to encode.
+  EncodeBufferTooSmall,
+  /// Returned when the buffer does not contain the whole ciphertext.
+  NotEnoughBytes,
+  /// Returned when the encoded value is longer than `u32::MAX` bytes.
+  TooLarge(usize),
+  /// Returned when encoding or decoding the value fails.
+  Transform(E),
+  /// Returned when the value is sealed with a key which is not in the keyring.
+  UnknownKey(u8),
+  /// Returned when the ciphertext, or its header, has been tampered with, or is sealed with a
+  /// different key with the same id.
+  AuthenticationFailed,
+  /// Returned when the cipher fails to encrypt the value.
+  EncryptionFailed,
+  /// Returned when the decrypted value does not consume all of the decrypted bytes.
+  TrailingBytes(TrailingBytes),
+  /// Returned when the memory for the decrypted bytes can not be allocated.
+  AllocationFailed {
+    /// The number of bytes which failed to be allocated.
+    requested: usize,
+  },
+}
+
+forward_decode_context!(SealedTransformError);
+
+impl<E: Into<TransformError>> From<SealedTransformError<E>> for TransformError {
+  fn from(err: SealedTransformError<E>) -> Self {
+    match err {
+      SealedTransformError::EncodeBufferTooSmall => Self::from_kind(ErrorKind::BufferTooSmall),
+      SealedTransformError::NotEnoughBytes => Self::from_kind(ErrorKind::UnexpectedEof),
+      SealedTransformError::TooLarge(len) => Self::wrap(
+        ErrorKind::InvalidValue,
+        SealedTransformError::<core::convert::Infallible>::TooLarge(len),
+      ),
+      SealedTransformError::Transform(e) => e.into(),
+      SealedTransformError::UnknownKey(id) => Self::wrap(
+        ErrorKind::InvalidValue,
+        SealedTransformError::<core::convert::Infallible>::UnknownKey(id),
+      ),
+      SealedTransformError::AuthenticationFailed => Self::wrap(
+        ErrorKind::InvalidValue,
+        SealedTransformError::<core::convert::Infallible>::AuthenticationFailed,
+      ),
+      SealedTransformError::EncryptionFailed => Self::wrap(
+        ErrorKind::Custom,
+        SealedTransformError::<core::convert::Infallible>::EncryptionFailed,
+      ),
+      SealedTransformError::TrailingBytes(e) => e.into(),
+      SealedTransformError::AllocationFailed { requested } => Self::wrap(
+        ErrorKind::LimitExceeded,
+        SealedTransformError::<core::convert::Infallible>::AllocationFailed { requested },
+      ),
+    }
+  }
+}
+
+impl<E> From<TrailingBytes> for SealedTransformError<E> {
+  #[inline]
+  fn from(err: TrailingBytes) -> Self {
+    Self::TrailingBytes(err)
+  }
+}
+
+impl<E: core::fmt::Display> core::fmt::Display for SealedTransformError<E> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::EncodeBufferTooSmall => write!(
+        f,
+        "buffer is too small, use `TransformableWith::encoded_len_with` to pre-allocate a buffer with enough space"
+      ),
+      Self::NotEnoughBytes => write!(f, "not enough bytes to decode"),
+      Self::TooLarge(len) => write!(f, "value of {len} bytes is too large to be sealed"),
+      Self::Transform(e) => write!(f, "{e}"),
+      Self::UnknownKey(id) => write!(f, "value is sealed with the unknown key {id}"),
+      Self::AuthenticationFailed => write!(f, "authentication failed"),
+      Self::EncryptionFailed => write!(f, "failed to encrypt"),
+      Self::TrailingBytes(e) => write!(f, "{e}"),
+      Self::AllocationFailed { requested } => write!(f, "failed to allocate {requested} bytes"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for SealedTransformError<E> {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Transform(e) => Some(e),
+      Self::TrailingBytes(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+/// A value whose encoding is encrypted and authenticated with an AEAD, e.g. ChaCha20-Poly1305.
+///
+/// The keys come from the [`Keyring`] the value is encoded and decoded with, so `Sealed` is
+/// [`TransformableWith`] the keyring, rather than [`Transformable`]. Values are sealed with the
+/// [primary](Keyring::primary) key, whose id is sent along, so peers open them with whatever key
+/// of their keyring has that id, which allows to rotate keys.
+///
+/// The wire format is the key id, the length of the ciphertext as a 4 bytes big-endian integer,
+/// a random nonce, the ciphertext and the tag. The key id and the length are authenticated too.
+///
+/// Random nonces are safe for about 2<sup>32</sup> values per key with 96-bit nonces, rotate keys
+/// before, or use a cipher with an extended nonce, e.g. XChaCha20-Poly1305.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Sealed<T> {
+  value: T,
+}
+
+impl<T> Sealed<T> {
+  /// Wraps the given value.
+  #[inline]
+  pub const fn new(value: T) -> Self {
+    Self { value }
+  }
+
+  /// Returns a reference to the value.
+  #[inline]
+  pub const fn get(&self) -> &T {
+    &self.value
+  }
+
+  /// Consumes the wrapper, returning the value.
+  #[inline]
+  pub fn into_inner(self) -> T {
+    self.value
+  }
+}
+
+impl<T> From<T> for Sealed<T> {
+  #[inline]
+  fn from(value: T) -> Self {
+    Self::new(value)
+  }
+}
+
+impl<T> core::ops::Deref for Sealed<T> {
+  type Target = T;
+
+  #[inline]
+  fn deref(&self) -> &T {
+    &self.value
+  }
+}
+
+#[inline]
+fn nonce_size<A: AeadCore>() -> usize {
+  A::NonceSize::USIZE
+}
+
+#[inline]
+fn tag_size<A: AeadCore>() -> usize {
+  A::TagSize::USIZE
+}
+
+impl<T, K> TransformableWith<K> for Sealed<T>
+where
+  K: Keyring + ?Sized,
+  K::Key: AeadInPlace,
+  T: TransformableWith<K>,
+{
+  type Error = SealedTransformError<T::Error>;
+
+  fn encode_with(&self, ctx: &K, dst: &mut [u8]) -> Result<usize, Self::Error> {
+    let encoded_len = self.encoded_len_with(ctx);
+    if dst.len() < encoded_len {
+      return Err(Self::Error::EncodeBufferTooSmall);
+    }
+
+    let (id, key) = ctx.primary();
+    let start = HEADER_LEN + nonce_size::<K::Key>();
+    let end = encoded_len - tag_size::<K::Key>();
+    let len = self
+      .value
+      .encode_with(ctx, &mut dst[start..end])
+      .map_err(SealedTransformError::Transform)?;
+    if len > u32::MAX as usize {
+      return Err(Self::Error::TooLarge(len));
+    }
+
+    dst[0] = id;
+    NetworkEndian::write_u32(&mut dst[1..HEADER_LEN], len as u32);
+    OsRng.fill_bytes(&mut dst[HEADER_LEN..start]);
+    let (head, body) = dst.split_at_mut(start);
+    let tag = key
+      .encrypt_in_place_detached(
+        Nonce::<K::Key>::from_slice(&head[HEADER_LEN..]),
+        &head[..HEADER_LEN],
+        &mut body[..len],
+      )
+      .map_err(|_| Self::Error::EncryptionFailed)?;
+    body[len..len + tag.len()].copy_from_slice(&tag);
+    Ok(start + len + tag.len())
+  }
+
+  fn encoded_len_with(&self, ctx: &K) -> usize {
+    HEADER_LEN + nonce_size::<K::Key>() + self.value.encoded_len_with(ctx) + tag_size::<K::Key>()
+  }
+
+  fn decode_with(ctx: &K, src: &[u8]) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized,
+  {
+    if src.len() < HEADER_LEN {
+      return Err(Self::Error::NotEnoughBytes);
+    }
+
+    let id = src[0];
+    let len = NetworkEndian::read_u32(&src[1..HEADER_LEN]) as usize;
+    let start = HEADER_LEN + nonce_size::<K::Key>();
+    let total = (start + tag_size::<K::Key>()).saturating_add(len);
+    if src.len() < total {
+      return Err(Self::Error::NotEnoughBytes);
+    }
+
+    let key = ctx.get(id).ok_or(Self::Error::UnknownKey(id))?;
+    let mut buf = Vec::new();
+    buf
+      .try_reserve_exact(len)
+      .map_err(|_| Self::Error::AllocationFailed { requested: len })?;
+    buf.extend_from_slice(&src[start..start + len]);
+    key
+      .decrypt_in_place_detached(
+        Nonce::<K::Key>::from_slice(&src[HEADER_LEN..start]),
+        &src[..HEADER_LEN],
+        &mut buf,
+        Tag::<K::Key>::from_slice(&src[start + len..total]),
+      )
+      .map_err(|_| Self::Error::AuthenticationFailed)?;
+
+    let (readed, val) = T::decode_with(ctx, &buf).map_err(SealedTransformError::Transform)?;
+    TrailingBytes::check(readed, buf.len())?;
+    Ok((total, Self::new(val)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::keyring::Keys;
+  use chacha20poly1305::{ChaCha20Poly1305, KeyInit};
+  use std::string::String;
+
+  fn keys(id: u8) -> Keys<ChaCha20Poly1305> {
+    Keys::new(id, ChaCha20Poly1305::new(&[id; 32].into()))
+  }
+
+  #[test]
+  fn test_sealed() {
+    let keyring = keys(1);
+    let val = Sealed::new(String::from("hello world"));
+    let encoded = val.encode_to_vec_with(&keyring).unwrap();
+    assert_eq!(encoded.len(), 5 + 12 + 4 + 11 + 16);
+    assert_eq!(encoded.len(), val.encoded_len_with(&keyring));
+    assert_eq!(encoded[0], 1);
+    assert!(!encoded.windows(11).any(|w| w == b"hello world"));
+    assert_eq!(
+      Sealed::<String>::decode_with(&keyring, &encoded).unwrap(),
+      (encoded.len(), val.clone())
+    );
+
+    // the nonce is random
+    assert_ne!(encoded, val.encode_to_vec_with(&keyring).unwrap());
+
+    assert!(matches!(
+      Sealed::<String>::decode_with(&keyring, &encoded[..encoded.len() - 1]),
+      Err(SealedTransformError::NotEnoughBytes)
+    ));
+  }
+
+  #[test]
+  fn test_tampered() {
+    let keyring = keys(1);
+    let val = Sealed::new(String::from("hello world"));
+    let encoded = val.encode_to_vec_with(&keyring).unwrap();
+
+    // the ciphertext, the nonce and the tag
+    for idx in [25, 10, encoded.len() - 1] {
+      let mut tampered = encoded.clone();
+      tampered[idx] ^= 0x01;
+      assert!(matches!(
+        Sealed::<String>::decode_with(&keyring, &tampered),
+        Err(SealedTransformError::AuthenticationFailed)
+      ));
+    }
+
+    // a different key with the same id
+    let other = Keys::new(1, ChaCha20Poly1305::new(&[2; 32].into()));
+    assert!(matches!(
+      Sealed::<String>::decode_with(&other, &encoded),
+      Err(SealedTransformError::AuthenticationFailed)
+    ));
+  }
+
+  #[test]
+  fn test_rotation() {
+    let mut keyring = keys(1);
+    let old = Sealed::new(String::from("sealed with 1"))
+      .encode_to_vec_with(&keyring)
+      .unwrap();
+
+    keyring.rotate(2, ChaCha20Poly1305::new(&[2; 32].into()));
+    assert_eq!(keyring.primary_id(), 2);
+    let new = Sealed::new(String::from("sealed with 2"))
+      .encode_to_vec_with(&keyring)
+      .unwrap();
+    assert_eq!(new[0], 2);
+    assert_eq!(
+      Sealed::<String>::decode_with(&keyring, &old)
+        .unwrap()
+        .1
+        .into_inner(),
+      "sealed with 1"
+    );
+
+    assert!(keyring.remove(2).is_none());
+    assert!(keyring.remove(1).is_some());
+    assert!(matches!(
+      Sealed::<String>::decode_with(&keyring, &old),
+      Err(SealedTransformError::UnknownKey(1))
+    ));
+    assert_eq!(
+      Sealed::<String>::decode_with(&keyring, &new)
+        .unwrap()
+        .1
+        .into_inner(),
+      "sealed with 2"
+    );
+  }
+}
@@ -40,13 +40,26 @@ impl<const N: usize> Transformable for ::smallvec::SmallVec<[u8; N]> {
     encoded_bytes_len(self.as_ref())
   }
 
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn as_io_slices<'a>(&'a self, slices: &mut GatherList<'a>) -> bool {
+    bytes_as_io_slices(self.as_ref(), slices)
+  }
+
   fn decode(src: &[u8]) -> Result<(usize, Self), Self::Error>
   where
     Self: Sized,
   {
-    decode_bytes(src)
-      .map(|(readed, b)| (readed, b.into()))
-      .map_err(|_| Self::Error::NotEnoughBytes)
+    decode_bytes(src).map(|(readed, b)| (readed, b.into()))
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn is_incomplete(err: &Self::Error) -> bool
+  where
+    Self: Sized,
+  {
+    matches!(err, BytesTransformError::NotEnoughBytes)
   }
 
   /// Decodes the value from the given reader.
@@ -84,6 +97,137 @@ impl<const N: usize> Transformable for ::smallvec::SmallVec<[u8; N]> {
       .await
       .map(|(readed, b)| (readed, b.into()))
   }
+
+  fn decode_limited(src: &[u8], budget: &mut DecodeBudget) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized,
+  {
+    charge_encoded_bytes(src, budget)?;
+    <Self as Transformable>::decode(src)
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn decode_from_reader_limited<R: std::io::Read>(
+    src: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    decode_bytes_from_limited(src, budget).map(|(readed, b)| (readed, b.into()))
+  }
+
+  #[cfg(feature = "async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+  async fn decode_from_async_reader_limited<R: futures_util::io::AsyncRead + Send + Unpin>(
+    src: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    decode_bytes_from_async_limited(src, budget)
+      .await
+      .map(|(readed, b)| (readed, b.into()))
+  }
+
+  #[cfg(feature = "owned-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+  async fn decode_from_owned_reader<R: crate::OwnedRead>(
+    reader: &mut R,
+    buf: Vec<u8>,
+  ) -> (std::io::Result<(usize, Self)>, Vec<u8>)
+  where
+    Self: Sized,
+  {
+    crate::owned::decode_bytes_from(reader, buf).await
+  }
+
+  #[cfg(feature = "owned-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+  async fn decode_from_owned_reader_limited<R: crate::OwnedRead>(
+    reader: &mut R,
+    buf: Vec<u8>,
+    budget: &mut DecodeBudget,
+  ) -> (std::io::Result<(usize, Self)>, Vec<u8>)
+  where
+    Self: Sized,
+  {
+    crate::owned::decode_bytes_from_limited(reader, buf, budget).await
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn encode_to_embedded_writer<W: embedded_io::Write>(
+    &self,
+    dst: &mut W,
+  ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+    let src = self.as_ref();
+    crate::embedded::encode_bytes_to(src, dst)
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn encode_to_embedded_async_writer<W: embedded_io_async::Write>(
+    &self,
+    dst: &mut W,
+  ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+    let src = self.as_ref();
+    crate::embedded::encode_bytes_to_async(src, dst).await
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn decode_from_embedded_reader<R: embedded_io::Read>(
+    src: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    crate::embedded::decode_bytes_from(src).map(|(readed, b)| (readed, b.into()))
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn decode_from_embedded_reader_limited<R: embedded_io::Read>(
+    src: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    crate::embedded::decode_bytes_from_limited(src, budget).map(|(readed, b)| (readed, b.into()))
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn decode_from_embedded_async_reader<R: embedded_io_async::Read>(
+    src: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    crate::embedded::decode_bytes_from_async(src)
+      .await
+      .map(|(readed, b)| (readed, b.into()))
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn decode_from_embedded_async_reader_limited<R: embedded_io_async::Read>(
+    src: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    crate::embedded::decode_bytes_from_async_limited(src, budget)
+      .await
+      .map(|(readed, b)| (readed, b.into()))
+  }
 }
 
+impl<const N: usize> ContextFree for ::smallvec::SmallVec<[u8; N]> {}
+
 test_transformable!(::smallvec::SmallVec<[u8; 10]> => test_smallvec_transformable(::smallvec::SmallVec::from([1, 2, 3, 4, 5, 6, 7, 8, 9, 0])));
@@ -22,6 +22,50 @@ pub enum StringTransformError {
   /// Returned when the decoding meet utf8 error.
   #[cfg_attr(feature = "std", error("{0}"))]
   Utf8Error(#[cfg_attr(feature = "std", from)] core::str::Utf8Error),
+  /// Returned when a [`DecodeLimits`] is exceeded.
+  #[cfg_attr(feature = "std", error("{0}"))]
+  Limit(#[cfg_attr(feature = "std", from)] LimitError),
+  /// Returned when the memory for the decoded value can not be allocated.
+  #[cfg_attr(feature = "std", error("failed to allocate {requested} bytes"))]
+  AllocationFailed {
+    /// The number of bytes which failed to be allocated.
+    requested: usize,
+  },
+}
+
+impl crate::DecodeContext for StringTransformError {}
+
+impl From<StringTransformError> for TransformError {
+  fn from(err: StringTransformError) -> Self {
+    match err {
+      StringTransformError::EncodeBufferTooSmall => Self::from_kind(ErrorKind::BufferTooSmall),
+      StringTransformError::NotEnoughBytes => Self::from_kind(ErrorKind::UnexpectedEof),
+      StringTransformError::Limit(e) => e.into(),
+      err @ StringTransformError::Utf8Error(_) => Self::wrap(ErrorKind::InvalidValue, err),
+      err @ StringTransformError::AllocationFailed { .. } => {
+        Self::wrap(ErrorKind::LimitExceeded, err)
+      }
+    }
+  }
+}
+
+impl StringTransformError {
+  #[inline]
+  fn from_bytes_error(err: BytesTransformError) -> Self {
+    match err {
+      BytesTransformError::EncodeBufferTooSmall => Self::EncodeBufferTooSmall,
+      BytesTransformError::Limit(err) => Self::Limit(err),
+      BytesTransformError::AllocationFailed { requested } => Self::AllocationFailed { requested },
+      BytesTransformError::NotEnoughBytes => Self::NotEnoughBytes,
+    }
+  }
+}
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+impl core::convert::From<LimitError> for StringTransformError {
+  fn from(err: LimitError) -> Self {
+    Self::Limit(err)
+  }
 }
 
 #[cfg(all(not(feature = "std"), feature = "alloc"))]
@@ -41,10 +85,63 @@ impl core::fmt::Display for StringTransformError {
       ),
       Self::NotEnoughBytes => write!(f, "not enough bytes to decode"),
       Self::Utf8Error(val) => write!(f, "{val}"),
+      Self::Limit(val) => write!(f, "{val}"),
+      Self::AllocationFailed { requested } => write!(f, "failed to allocate {requested} bytes"),
     }
   }
 }
 
+/// Builds the string from the decoded bytes, reusing their allocation where the type allows.
+trait FromUtf8Vec: Sized {
+  fn from_utf8_vec(buf: Vec<u8>) -> Result<Self, core::str::Utf8Error>;
+}
+
+impl FromUtf8Vec for String {
+  #[inline]
+  fn from_utf8_vec(buf: Vec<u8>) -> Result<Self, core::str::Utf8Error> {
+    String::from_utf8(buf).map_err(|e| e.utf8_error())
+  }
+}
+
+impl FromUtf8Vec for Box<str> {
+  #[inline]
+  fn from_utf8_vec(buf: Vec<u8>) -> Result<Self, core::str::Utf8Error> {
+    String::from_utf8_vec(buf).map(String::into_boxed_str)
+  }
+}
+
+impl FromUtf8Vec for Arc<str> {
+  #[inline]
+  fn from_utf8_vec(buf: Vec<u8>) -> Result<Self, core::str::Utf8Error> {
+    core::str::from_utf8(&buf).map(Self::from)
+  }
+}
+
+#[cfg(feature = "smol_str")]
+impl FromUtf8Vec for smol_str::SmolStr {
+  #[inline]
+  fn from_utf8_vec(buf: Vec<u8>) -> Result<Self, core::str::Utf8Error> {
+    core::str::from_utf8(&buf).map(Self::from)
+  }
+}
+
+/// Reports the allocation failures of the byte readers as [`StringTransformError`]s.
+#[cfg(feature = "std")]
+fn string_io_error(err: std::io::Error) -> std::io::Error {
+  match err
+    .get_ref()
+    .and_then(|e| e.downcast_ref::<BytesTransformError>())
+  {
+    Some(BytesTransformError::AllocationFailed { requested }) => std::io::Error::new(
+      err.kind(),
+      StringTransformError::AllocationFailed {
+        requested: *requested,
+      },
+    ),
+    _ => err,
+  }
+}
+
 macro_rules! impl_string {
   ($ty: ty => $test_fn:ident($init: expr)) => {
     impl Transformable for $ty {
@@ -77,28 +174,44 @@ macro_rules! impl_string {
         encoded_bytes_len(src.as_bytes())
       }
 
+      #[cfg(feature = "std")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+      fn as_io_slices<'a>(&'a self, slices: &mut GatherList<'a>) -> bool {
+        let src: &str = self.borrow();
+        bytes_as_io_slices(src.as_bytes(), slices)
+      }
+
       fn decode(src: &[u8]) -> Result<(usize, Self), Self::Error>
       where
         Self: Sized,
       {
         decode_bytes(src)
-          .map_err(|_| Self::Error::NotEnoughBytes)
+          .map_err(Self::Error::from_bytes_error)
           .and_then(|(readed, bytes)| {
-            core::str::from_utf8(bytes.as_ref())
-              .map(|s| (readed, Self::from(s)))
+            Self::from_utf8_vec(bytes)
+              .map(|s| (readed, s))
               .map_err(Into::into)
           })
       }
 
+      #[cfg(feature = "std")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+      fn is_incomplete(err: &Self::Error) -> bool
+      where
+        Self: Sized,
+      {
+        matches!(err, StringTransformError::NotEnoughBytes)
+      }
+
       #[cfg(feature = "std")]
       #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
       fn decode_from_reader<R: std::io::Read>(src: &mut R) -> std::io::Result<(usize, Self)>
       where
         Self: Sized,
       {
-        decode_bytes_from(src).and_then(|(readed, bytes)| {
-          core::str::from_utf8(bytes.as_ref())
-            .map(|s| (readed, Self::from(s)))
+        decode_bytes_from(src).map_err(string_io_error).and_then(|(readed, bytes)| {
+          Self::from_utf8_vec(bytes)
+            .map(|s| (readed, s))
             .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
         })
       }
@@ -113,14 +226,173 @@ macro_rules! impl_string {
       {
         decode_bytes_from_async(src)
           .await
+          .map_err(string_io_error)
+          .and_then(|(readed, bytes)| {
+            Self::from_utf8_vec(bytes)
+              .map(|s| (readed, s))
+              .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+          })
+      }
+
+      fn decode_limited(src: &[u8], budget: &mut DecodeBudget) -> Result<(usize, Self), Self::Error>
+      where
+        Self: Sized,
+      {
+        charge_encoded_bytes(src, budget)?;
+        <Self as Transformable>::decode(src)
+      }
+
+      #[cfg(feature = "std")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+      fn decode_from_reader_limited<R: std::io::Read>(
+        src: &mut R,
+        budget: &mut DecodeBudget,
+      ) -> std::io::Result<(usize, Self)>
+      where
+        Self: Sized,
+      {
+        decode_bytes_from_limited(src, budget).map_err(string_io_error).and_then(|(readed, bytes)| {
+          Self::from_utf8_vec(bytes)
+            .map(|s| (readed, s))
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+        })
+      }
+
+      #[cfg(feature = "async")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+      async fn decode_from_async_reader_limited<R: futures_util::io::AsyncRead + Send + Unpin>(
+        src: &mut R,
+        budget: &mut DecodeBudget,
+      ) -> std::io::Result<(usize, Self)>
+      where
+        Self: Sized,
+      {
+        decode_bytes_from_async_limited(src, budget)
+          .await
+          .map_err(string_io_error)
           .and_then(|(readed, bytes)| {
-            core::str::from_utf8(bytes.as_ref())
-              .map(|s| (readed, Self::from(s)))
+            Self::from_utf8_vec(bytes)
+              .map(|s| (readed, s))
               .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
           })
       }
+
+      #[cfg(feature = "owned-io")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+      async fn decode_from_owned_reader<R: crate::OwnedRead>(
+        reader: &mut R,
+        buf: Vec<u8>,
+      ) -> (std::io::Result<(usize, Self)>, Vec<u8>)
+      where
+        Self: Sized,
+      {
+        let (res, buf) = crate::owned::decode_bytes_from(reader, buf).await;
+        (res.map_err(string_io_error), buf)
+      }
+
+      #[cfg(feature = "owned-io")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+      async fn decode_from_owned_reader_limited<R: crate::OwnedRead>(
+        reader: &mut R,
+        buf: Vec<u8>,
+        budget: &mut DecodeBudget,
+      ) -> (std::io::Result<(usize, Self)>, Vec<u8>)
+      where
+        Self: Sized,
+      {
+        let (res, buf) = crate::owned::decode_bytes_from_limited(reader, buf, budget).await;
+        (res.map_err(string_io_error), buf)
+      }
+
+      #[cfg(feature = "embedded-io")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+      fn encode_to_embedded_writer<W: embedded_io::Write>(
+        &self,
+        dst: &mut W,
+      ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+        let src: &str = self.borrow();
+        let src = src.as_bytes();
+        crate::embedded::encode_bytes_to(src, dst)
+      }
+
+      #[cfg(feature = "embedded-io-async")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+      async fn encode_to_embedded_async_writer<W: embedded_io_async::Write>(
+        &self,
+        dst: &mut W,
+      ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+        let src: &str = self.borrow();
+        let src = src.as_bytes();
+        crate::embedded::encode_bytes_to_async(src, dst).await
+      }
+
+      #[cfg(feature = "embedded-io")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+      fn decode_from_embedded_reader<R: embedded_io::Read>(
+        src: &mut R,
+      ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+      where
+        Self: Sized,
+      {
+        crate::embedded::decode_bytes_from(src).and_then(|(readed, bytes)| {
+          Self::from_utf8_vec(bytes)
+            .map(|s| (readed, s))
+            .map_err(|e| EmbeddedIoError::Transform(e.into()))
+        })
+      }
+
+      #[cfg(feature = "embedded-io")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+      fn decode_from_embedded_reader_limited<R: embedded_io::Read>(
+        src: &mut R,
+        budget: &mut DecodeBudget,
+      ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+      where
+        Self: Sized,
+      {
+        crate::embedded::decode_bytes_from_limited(src, budget).and_then(|(readed, bytes)| {
+          Self::from_utf8_vec(bytes)
+            .map(|s| (readed, s))
+            .map_err(|e| EmbeddedIoError::Transform(e.into()))
+        })
+      }
+
+      #[cfg(feature = "embedded-io-async")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+      async fn decode_from_embedded_async_reader<R: embedded_io_async::Read>(
+        src: &mut R,
+      ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+      where
+        Self: Sized,
+      {
+        crate::embedded::decode_bytes_from_async(src)
+          .await.and_then(|(readed, bytes)| {
+          Self::from_utf8_vec(bytes)
+            .map(|s| (readed, s))
+            .map_err(|e| EmbeddedIoError::Transform(e.into()))
+        })
+      }
+
+      #[cfg(feature = "embedded-io-async")]
+      #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+      async fn decode_from_embedded_async_reader_limited<R: embedded_io_async::Read>(
+        src: &mut R,
+        budget: &mut DecodeBudget,
+      ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+      where
+        Self: Sized,
+      {
+        crate::embedded::decode_bytes_from_async_limited(src, budget)
+          .await.and_then(|(readed, bytes)| {
+          Self::from_utf8_vec(bytes)
+            .map(|s| (readed, s))
+            .map_err(|e| EmbeddedIoError::Transform(e.into()))
+        })
+      }
     }
 
+    impl ContextFree for $ty {}
+
     test_transformable!($ty => $test_fn($init));
   };
 }
@@ -133,3 +405,47 @@ impl_string!(smol_str::SmolStr => test_smol_str_transformable(smol_str::SmolStr:
 impl_string!(Box<str> => test_box_str_transformable(Box::from("hello world")));
 
 impl_string!(Arc<str> => test_arc_str_transformable(Arc::from("hello world")));
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_invalid_utf8() {
+    let encoded = [0, 0, 0, 2, 0xc3, 0x28];
+    assert!(matches!(
+      <String as Transformable>::decode(&encoded),
+      Err(StringTransformError::Utf8Error(_))
+    ));
+    assert!(matches!(
+      <Box<str> as Transformable>::decode(&encoded),
+      Err(StringTransformError::Utf8Error(_))
+    ));
+    let err = <Arc<str> as Transformable>::decode_from_reader(&mut &encoded[..]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    // a fully buffered invalid value is not read again from the reader
+    let mut reader = std::io::BufReader::new(&encoded[..]);
+    let err = <String as Transformable>::decode_from_buf_reader(&mut reader).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(std::io::BufRead::fill_buf(&mut reader).unwrap(), encoded);
+  }
+
+  #[test]
+  fn test_allocation_failed() {
+    let err = std::io::Error::new(
+      std::io::ErrorKind::OutOfMemory,
+      BytesTransformError::AllocationFailed { requested: 42 },
+    );
+    let err = string_io_error(err);
+    assert_eq!(err.kind(), std::io::ErrorKind::OutOfMemory);
+    assert!(matches!(
+      err
+        .into_inner()
+        .unwrap()
+        .downcast::<StringTransformError>()
+        .as_deref(),
+      Ok(StringTransformError::AllocationFailed { requested: 42 })
+    ));
+  }
+}
@@ -1,6 +1,9 @@
 use core::{mem, time::Duration};
 
 use super::Transformable;
+#[cfg(feature = "embedded-io")]
+use crate::EmbeddedIoError;
+use crate::{ContextFree, ErrorKind, TransformError};
 
 const ENCODED_LEN: usize = mem::size_of::<u64>() + mem::size_of::<u32>();
 
@@ -13,6 +16,17 @@ pub enum DurationTransformError {
   NotEnoughBytes,
 }
 
+impl crate::DecodeContext for DurationTransformError {}
+
+impl From<DurationTransformError> for TransformError {
+  fn from(err: DurationTransformError) -> Self {
+    match err {
+      DurationTransformError::EncodeBufferTooSmall => Self::from_kind(ErrorKind::BufferTooSmall),
+      DurationTransformError::NotEnoughBytes => Self::from_kind(ErrorKind::UnexpectedEof),
+    }
+  }
+}
+
 impl core::fmt::Display for DurationTransformError {
   fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     match self {
@@ -80,6 +94,15 @@ impl Transformable for Duration {
     Ok(decode_duration_unchecked(src))
   }
 
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn is_incomplete(err: &Self::Error) -> bool
+  where
+    Self: Sized,
+  {
+    matches!(err, DurationTransformError::NotEnoughBytes)
+  }
+
   #[cfg(feature = "std")]
   #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
   fn decode_from_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<(usize, Self)>
@@ -106,8 +129,71 @@ impl Transformable for Duration {
     reader.read_exact(&mut buf).await?;
     Ok(decode_duration_unchecked(&buf))
   }
+
+  #[cfg(feature = "owned-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+  async fn decode_from_owned_reader<R: crate::OwnedRead>(
+    reader: &mut R,
+    buf: Vec<u8>,
+  ) -> (std::io::Result<(usize, Self)>, Vec<u8>)
+  where
+    Self: Sized,
+  {
+    crate::owned::decode_fixed_from::<_, _, ENCODED_LEN>(reader, buf).await
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn encode_to_embedded_writer<W: embedded_io::Write>(
+    &self,
+    writer: &mut W,
+  ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+    let buf = encode_duration_unchecked(*self);
+    writer
+      .write_all(&buf)
+      .map(|_| ENCODED_LEN)
+      .map_err(EmbeddedIoError::Io)
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn encode_to_embedded_async_writer<W: embedded_io_async::Write>(
+    &self,
+    writer: &mut W,
+  ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+    let buf = encode_duration_unchecked(*self);
+    writer
+      .write_all(&buf)
+      .await
+      .map(|_| ENCODED_LEN)
+      .map_err(EmbeddedIoError::Io)
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn decode_from_embedded_reader<R: embedded_io::Read>(
+    reader: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    crate::embedded::decode_fixed_from::<_, _, ENCODED_LEN>(reader)
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn decode_from_embedded_async_reader<R: embedded_io_async::Read>(
+    reader: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    crate::embedded::decode_fixed_from_async::<_, _, ENCODED_LEN>(reader).await
+  }
 }
 
+impl ContextFree for Duration {}
+
 #[inline]
 const fn encode_duration_unchecked(dur: Duration) -> [u8; ENCODED_LEN] {
   let secs = dur.as_secs().to_be_bytes();
@@ -4,6 +4,7 @@ use std::{
 };
 
 use super::*;
+use crate::{ContextFree, ErrorKind, TransformError};
 
 /// Error returned by [`Instant`] when transforming.
 #[derive(Debug, Clone)]
@@ -16,6 +17,18 @@ pub enum InstantTransformError {
   InvalidSystemTime(SystemTimeError),
 }
 
+impl crate::DecodeContext for InstantTransformError {}
+
+impl From<InstantTransformError> for TransformError {
+  fn from(err: InstantTransformError) -> Self {
+    match err {
+      InstantTransformError::EncodeBufferTooSmall => Self::from_kind(ErrorKind::BufferTooSmall),
+      InstantTransformError::NotEnoughBytes => Self::from_kind(ErrorKind::UnexpectedEof),
+      err @ InstantTransformError::InvalidSystemTime(_) => Self::wrap(ErrorKind::InvalidValue, err),
+    }
+  }
+}
+
 impl core::fmt::Display for InstantTransformError {
   fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     match self {
@@ -89,6 +102,15 @@ impl Transformable for Instant {
     Ok((readed, decode_instant_from_duration(instant)))
   }
 
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn is_incomplete(err: &Self::Error) -> bool
+  where
+    Self: Sized,
+  {
+    matches!(err, InstantTransformError::NotEnoughBytes)
+  }
+
   #[cfg(feature = "std")]
   #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
   fn decode_from_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<(usize, Self)>
@@ -117,8 +139,62 @@ impl Transformable for Instant {
     let (readed, instant) = decode_duration_unchecked(&buf);
     Ok((readed, decode_instant_from_duration(instant)))
   }
+
+  #[cfg(feature = "owned-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+  async fn decode_from_owned_reader<R: crate::OwnedRead>(
+    reader: &mut R,
+    buf: Vec<u8>,
+  ) -> (std::io::Result<(usize, Self)>, Vec<u8>)
+  where
+    Self: Sized,
+  {
+    crate::owned::decode_fixed_from::<_, _, ENCODED_LEN>(reader, buf).await
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn encode_to_embedded_writer<W: embedded_io::Write>(
+    &self,
+    writer: &mut W,
+  ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+    crate::embedded::encode_inlined_to(self, writer)
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn encode_to_embedded_async_writer<W: embedded_io_async::Write>(
+    &self,
+    writer: &mut W,
+  ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+    crate::embedded::encode_inlined_to_async(self, writer).await
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn decode_from_embedded_reader<R: embedded_io::Read>(
+    reader: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    crate::embedded::decode_fixed_from::<_, _, ENCODED_LEN>(reader)
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn decode_from_embedded_async_reader<R: embedded_io_async::Read>(
+    reader: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    crate::embedded::decode_fixed_from_async::<_, _, ENCODED_LEN>(reader).await
+  }
 }
 
+impl ContextFree for Instant {}
+
 fn init(now: Instant) -> (SystemTime, Instant) {
   static ONCE: OnceLock<(SystemTime, Instant)> = OnceLock::new();
   *ONCE.get_or_init(|| {
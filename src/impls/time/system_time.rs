@@ -1,6 +1,7 @@
 use std::time::{SystemTime, SystemTimeError, UNIX_EPOCH};
 
 use super::*;
+use crate::{ContextFree, ErrorKind, TransformError};
 
 /// Error returned by [`SystemTime`] when transforming.
 #[derive(Debug, Clone)]
@@ -13,6 +14,20 @@ pub enum SystemTimeTransformError {
   InvalidSystemTime(SystemTimeError),
 }
 
+impl crate::DecodeContext for SystemTimeTransformError {}
+
+impl From<SystemTimeTransformError> for TransformError {
+  fn from(err: SystemTimeTransformError) -> Self {
+    match err {
+      SystemTimeTransformError::EncodeBufferTooSmall => Self::from_kind(ErrorKind::BufferTooSmall),
+      SystemTimeTransformError::NotEnoughBytes => Self::from_kind(ErrorKind::UnexpectedEof),
+      err @ SystemTimeTransformError::InvalidSystemTime(_) => {
+        Self::wrap(ErrorKind::InvalidValue, err)
+      }
+    }
+  }
+}
+
 impl core::fmt::Display for SystemTimeTransformError {
   fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     match self {
@@ -90,6 +105,15 @@ impl Transformable for SystemTime {
     Ok((readed, UNIX_EPOCH + dur))
   }
 
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn is_incomplete(err: &Self::Error) -> bool
+  where
+    Self: Sized,
+  {
+    matches!(err, SystemTimeTransformError::NotEnoughBytes)
+  }
+
   #[cfg(feature = "std")]
   #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
   fn decode_from_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<(usize, Self)>
@@ -118,8 +142,62 @@ impl Transformable for SystemTime {
     let (readed, dur) = decode_duration_unchecked(&buf);
     Ok((readed, UNIX_EPOCH + dur))
   }
+
+  #[cfg(feature = "owned-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+  async fn decode_from_owned_reader<R: crate::OwnedRead>(
+    reader: &mut R,
+    buf: Vec<u8>,
+  ) -> (std::io::Result<(usize, Self)>, Vec<u8>)
+  where
+    Self: Sized,
+  {
+    crate::owned::decode_fixed_from::<_, _, ENCODED_LEN>(reader, buf).await
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn encode_to_embedded_writer<W: embedded_io::Write>(
+    &self,
+    writer: &mut W,
+  ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+    crate::embedded::encode_inlined_to(self, writer)
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn encode_to_embedded_async_writer<W: embedded_io_async::Write>(
+    &self,
+    writer: &mut W,
+  ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+    crate::embedded::encode_inlined_to_async(self, writer).await
+  }
+
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn decode_from_embedded_reader<R: embedded_io::Read>(
+    reader: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    crate::embedded::decode_fixed_from::<_, _, ENCODED_LEN>(reader)
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  async fn decode_from_embedded_async_reader<R: embedded_io_async::Read>(
+    reader: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    crate::embedded::decode_fixed_from_async::<_, _, ENCODED_LEN>(reader).await
+  }
 }
 
+impl ContextFree for SystemTime {}
+
 test_transformable!(SystemTime => test_systemtime_transformable({
   let now = SystemTime::now();
   std::thread::sleep(std::time::Duration::from_millis(10));
@@ -0,0 +1,459 @@
+use super::*;
+
+#[cfg(feature = "std")]
+use crate::utils::invalid_data;
+use crate::utils::{decode_varint, encode_varint, encoded_len_varint, DecodeVarintError};
+
+/// The max number of bytes of a LEB128 encoded `u128`.
+const MAX_VARINT_LEN: usize = 19;
+
+/// The error type for errors that get returned when encoding or decoding [`Varint`] or [`ZigZag`] fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VarintTransformError {
+  /// Returned when the buffer is too small to encode.
+  EncodeBufferTooSmall,
+  /// Returned when there is not enough bytes to decode.
+  NotEnoughBytes,
+  /// Returned when the decoded value does not fit in the integer type.
+  Overflow,
+}
+
+impl crate::DecodeContext for VarintTransformError {}
+
+impl From<VarintTransformError> for TransformError {
+  fn from(err: VarintTransformError) -> Self {
+    match err {
+      VarintTransformError::EncodeBufferTooSmall => Self::from_kind(ErrorKind::BufferTooSmall),
+      VarintTransformError::NotEnoughBytes => Self::from_kind(ErrorKind::UnexpectedEof),
+      err @ VarintTransformError::Overflow => Self::wrap(ErrorKind::InvalidValue, err),
+    }
+  }
+}
+
+impl core::fmt::Display for VarintTransformError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::EncodeBufferTooSmall => write!(f, "buffer is too small, use `Transformable::encoded_len` to pre-allocate a buffer with enough space"),
+      Self::NotEnoughBytes => write!(f, "not enough bytes to decode"),
+      Self::Overflow => write!(f, "varint overflows the integer type"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VarintTransformError {}
+
+impl From<DecodeVarintError> for VarintTransformError {
+  fn from(err: DecodeVarintError) -> Self {
+    match err {
+      DecodeVarintError::Overflow => Self::Overflow,
+      DecodeVarintError::NotEnoughBytes => Self::NotEnoughBytes,
+    }
+  }
+}
+
+/// An integer encoded in LEB128 variable length format, so small values take fewer bytes.
+///
+/// Signed integers are encoded as their two's complement, so negative values always take the
+/// max number of bytes, use [`ZigZag`] for signed integers which are often negative.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Varint<T>(pub T);
+
+/// A signed integer encoded with ZigZag and then LEB128 variable length format, so values with
+/// a small magnitude take fewer bytes, whether they are positive or negative.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ZigZag<T>(pub T);
+
+macro_rules! impl_wrapper {
+  ($($wrapper:ident), + $(,)?) => {
+    $(
+      impl<T> $wrapper<T> {
+        /// Consumes the wrapper, returning the integer.
+        #[inline]
+        pub fn into_inner(self) -> T {
+          self.0
+        }
+      }
+
+      impl<T> From<T> for $wrapper<T> {
+        #[inline]
+        fn from(val: T) -> Self {
+          Self(val)
+        }
+      }
+
+      impl<T> core::ops::Deref for $wrapper<T> {
+        type Target = T;
+
+        #[inline]
+        fn deref(&self) -> &T {
+          &self.0
+        }
+      }
+    )*
+  };
+}
+
+impl_wrapper!(Varint, ZigZag);
+
+/// The unsigned integer which is LEB128 encoded on the wire.
+trait Leb128: Sized {
+  fn encoded_len(self) -> usize;
+
+  fn encode(self, dst: &mut [u8]) -> Result<usize, VarintTransformError>;
+
+  fn decode(src: &[u8]) -> Result<(usize, Self), VarintTransformError>;
+}
+
+impl Leb128 for u64 {
+  fn encoded_len(self) -> usize {
+    encoded_len_varint(self)
+  }
+
+  fn encode(self, dst: &mut [u8]) -> Result<usize, VarintTransformError> {
+    if dst.len() < encoded_len_varint(self) {
+      return Err(VarintTransformError::EncodeBufferTooSmall);
+    }
+
+    encode_varint(self, dst).map_err(|_| VarintTransformError::EncodeBufferTooSmall)
+  }
+
+  fn decode(src: &[u8]) -> Result<(usize, Self), VarintTransformError> {
+    decode_varint(src).map_err(Into::into)
+  }
+}
+
+impl Leb128 for u128 {
+  fn encoded_len(self) -> usize {
+    let bits = 128 - (self | 1).leading_zeros() as usize;
+    bits.div_ceil(7)
+  }
+
+  fn encode(mut self, dst: &mut [u8]) -> Result<usize, VarintTransformError> {
+    if dst.len() < self.encoded_len() {
+      return Err(VarintTransformError::EncodeBufferTooSmall);
+    }
+
+    let mut i = 0;
+    while self >= 0x80 {
+      dst[i] = (self as u8) | 0x80;
+      self >>= 7;
+      i += 1;
+    }
+    dst[i] = self as u8;
+    Ok(i + 1)
+  }
+
+  fn decode(src: &[u8]) -> Result<(usize, Self), VarintTransformError> {
+    let mut x = 0u128;
+    for i in 0..MAX_VARINT_LEN {
+      let b = *src.get(i).ok_or(VarintTransformError::NotEnoughBytes)?;
+      if b < 0x80 {
+        // only the 2 low bits of the last byte fit in u128
+        if i == MAX_VARINT_LEN - 1 && b > 3 {
+          return Err(VarintTransformError::Overflow);
+        }
+        return Ok((i + 1, x | (b as u128) << (7 * i)));
+      }
+      x |= ((b & 0x7f) as u128) << (7 * i);
+    }
+    Err(VarintTransformError::Overflow)
+  }
+}
+
+/// Reads at most `$max_len` LEB128 bytes with the given read expression, which fills `$buf`
+/// with a single byte.
+///
+/// Evaluates to the read bytes.
+#[cfg(any(feature = "std", feature = "embedded-io"))]
+macro_rules! read_varint {
+  ($max_len:expr, |$buf:ident| $read:expr) => {{
+    let mut bytes = [0u8; MAX_VARINT_LEN];
+    let mut filled = 0;
+    loop {
+      let $buf = &mut bytes[filled..filled + 1];
+      $read;
+      filled += 1;
+      if bytes[filled - 1] & 0x80 == 0 || filled == $max_len {
+        break;
+      }
+    }
+    (bytes, filled)
+  }};
+}
+
+macro_rules! impl_varint {
+  ($($wrapper:ident<$ty:ty> as $wide:ty {
+    encode: |$enc:ident| $to_wire:expr,
+    decode: |$dec:ident| $from_wire:expr $(,)?
+  }), + $(,)?) => {
+    $(
+      impl $wrapper<$ty> {
+        /// The max number of bytes of the encoded integer, longer encodings overflow it, whether
+        /// they are decoded from a slice or a reader.
+        const MAX_LEN: usize = (<$ty>::BITS as usize).div_ceil(7);
+
+        #[inline]
+        fn to_wire(self) -> $wide {
+          let $enc = self.0;
+          $to_wire
+        }
+
+        #[inline]
+        fn from_wire($dec: $wide) -> Option<Self> {
+          $from_wire.map(Self)
+        }
+      }
+
+      impl Transformable for $wrapper<$ty> {
+        type Error = VarintTransformError;
+
+        fn encode(&self, dst: &mut [u8]) -> Result<usize, Self::Error> {
+          Leb128::encode(self.to_wire(), dst)
+        }
+
+        fn encoded_len(&self) -> usize {
+          Leb128::encoded_len(self.to_wire())
+        }
+
+        fn decode(src: &[u8]) -> Result<(usize, Self), Self::Error>
+        where
+          Self: Sized,
+        {
+          let max = src.len().min(Self::MAX_LEN);
+          let (readed, val) = match <$wide as Leb128>::decode(&src[..max]) {
+            Err(Self::Error::NotEnoughBytes) if src.len() >= Self::MAX_LEN => {
+              return Err(Self::Error::Overflow)
+            }
+            res => res?,
+          };
+          Self::from_wire(val)
+            .map(|val| (readed, val))
+            .ok_or(Self::Error::Overflow)
+        }
+
+        #[cfg(feature = "std")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+        fn is_incomplete(err: &Self::Error) -> bool
+        where
+          Self: Sized,
+        {
+          matches!(err, VarintTransformError::NotEnoughBytes)
+        }
+
+        #[cfg(feature = "std")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+        fn decode_from_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<(usize, Self)>
+        where
+          Self: Sized,
+        {
+          let (bytes, filled) = read_varint!(Self::MAX_LEN, |buf| reader.read_exact(buf)?);
+          <Self as Transformable>::decode(&bytes[..filled]).map_err(invalid_data)
+        }
+
+        #[cfg(feature = "async")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+        async fn decode_from_async_reader<R: futures_util::io::AsyncRead + Send + Unpin>(
+          reader: &mut R,
+        ) -> std::io::Result<(usize, Self)>
+        where
+          Self: Sized,
+        {
+          use futures_util::AsyncReadExt;
+
+          let (bytes, filled) = read_varint!(Self::MAX_LEN, |buf| reader.read_exact(buf).await?);
+          <Self as Transformable>::decode(&bytes[..filled]).map_err(invalid_data)
+        }
+
+        #[cfg(feature = "owned-io")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+        async fn decode_from_owned_reader<R: crate::OwnedRead>(
+          reader: &mut R,
+          mut buf: Vec<u8>,
+        ) -> (std::io::Result<(usize, Self)>, Vec<u8>)
+        where
+          Self: Sized,
+        {
+          buf.clear();
+          loop {
+            let res;
+            (res, buf) = reader.read_exact_owned(buf, 1).await;
+            if let Err(e) = res {
+              return (Err(e), buf);
+            }
+
+            if buf[buf.len() - 1] & 0x80 == 0 || buf.len() == Self::MAX_LEN {
+              break;
+            }
+          }
+
+          let res = <Self as Transformable>::decode(&buf).map_err(invalid_data);
+          (res, buf)
+        }
+
+        #[cfg(feature = "embedded-io")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+        fn decode_from_embedded_reader<R: embedded_io::Read>(
+          reader: &mut R,
+        ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+        where
+          Self: Sized,
+        {
+          let (bytes, filled) = read_varint!(Self::MAX_LEN, |buf| reader.read_exact(buf)?);
+          <Self as Transformable>::decode(&bytes[..filled]).map_err(EmbeddedIoError::Transform)
+        }
+
+        #[cfg(feature = "embedded-io-async")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+        async fn decode_from_embedded_async_reader<R: embedded_io_async::Read>(
+          reader: &mut R,
+        ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+        where
+          Self: Sized,
+        {
+          let (bytes, filled) = read_varint!(Self::MAX_LEN, |buf| reader.read_exact(buf).await?);
+          <Self as Transformable>::decode(&bytes[..filled]).map_err(EmbeddedIoError::Transform)
+        }
+      }
+
+      impl ContextFree for $wrapper<$ty> {}
+
+      #[cfg(test)]
+      paste::paste! {
+        test_transformable!($wrapper<$ty> => [< test _ $wrapper:snake _ $ty _ transformable >]($wrapper(rand::random())));
+      }
+    )*
+  };
+}
+
+macro_rules! impl_unsigned_varint {
+  ($($ty:ident as $wide:ty), + $(,)?) => {
+    impl_varint!($(
+      Varint<$ty> as $wide {
+        encode: |val| val as $wide,
+        decode: |val| $ty::try_from(val).ok(),
+      }
+    ), +);
+  };
+}
+
+macro_rules! impl_signed_varint {
+  ($($ty:ident($unsigned:ident) as $wide:ty), + $(,)?) => {
+    impl_varint!($(
+      Varint<$ty> as $wide {
+        encode: |val| val as $unsigned as $wide,
+        decode: |val| $unsigned::try_from(val).ok().map(|val| val as $ty),
+      },
+      ZigZag<$ty> as $wide {
+        encode: |val| ((val << 1) ^ (val >> ($ty::BITS - 1))) as $unsigned as $wide,
+        decode: |val| $unsigned::try_from(val)
+          .ok()
+          .map(|val| ((val >> 1) as $ty) ^ -((val & 1) as $ty)),
+      }
+    ), +);
+  };
+}
+
+impl_unsigned_varint!(u8 as u64, u16 as u64, u32 as u64, u64 as u64, u128 as u128);
+impl_signed_varint!(
+  i8(u8) as u64,
+  i16(u16) as u64,
+  i32(u32) as u64,
+  i64(u64) as u64,
+  i128(u128) as u128,
+);
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn encoded<T: Transformable<Error = VarintTransformError>>(val: T) -> std::vec::Vec<u8> {
+    let mut buf = std::vec![0; Transformable::encoded_len(&val)];
+    let len = Transformable::encode(&val, &mut buf).unwrap();
+    assert_eq!(len, buf.len());
+    buf
+  }
+
+  #[test]
+  fn test_varint_encoding() {
+    assert_eq!(encoded(Varint(0u64)), [0x00]);
+    assert_eq!(encoded(Varint(127u64)), [0x7f]);
+    assert_eq!(encoded(Varint(300u32)), [0xac, 0x02]);
+    assert_eq!(encoded(Varint(u64::MAX)).len(), 10);
+    assert_eq!(encoded(Varint(u128::MAX)).len(), 19);
+    assert_eq!(encoded(Varint(-1i32)).len(), 5);
+
+    assert_eq!(encoded(ZigZag(0i64)), [0x00]);
+    assert_eq!(encoded(ZigZag(-1i64)), [0x01]);
+    assert_eq!(encoded(ZigZag(1i64)), [0x02]);
+    assert_eq!(encoded(ZigZag(-64i8)), [0x7f]);
+    assert_eq!(encoded(ZigZag(i128::MIN)).len(), 19);
+
+    for val in [i64::MIN, -2, -1, 0, 1, i64::MAX] {
+      let (_, decoded) = <ZigZag<i64> as Transformable>::decode(&encoded(ZigZag(val))).unwrap();
+      assert_eq!(decoded.0, val);
+    }
+
+    let (_, decoded) =
+      <Varint<u128> as Transformable>::decode(&encoded(Varint(u128::MAX))).unwrap();
+    assert_eq!(decoded.0, u128::MAX);
+  }
+
+  #[test]
+  fn test_varint_overflow() {
+    assert_eq!(
+      <Varint<u8> as Transformable>::decode(&[0xac, 0x02]).unwrap_err(),
+      VarintTransformError::Overflow
+    );
+    assert_eq!(
+      <Varint<u128> as Transformable>::decode(&[0xff; 19]).unwrap_err(),
+      VarintTransformError::Overflow
+    );
+    assert_eq!(
+      <Varint<u64> as Transformable>::decode(&[0x80]).unwrap_err(),
+      VarintTransformError::NotEnoughBytes
+    );
+    assert_eq!(
+      <Varint<u8> as Transformable>::decode(&[0x80]).unwrap_err(),
+      VarintTransformError::NotEnoughBytes
+    );
+    assert_eq!(
+      Transformable::encode(&Varint(300u64), &mut [0; 1]).unwrap_err(),
+      VarintTransformError::EncodeBufferTooSmall
+    );
+  }
+
+  #[test]
+  fn test_non_minimal_encoding() {
+    // `1`, padded with zero continuation bytes
+    let padded = [0x81, 0x80, 0x80, 0x00];
+
+    // within the max length of the integer
+    assert_eq!(
+      <Varint<u8> as Transformable>::decode(&[0x81, 0x00]).unwrap(),
+      (2, Varint(1))
+    );
+    assert_eq!(
+      <Varint<u32> as Transformable>::decode(&padded).unwrap(),
+      (4, Varint(1))
+    );
+
+    // longer than the max length of the integer, from a slice and from a reader alike
+    assert_eq!(
+      <Varint<u8> as Transformable>::decode(&padded).unwrap_err(),
+      VarintTransformError::Overflow
+    );
+    #[cfg(feature = "std")]
+    {
+      let err = <Varint<u8> as Transformable>::decode_from_reader(&mut &padded[..]).unwrap_err();
+      assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+      assert_eq!(
+        err
+          .into_inner()
+          .unwrap()
+          .downcast_ref::<VarintTransformError>(),
+        Some(&VarintTransformError::Overflow)
+      );
+    }
+  }
+}
@@ -0,0 +1,484 @@
+use super::*;
+
+use byteorder::{ByteOrder, NetworkEndian};
+
+use crate::TrailingBytes;
+
+/// The length of the total length of the message, which comes first.
+const LEN_SIZE: usize = core::mem::size_of::<u32>();
+
+/// Decodes a value encoded by an older version of its type, and migrates it into the current one.
+///
+/// Returns the number of bytes read and the value, like [`Transformable::decode`].
+pub type Migration<T> = fn(&[u8]) -> Result<(usize, T), <T as Transformable>::Error>;
+
+/// A type whose encoding changes across versions, which can be wrapped in [`Versioned`].
+///
+/// When a field is added, bump the [`VERSION`](Migrate::VERSION) and register a migration which
+/// decodes the previous encoding and fills the new field with a default.
+pub trait Migrate: Transformable + Sized {
+  /// The current version, which values are encoded with.
+  const VERSION: u32;
+
+  /// Returns the decoder of the given older version, or `None` if it can not be decoded anymore.
+  ///
+  /// The default implementation returns `None`, only the current version can be decoded.
+  fn migration(version: u32) -> Option<Migration<Self>> {
+    let _ = version;
+    None
+  }
+}
+
+/// The error type for errors that get returned when encoding or decoding [`Versioned`] fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionedTransformError<E> {
+  /// Returned when the buffer is too small to encode.
+  EncodeBufferTooSmall,
+  /// Returned when the buffer does not contain the whole message.
+  NotEnoughBytes,
+  /// Returned when the encoded message is longer than `u32::MAX` bytes.
+  TooLarge(usize),
+  /// Returned when decoding the version fails.
+  Version(VarintTransformError),
+  /// Returned when encoding the value fails.
+  Transform(E),
+  /// Returned when decoding the value fails.
+  Decode {
+    /// The offset of the value in the message.
+    offset: usize,
+    /// The error of the value.
+    source: E,
+  },
+  /// Returned when the value is encoded by a newer version than the current one.
+  UnsupportedVersion {
+    /// The version of the value.
+    got: u32,
+    /// The current version.
+    max: u32,
+  },
+  /// Returned when the value is encoded by an older version which has no migration.
+  MissingMigration(u32),
+  /// Returned when the value does not consume all of its message.
+  TrailingBytes(TrailingBytes),
+}
+
+forward_decode_context!(VersionedTransformError, Decode);
+
+impl<E: Into<TransformError>> From<VersionedTransformError<E>> for TransformError {
+  fn from(err: VersionedTransformError<E>) -> Self {
+    match err {
+      VersionedTransformError::EncodeBufferTooSmall => Self::from_kind(ErrorKind::BufferTooSmall),
+      VersionedTransformError::NotEnoughBytes => Self::from_kind(ErrorKind::UnexpectedEof),
+      VersionedTransformError::TooLarge(len) => Self::wrap(
+        ErrorKind::InvalidValue,
+        VersionedTransformError::<core::convert::Infallible>::TooLarge(len),
+      ),
+      VersionedTransformError::Version(e) => e.into(),
+      VersionedTransformError::Transform(e) => e.into(),
+      VersionedTransformError::Decode { offset, source } => source.into().at_offset(offset),
+      VersionedTransformError::UnsupportedVersion { got, max } => Self::wrap(
+        ErrorKind::InvalidValue,
+        VersionedTransformError::<core::convert::Infallible>::UnsupportedVersion { got, max },
+      ),
+      VersionedTransformError::MissingMigration(v) => Self::wrap(
+        ErrorKind::InvalidValue,
+        VersionedTransformError::<core::convert::Infallible>::MissingMigration(v),
+      ),
+      VersionedTransformError::TrailingBytes(e) => e.into(),
+    }
+  }
+}
+
+impl<E> From<TrailingBytes> for VersionedTransformError<E> {
+  #[inline]
+  fn from(err: TrailingBytes) -> Self {
+    Self::TrailingBytes(err)
+  }
+}
+
+impl<E: core::fmt::Display> core::fmt::Display for VersionedTransformError<E> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::EncodeBufferTooSmall => write!(
+        f,
+        "buffer is too small, use `Transformable::encoded_len` to pre-allocate a buffer with enough space"
+      ),
+      Self::NotEnoughBytes => write!(f, "not enough bytes to decode"),
+      Self::TooLarge(len) => write!(f, "message of {len} bytes is too large to be versioned"),
+      Self::Version(e) => write!(f, "{e}"),
+      Self::Transform(e) => write!(f, "{e}"),
+      Self::Decode { source, .. } => write!(f, "{source}"),
+      Self::UnsupportedVersion { got, max } => write!(
+        f,
+        "unsupported version {got}, the max supported version is {max}"
+      ),
+      Self::MissingMigration(version) => write!(f, "no migration from version {version}"),
+      Self::TrailingBytes(e) => write!(f, "{e}"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for VersionedTransformError<E> {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Version(e) => Some(e),
+      Self::Transform(e) => Some(e),
+      Self::Decode { source, .. } => Some(source),
+      Self::TrailingBytes(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+/// A value encoded together with the [version](Migrate::VERSION) of its type.
+///
+/// Values encoded by older versions are decoded with the matching [migration](Migrate::migration),
+/// so binaries of different versions can talk to each other during a rolling upgrade, as long as
+/// the newer one registers a migration for the older one. Values encoded by newer versions fail
+/// with [`UnsupportedVersion`](VersionedTransformError::UnsupportedVersion).
+///
+/// The value is preceded by the total length of the message, as a 4 bytes big-endian integer, and
+/// the version, in LEB128 variable length format, so its old encodings are read from a stream
+/// without knowing how long they are.
+///
+/// It is [`ContextFree`], as the migrations decode the old versions without a context.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Versioned<T> {
+  value: T,
+}
+
+impl<T> Versioned<T> {
+  /// Wraps the given value.
+  #[inline]
+  pub const fn new(value: T) -> Self {
+    Self { value }
+  }
+
+  /// Returns a reference to the value.
+  #[inline]
+  pub const fn get(&self) -> &T {
+    &self.value
+  }
+
+  /// Consumes the wrapper, returning the value.
+  #[inline]
+  pub fn into_inner(self) -> T {
+    self.value
+  }
+}
+
+impl<T> From<T> for Versioned<T> {
+  #[inline]
+  fn from(value: T) -> Self {
+    Self::new(value)
+  }
+}
+
+impl<T> core::ops::Deref for Versioned<T> {
+  type Target = T;
+
+  #[inline]
+  fn deref(&self) -> &T {
+    &self.value
+  }
+}
+
+impl<T: Migrate> Versioned<T> {
+  /// Decodes the value of the given version from its message, decoding the current version with
+  /// `decode`.
+  fn decode_versioned(
+    src: &[u8],
+    decode: impl FnOnce(&[u8]) -> Result<(usize, T), T::Error>,
+  ) -> Result<(usize, Self), VersionedTransformError<T::Error>> {
+    if src.len() < LEN_SIZE {
+      return Err(VersionedTransformError::NotEnoughBytes);
+    }
+
+    let len = NetworkEndian::read_u32(src) as usize;
+    if len < LEN_SIZE || src.len() < len {
+      return Err(VersionedTransformError::NotEnoughBytes);
+    }
+
+    let (readed, Varint(version)) = <Varint<u32> as Transformable>::decode(&src[LEN_SIZE..len])
+      .map_err(VersionedTransformError::Version)?;
+    let start = LEN_SIZE + readed;
+    let payload = &src[start..len];
+    let (readed, val) = match version {
+      v if v == T::VERSION => decode(payload),
+      v if v > T::VERSION => {
+        return Err(VersionedTransformError::UnsupportedVersion {
+          got: v,
+          max: T::VERSION,
+        })
+      }
+      v => match T::migration(v) {
+        Some(migrate) => migrate(payload),
+        None => return Err(VersionedTransformError::MissingMigration(v)),
+      },
+    }
+    .map_err(|source| VersionedTransformError::Decode {
+      offset: start,
+      source,
+    })?;
+    TrailingBytes::check(readed, payload.len())?;
+    Ok((len, Self::new(val)))
+  }
+}
+
+impl<T: Migrate> Transformable for Versioned<T> {
+  type Error = VersionedTransformError<T::Error>;
+
+  fn encode(&self, dst: &mut [u8]) -> Result<usize, Self::Error> {
+    let encoded_len = Transformable::encoded_len(self);
+    if encoded_len > u32::MAX as usize {
+      return Err(Self::Error::TooLarge(encoded_len));
+    }
+
+    if dst.len() < encoded_len {
+      return Err(Self::Error::EncodeBufferTooSmall);
+    }
+
+    let offset = LEN_SIZE
+      + Transformable::encode(&Varint(T::VERSION), &mut dst[LEN_SIZE..])
+        .map_err(VersionedTransformError::Version)?;
+    let len = offset
+      + Transformable::encode(&self.value, &mut dst[offset..])
+        .map_err(VersionedTransformError::Transform)?;
+    NetworkEndian::write_u32(dst, len as u32);
+    Ok(len)
+  }
+
+  fn encoded_len(&self) -> usize {
+    LEN_SIZE
+      + Transformable::encoded_len(&Varint(T::VERSION))
+      + Transformable::encoded_len(&self.value)
+  }
+
+  fn decode(src: &[u8]) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized,
+  {
+    Self::decode_versioned(src, T::decode)
+  }
+
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn is_incomplete(err: &Self::Error) -> bool
+  where
+    Self: Sized,
+  {
+    matches!(err, VersionedTransformError::NotEnoughBytes)
+  }
+
+  fn decode_limited(src: &[u8], budget: &mut DecodeBudget) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized,
+  {
+    Self::decode_versioned(src, |payload| T::decode_limited(payload, budget))
+  }
+}
+
+impl<T: Migrate> ContextFree for Versioned<T> {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// The v1 of the member, without the incarnation.
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  struct MemberV1 {
+    id: u64,
+  }
+
+  impl Transformable for MemberV1 {
+    type Error = NumberTransformError;
+
+    fn encode(&self, dst: &mut [u8]) -> Result<usize, Self::Error> {
+      Transformable::encode(&self.id, dst)
+    }
+
+    fn encoded_len(&self) -> usize {
+      8
+    }
+
+    fn decode(src: &[u8]) -> Result<(usize, Self), Self::Error> {
+      <u64 as Transformable>::decode(src).map(|(readed, id)| (readed, Self { id }))
+    }
+  }
+
+  impl Migrate for MemberV1 {
+    const VERSION: u32 = 1;
+  }
+
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  struct Member {
+    id: u64,
+    incarnation: u32,
+  }
+
+  impl Transformable for Member {
+    type Error = NumberTransformError;
+
+    fn encode(&self, dst: &mut [u8]) -> Result<usize, Self::Error> {
+      let len = Transformable::encode(&self.id, dst)?;
+      Transformable::encode(&self.incarnation, &mut dst[len..]).map(|n| len + n)
+    }
+
+    fn encoded_len(&self) -> usize {
+      12
+    }
+
+    fn decode(src: &[u8]) -> Result<(usize, Self), Self::Error> {
+      let (readed, id) = <u64 as Transformable>::decode(src)?;
+      let (n, incarnation) = <u32 as Transformable>::decode(&src[readed..])?;
+      Ok((readed + n, Self { id, incarnation }))
+    }
+  }
+
+  impl Migrate for Member {
+    const VERSION: u32 = 2;
+
+    fn migration(version: u32) -> Option<Migration<Self>> {
+      match version {
+        1 => Some(|src| {
+          let (readed, MemberV1 { id }) = <MemberV1 as Transformable>::decode(src)?;
+          Ok((readed, Member { id, incarnation: 0 }))
+        }),
+        _ => None,
+      }
+    }
+  }
+
+  test_transformable!(Versioned<Member> => test_versioned_transformable(Versioned::new(Member { id: rand::random(), incarnation: rand::random() })));
+
+  #[test]
+  fn test_migration() {
+    let v1 = Versioned::new(MemberV1 { id: 7 });
+    let mut buf = [0u8; 64];
+    let len = Transformable::encode(&v1, &mut buf).unwrap();
+    assert_eq!(len, 4 + 1 + 8);
+    assert_eq!(&buf[..5], &[0, 0, 0, 13, 1]);
+    assert_eq!(
+      <Versioned<Member> as Transformable>::decode(&buf[..len]).unwrap(),
+      (
+        len,
+        Versioned::new(Member {
+          id: 7,
+          incarnation: 0
+        })
+      )
+    );
+
+    #[cfg(feature = "std")]
+    {
+      let (readed, member) =
+        <Versioned<Member> as Transformable>::decode_from_reader(&mut &buf[..len]).unwrap();
+      assert_eq!(readed, len);
+      assert_eq!(member.incarnation, 0);
+    }
+  }
+
+  #[test]
+  fn test_unsupported_version() {
+    let v2 = Versioned::new(Member {
+      id: 7,
+      incarnation: 3,
+    });
+    let mut buf = [0u8; 64];
+    let len = Transformable::encode(&v2, &mut buf).unwrap();
+    assert!(matches!(
+      <Versioned<MemberV1> as Transformable>::decode(&buf[..len]),
+      Err(VersionedTransformError::UnsupportedVersion { got: 2, max: 1 })
+    ));
+
+    // a version without a migration
+    buf[4] = 0;
+    assert!(matches!(
+      <Versioned<Member> as Transformable>::decode(&buf[..len]),
+      Err(VersionedTransformError::MissingMigration(0))
+    ));
+
+    assert!(matches!(
+      <Versioned<Member> as Transformable>::decode(&buf[..len - 1]),
+      Err(VersionedTransformError::NotEnoughBytes)
+    ));
+  }
+
+  /// A member whose errors record where they happened.
+  #[cfg(feature = "std")]
+  #[derive(Debug, Default, Clone, PartialEq, Eq)]
+  struct Tagged {
+    id: u64,
+    meta: std::vec::Vec<u8>,
+  }
+
+  #[cfg(feature = "std")]
+  impl_tagged!(Tagged: TransformError {
+    1 => id,
+    2 => meta,
+  });
+
+  #[cfg(feature = "std")]
+  impl Migrate for Tagged {
+    const VERSION: u32 = 1;
+  }
+
+  #[test]
+  #[cfg(feature = "std")]
+  fn test_error_offset() {
+    let tagged = Tagged {
+      id: 1,
+      meta: std::vec![1, 2, 3],
+    };
+    let mut encoded = Transformable::encode_to_vec(&Versioned::new(tagged)).unwrap();
+
+    // the value starts after the length and the version, and its meta after the length of the
+    // value, the header and the id field, and the header of the meta field
+    let meta = 4 + 1 + 4 + 2 + 8 + 2;
+    assert_eq!(&encoded[meta..meta + 4], &[0, 0, 0, 3]);
+    encoded[meta + 3] = 4;
+    let err = <Versioned<Tagged> as Transformable>::decode(&encoded).unwrap_err();
+    assert!(matches!(
+      err,
+      VersionedTransformError::Decode { offset: 5, .. }
+    ));
+    let err = TransformError::from(err);
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    assert_eq!(err.offset(), Some(meta));
+    assert_eq!(err.path(), &[crate::PathSegment::Field("meta")]);
+  }
+
+  #[test]
+  #[cfg(target_pointer_width = "64")]
+  fn test_too_large() {
+    /// A value which claims to be longer than a message can be.
+    struct Huge;
+
+    impl Transformable for Huge {
+      type Error = NumberTransformError;
+
+      fn encode(&self, _: &mut [u8]) -> Result<usize, Self::Error> {
+        unreachable!()
+      }
+
+      fn encoded_len(&self) -> usize {
+        u32::MAX as usize
+      }
+
+      fn decode(_: &[u8]) -> Result<(usize, Self), Self::Error> {
+        unreachable!()
+      }
+    }
+
+    impl Migrate for Huge {
+      const VERSION: u32 = 1;
+    }
+
+    let len = u32::MAX as usize + LEN_SIZE + 1;
+    assert!(matches!(
+      Transformable::encode(&Versioned::new(Huge), &mut []),
+      Err(VersionedTransformError::TooLarge(l)) if l == len
+    ));
+  }
+}
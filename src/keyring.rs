@@ -0,0 +1,115 @@
+//! Keyrings for `Sealed` and `Authenticated` values.
+//!
+//! Every key has a one byte id, which is sent along with the value, so keys can be rotated
+//! without a flag day: add the new key everywhere, make it the primary key, then remove the old
+//! one once no peer uses it anymore.
+
+#[cfg(not(feature = "std"))]
+use ::alloc::vec::Vec;
+
+/// The key of [`Authenticated`](crate::Authenticated) values, created from a secret with
+/// [`Mac::new_from_slice`](hmac::Mac::new_from_slice).
+#[cfg(feature = "hmac")]
+#[cfg_attr(docsrs, doc(cfg(feature = "hmac")))]
+pub type HmacSha256 = hmac::Hmac<sha2::Sha256>;
+
+/// A set of keys, one of which is used to seal or authenticate new values.
+pub trait Keyring {
+  /// The type of the keys.
+  type Key;
+
+  /// Returns the id and the key new values are sealed or authenticated with.
+  fn primary(&self) -> (u8, &Self::Key);
+
+  /// Returns the key with the given id.
+  fn get(&self, id: u8) -> Option<&Self::Key>;
+}
+
+/// A [`Keyring`] which holds its keys in memory.
+#[derive(Clone)]
+pub struct Keys<K> {
+  primary: u8,
+  keys: Vec<(u8, K)>,
+}
+
+impl<K> Keys<K> {
+  /// Creates a keyring with the given primary key.
+  #[inline]
+  pub fn new(id: u8, key: K) -> Self {
+    Self {
+      primary: id,
+      keys: ::alloc::vec![(id, key)],
+    }
+  }
+
+  /// Returns the id of the primary key.
+  #[inline]
+  pub const fn primary_id(&self) -> u8 {
+    self.primary
+  }
+
+  /// Returns the ids of all the keys.
+  #[inline]
+  pub fn ids(&self) -> impl Iterator<Item = u8> + '_ {
+    self.keys.iter().map(|(id, _)| *id)
+  }
+
+  /// Adds a key which is only used to open or verify values, returning the key it replaces.
+  pub fn insert(&mut self, id: u8, key: K) -> Option<K> {
+    match self.keys.iter_mut().find(|(k, _)| *k == id) {
+      Some((_, old)) => Some(core::mem::replace(old, key)),
+      None => {
+        self.keys.push((id, key));
+        None
+      }
+    }
+  }
+
+  /// Adds a key and makes it the primary key, returning the key it replaces.
+  ///
+  /// The previous primary key is kept, so values sealed or authenticated with it can still be
+  /// opened or verified.
+  pub fn rotate(&mut self, id: u8, key: K) -> Option<K> {
+    let old = self.insert(id, key);
+    self.primary = id;
+    old
+  }
+
+  /// Removes the key with the given id.
+  ///
+  /// The primary key can not be removed, returns `None` for it.
+  pub fn remove(&mut self, id: u8) -> Option<K> {
+    if id == self.primary {
+      return None;
+    }
+
+    let idx = self.keys.iter().position(|(k, _)| *k == id)?;
+    Some(self.keys.swap_remove(idx).1)
+  }
+}
+
+// Only shows the id of the primary key, the keys are secret.
+impl<K> core::fmt::Debug for Keys<K> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("Keys")
+      .field("primary", &self.primary)
+      .finish_non_exhaustive()
+  }
+}
+
+impl<K> Keyring for Keys<K> {
+  type Key = K;
+
+  #[inline]
+  fn primary(&self) -> (u8, &K) {
+    self
+      .get(self.primary)
+      .map(|key| (self.primary, key))
+      .expect("the primary key is never removed")
+  }
+
+  #[inline]
+  fn get(&self, id: u8) -> Option<&K> {
+    self.keys.iter().find(|(k, _)| *k == id).map(|(_, key)| key)
+  }
+}
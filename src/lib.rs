@@ -25,9 +25,52 @@ macro_rules! test_transformable {
   };
 }
 
-#[cfg(any(feature = "alloc", feature = "std"))]
+/// Implements [`DecodeContext`] for the error of a wrapper, which forwards the context to the
+/// error of the wrapped value, in the `Transform` variant and in the given `{ offset, source }`
+/// variant, if any.
+#[allow(unused_macros)]
+macro_rules! forward_decode_context {
+  ($ty:ident $(, $decode:ident)?) => {
+    impl<E: $crate::DecodeContext> $crate::DecodeContext for $ty<E> {
+      fn in_field(self, name: &'static str) -> Self {
+        match self {
+          Self::Transform(e) => Self::Transform(e.in_field(name)),
+          $(Self::$decode { offset, source } => Self::$decode {
+            offset,
+            source: source.in_field(name),
+          },)?
+          e => e,
+        }
+      }
+
+      fn at_index(self, index: usize) -> Self {
+        match self {
+          Self::Transform(e) => Self::Transform(e.at_index(index)),
+          $(Self::$decode { offset, source } => Self::$decode {
+            offset,
+            source: source.at_index(index),
+          },)?
+          e => e,
+        }
+      }
+
+      fn at_offset(self, offset: usize) -> Self {
+        match self {
+          Self::Transform(e) => Self::Transform(e.at_offset(offset)),
+          $(Self::$decode { offset: start, source } => Self::$decode {
+            offset: start + offset,
+            source,
+          },)?
+          e => e,
+        }
+      }
+    }
+  };
+}
+
+#[cfg(any(feature = "alloc", feature = "std", feature = "embedded-io"))]
 const MESSAGE_SIZE_LEN: usize = core::mem::size_of::<u32>();
-#[cfg(feature = "std")]
+#[cfg(any(feature = "alloc", feature = "embedded-io"))]
 const MAX_INLINED_BYTES: usize = 256;
 
 /// The type can transform its representation between structured and byte form.
@@ -60,6 +103,140 @@ pub trait Transformable {
   fn decode(src: &[u8]) -> Result<(usize, Self), Self::Error>
   where
     Self: Sized;
+
+  /// Decodes the value from the given buffer received over the wire, within the given budget.
+  ///
+  /// Types which allocate while decoding, e.g. byte and string types, check the length prefixes
+  /// against the [`DecodeLimits`] of the budget and take what they allocate from it, and nested
+  /// types track their depth with [`DecodeBudget::enter`].
+  /// The default implementation forwards to [`decode`](Transformable::decode).
+  fn decode_limited(src: &[u8], budget: &mut DecodeBudget) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized,
+  {
+    let _ = budget;
+    Self::decode(src)
+  }
+
+  /// Decodes the value from the given buffer, which must contain exactly one encoded value.
+  ///
+  /// Fails with [`DecodeExactError::TrailingBytes`] if the value does not consume the whole buffer.
+  fn decode_exact(src: &[u8]) -> Result<Self, DecodeExactError<Self::Error>>
+  where
+    Self: Sized,
+  {
+    let (readed, val) = Self::decode(src).map_err(DecodeExactError::Decode)?;
+    TrailingBytes::check(readed, src.len())?;
+    Ok(val)
+  }
+
+  /// Decodes the value from the given buffer, which must start with the canonical encoding of it.
+  ///
+  /// The value is encoded again and compared with the bytes it was decoded from, so non-minimal
+  /// varints, unsorted map entries, etc. fail with [`DecodeCanonicalError::NonCanonical`],
+  /// as long as [`encode`](Transformable::encode) is canonical, see [`canonical`].
+  #[cfg(feature = "alloc")]
+  fn decode_canonical(src: &[u8]) -> Result<(usize, Self), DecodeCanonicalError<Self::Error>>
+  where
+    Self: Sized,
+  {
+    let (readed, val) = Self::decode(src).map_err(DecodeCanonicalError::Decode)?;
+    let encoded = val.encode_to_vec().map_err(DecodeCanonicalError::Decode)?;
+    NonCanonical::check(&encoded, &src[..readed])?;
+    Ok((readed, val))
+  }
+
+  /// Encodes the value into the given [`embedded_io::Write`] writer for transmission.
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn encode_to_embedded_writer<W: embedded_io::Write>(
+    &self,
+    writer: &mut W,
+  ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+    embedded::encode_to(self, writer)
+  }
+
+  /// Encodes the value into the given [`embedded_io_async::Write`] writer for transmission.
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  fn encode_to_embedded_async_writer<W: embedded_io_async::Write>(
+    &self,
+    writer: &mut W,
+  ) -> impl core::future::Future<Output = Result<usize, EmbeddedIoError<W::Error, Self::Error>>> {
+    embedded::encode_to_async(self, writer)
+  }
+
+  /// Decodes the value from the given [`embedded_io::Read`] reader received over the wire.
+  ///
+  /// Returns the number of bytes read from the reader and the struct.
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn decode_from_embedded_reader<R: embedded_io::Read>(
+    reader: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    embedded::decode_from(reader)
+  }
+
+  /// Decodes the value from the given [`embedded_io_async::Read`] reader received over the wire.
+  ///
+  /// Returns the number of bytes read from the reader and the struct.
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  fn decode_from_embedded_async_reader<R: embedded_io_async::Read>(
+    reader: &mut R,
+  ) -> impl core::future::Future<Output = Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>>
+  where
+    Self: Sized,
+  {
+    embedded::decode_from_async(reader)
+  }
+
+  /// Decodes the value from the given [`embedded_io::Read`] reader received over the wire, within
+  /// the given budget.
+  ///
+  /// Exceeding a limit fails with [`EmbeddedIoError::Limit`].
+  /// The default implementation forwards to
+  /// [`decode_from_embedded_reader`](Transformable::decode_from_embedded_reader), but never reads
+  /// more than the max message size from the reader, which is the only limit it enforces. Types
+  /// which allocate, or which decode nested values, override it to check the other limits too,
+  /// like the built-in byte, string and wrapper types do.
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn decode_from_embedded_reader_limited<R: embedded_io::Read>(
+    reader: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    let mut reader = limits::LimitedEmbeddedReader::new(reader, budget.limits().max_message_size());
+    let res = Self::decode_from_embedded_reader(&mut reader);
+    reader.check(res)
+  }
+
+  /// Decodes the value from the given [`embedded_io_async::Read`] reader received over the wire,
+  /// within the given budget.
+  ///
+  /// See [`decode_from_embedded_reader_limited`](Transformable::decode_from_embedded_reader_limited).
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  fn decode_from_embedded_async_reader_limited<R: embedded_io_async::Read>(
+    reader: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> impl core::future::Future<Output = Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>>
+  where
+    Self: Sized,
+  {
+    async move {
+      let mut reader =
+        limits::LimitedEmbeddedReader::new(reader, budget.limits().max_message_size());
+      let res = Self::decode_from_embedded_async_reader(&mut reader).await;
+      reader.check(res)
+    }
+  }
 }
 
 /// The type can transform its representation between structured and byte form.
@@ -83,6 +260,11 @@ pub trait Transformable: Send + Sync + 'static {
 
   /// Encodes the value into the given writer for transmission.
   fn encode_to_writer<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<usize> {
+    let mut slices = GatherList::new();
+    if self.as_io_slices(&mut slices) {
+      return slices.write_to(writer);
+    }
+
     let encoded_len = self.encoded_len();
     if encoded_len <= MAX_INLINED_BYTES {
       let mut buf = [0u8; MAX_INLINED_BYTES];
@@ -104,6 +286,11 @@ pub trait Transformable: Send + Sync + 'static {
   ) -> impl std::future::Future<Output = std::io::Result<usize>> + Send {
     use futures_util::io::AsyncWriteExt;
     async move {
+      let mut slices = GatherList::new();
+      if self.as_io_slices(&mut slices) {
+        return slices.write_to_async(writer).await;
+      }
+
       let encoded_len = self.encoded_len();
       if encoded_len <= MAX_INLINED_BYTES {
         let mut buf = [0u8; MAX_INLINED_BYTES];
@@ -121,6 +308,17 @@ pub trait Transformable: Send + Sync + 'static {
   /// This is used to pre-allocate a buffer for encoding.
   fn encoded_len(&self) -> usize;
 
+  /// Describes the encoded form of the value as a list of byte segments, which can be written
+  /// out with vectored writes, without copying large payloads into an intermediate buffer.
+  ///
+  /// Returns `false` if the value can not describe itself this way, in which case the contents of
+  /// `slices` are unspecified and the value is encoded with [`encode`](Transformable::encode) instead.
+  /// The default implementation returns `false`.
+  fn as_io_slices<'a>(&'a self, slices: &mut GatherList<'a>) -> bool {
+    let _ = slices;
+    false
+  }
+
   /// Decodes the value from the given buffer received over the wire.
   ///
   /// Returns the number of bytes read from the buffer and the struct.
@@ -140,6 +338,7 @@ pub trait Transformable: Send + Sync + 'static {
     let mut len = [0u8; MESSAGE_SIZE_LEN];
     reader.read_exact(&mut len)?;
     let msg_len = NetworkEndian::read_u32(&len) as usize;
+    let rest = utils::frame_rest_len(msg_len)?;
 
     if msg_len <= MAX_INLINED_BYTES {
       let mut buf = [0u8; MAX_INLINED_BYTES];
@@ -147,13 +346,46 @@ pub trait Transformable: Send + Sync + 'static {
       reader.read_exact(&mut buf[MESSAGE_SIZE_LEN..msg_len])?;
       Self::decode(&buf[..msg_len]).map_err(utils::invalid_data)
     } else {
-      let mut buf = ::std::vec![0u8; msg_len];
-      buf[..MESSAGE_SIZE_LEN].copy_from_slice(&len);
-      reader.read_exact(&mut buf[MESSAGE_SIZE_LEN..])?;
+      let mut buf = len.to_vec();
+      utils::read_exact_to_vec(reader, &mut buf, rest)?;
       Self::decode(&buf).map_err(utils::invalid_data)
     }
   }
 
+  /// Decodes the value from the given buffered reader received over the wire.
+  ///
+  /// If the whole message is already buffered, the value is decoded directly from
+  /// [`fill_buf`](std::io::BufRead::fill_buf) without copying it into an intermediate buffer.
+  /// Otherwise, this falls back to [`decode_from_reader`](Transformable::decode_from_reader), if decoding
+  /// from the buffer fails with an error which [`is_incomplete`](Transformable::is_incomplete). Any other
+  /// error is returned as an [`InvalidData`](std::io::ErrorKind::InvalidData) error.
+  ///
+  /// Returns the number of bytes read from the reader and the struct.
+  fn decode_from_buf_reader<R: std::io::BufRead>(reader: &mut R) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    decode_from_buf_reader(
+      reader,
+      Self::decode,
+      Self::is_incomplete,
+      Self::decode_from_reader,
+    )
+  }
+
+  /// Returns `true` if the given error of [`decode`](Transformable::decode) only means that the buffer
+  /// ends before the value, so the value may still be decoded once more bytes are available.
+  ///
+  /// The default implementation checks whether the first [`TransformError`] or I/O error in the
+  /// source chain of the error is of kind [`UnexpectedEof`](ErrorKind::UnexpectedEof). The
+  /// built-in types recognize their own errors.
+  fn is_incomplete(err: &Self::Error) -> bool
+  where
+    Self: Sized,
+  {
+    utils::is_unexpected_eof(err)
+  }
+
   /// Decodes the value from the given async reader received over the wire.
   ///
   /// Returns the number of bytes read from the reader and the struct.
@@ -172,6 +404,7 @@ pub trait Transformable: Send + Sync + 'static {
       let mut len = [0u8; MESSAGE_SIZE_LEN];
       reader.read_exact(&mut len).await?;
       let msg_len = NetworkEndian::read_u32(&len) as usize;
+      let rest = utils::frame_rest_len(msg_len)?;
 
       if msg_len <= MAX_INLINED_BYTES {
         let mut buf = [0u8; MAX_INLINED_BYTES];
@@ -181,13 +414,283 @@ pub trait Transformable: Send + Sync + 'static {
           .await?;
         Self::decode(&buf[..msg_len]).map_err(utils::invalid_data)
       } else {
-        let mut buf = vec![0u8; msg_len];
-        buf[..MESSAGE_SIZE_LEN].copy_from_slice(&len);
-        reader.read_exact(&mut buf[MESSAGE_SIZE_LEN..]).await?;
+        let mut buf = len.to_vec();
+        utils::read_exact_to_vec_async(reader, &mut buf, rest).await?;
         Self::decode(&buf).map_err(utils::invalid_data)
       }
     }
   }
+
+  /// Decodes the value from the given buffer received over the wire, within the given budget.
+  ///
+  /// Types which allocate while decoding, e.g. byte and string types, check the length prefixes
+  /// against the [`DecodeLimits`] of the budget and take what they allocate from it, and nested
+  /// types track their depth with [`DecodeBudget::enter`].
+  /// The default implementation forwards to [`decode`](Transformable::decode).
+  fn decode_limited(src: &[u8], budget: &mut DecodeBudget) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized,
+  {
+    let _ = budget;
+    Self::decode(src)
+  }
+
+  /// Decodes the value from the given reader received over the wire, within the given budget.
+  ///
+  /// Exceeding a limit fails with an [`InvalidData`](std::io::ErrorKind::InvalidData) error,
+  /// which wraps a [`LimitError`].
+  /// The default implementation forwards to [`decode_from_reader`](Transformable::decode_from_reader),
+  /// but never reads more than the max message size from the reader, which is the only limit it
+  /// enforces. Types which allocate, or which decode nested values, override it to check the
+  /// other limits too, like the built-in byte, string and wrapper types do.
+  fn decode_from_reader_limited<R: std::io::Read>(
+    reader: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    let limit = budget.limits().max_message_size();
+    Self::decode_from_reader(&mut LimitedReader::new(reader, limit))
+  }
+
+  /// Decodes the value from the given async reader received over the wire, within the given budget.
+  ///
+  /// See [`decode_from_reader_limited`](Transformable::decode_from_reader_limited).
+  #[cfg(feature = "async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+  fn decode_from_async_reader_limited<R: futures_util::io::AsyncRead + Send + Unpin>(
+    reader: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> impl std::future::Future<Output = std::io::Result<(usize, Self)>> + Send
+  where
+    Self: Sized,
+  {
+    let limit = budget.limits().max_message_size();
+    async move { Self::decode_from_async_reader(&mut LimitedReader::new(reader, limit)).await }
+  }
+
+  /// Decodes the value from the given buffer, which must contain exactly one encoded value.
+  ///
+  /// Fails with [`DecodeExactError::TrailingBytes`] if the value does not consume the whole buffer.
+  fn decode_exact(src: &[u8]) -> Result<Self, DecodeExactError<Self::Error>>
+  where
+    Self: Sized,
+  {
+    let (readed, val) = Self::decode(src).map_err(DecodeExactError::Decode)?;
+    TrailingBytes::check(readed, src.len())?;
+    Ok(val)
+  }
+
+  /// Decodes the value from the given buffer, which must start with the canonical encoding of it.
+  ///
+  /// The value is encoded again and compared with the bytes it was decoded from, so non-minimal
+  /// varints, unsorted map entries, etc. fail with [`DecodeCanonicalError::NonCanonical`],
+  /// as long as [`encode`](Transformable::encode) is canonical, see [`canonical`].
+  fn decode_canonical(src: &[u8]) -> Result<(usize, Self), DecodeCanonicalError<Self::Error>>
+  where
+    Self: Sized,
+  {
+    let (readed, val) = Self::decode(src).map_err(DecodeCanonicalError::Decode)?;
+    let encoded = val.encode_to_vec().map_err(DecodeCanonicalError::Decode)?;
+    NonCanonical::check(&encoded, &src[..readed])?;
+    Ok((readed, val))
+  }
+
+  /// Decodes the value from the given reader, which must consume all the bytes it reads.
+  ///
+  /// The value is decoded with [`decode_from_reader`](Transformable::decode_from_reader), in its own
+  /// format, and the bytes it reports as consumed are checked against the bytes read from the
+  /// reader. Values which start with their total length, like the default
+  /// [`decode_from_reader`](Transformable::decode_from_reader) expects, read their whole frame, so if they
+  /// do not consume all of it, this fails with an [`InvalidData`](std::io::ErrorKind::InvalidData)
+  /// error, which wraps a [`TrailingBytes`]. Self-delimiting values, e.g. integers, bytes and
+  /// strings, never read more than they consume.
+  ///
+  /// Nothing is read past the value, so the following values stay in the reader.
+  fn decode_from_reader_exact<R: std::io::Read>(reader: &mut R) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    let mut reader = utils::CountingReader::new(reader);
+    let (readed, val) = Self::decode_from_reader(&mut reader)?;
+    TrailingBytes::check(readed, reader.count()).map_err(utils::invalid_data)?;
+    Ok((readed, val))
+  }
+
+  /// Decodes the value from the given async reader, which must consume all the bytes it reads.
+  ///
+  /// See [`decode_from_reader_exact`](Transformable::decode_from_reader_exact).
+  #[cfg(feature = "async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+  fn decode_from_async_reader_exact<R: futures_util::io::AsyncRead + Send + Unpin>(
+    reader: &mut R,
+  ) -> impl std::future::Future<Output = std::io::Result<(usize, Self)>> + Send
+  where
+    Self: Sized,
+  {
+    async move {
+      let mut reader = utils::CountingReader::new(reader);
+      let (readed, val) = Self::decode_from_async_reader(&mut reader).await?;
+      TrailingBytes::check(readed, reader.count()).map_err(utils::invalid_data)?;
+      Ok((readed, val))
+    }
+  }
+
+  /// Encodes the value into the given buffer, taking the ownership of it.
+  ///
+  /// The buffer is cleared and resized to fit the encoded value, then returned back
+  /// to the caller, so it can be reused across messages.
+  #[cfg(feature = "owned-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+  fn encode_owned(&self, buf: Vec<u8>) -> (Result<usize, Self::Error>, Vec<u8>) {
+    owned::encode_owned(self, buf)
+  }
+
+  /// Encodes the value into the given completion-based writer for transmission.
+  ///
+  /// Returns the number of bytes written and the buffer, so it can be reused.
+  #[cfg(feature = "owned-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+  fn encode_to_owned_writer<W: OwnedWrite>(
+    &self,
+    writer: &mut W,
+    buf: Vec<u8>,
+  ) -> impl std::future::Future<Output = (std::io::Result<usize>, Vec<u8>)> {
+    owned::encode_to(self, writer, buf)
+  }
+
+  /// Decodes the value from the given completion-based reader received over the wire.
+  ///
+  /// Returns the number of bytes read from the reader and the struct, and the buffer,
+  /// so it can be reused.
+  #[cfg(feature = "owned-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+  fn decode_from_owned_reader<R: OwnedRead>(
+    reader: &mut R,
+    buf: Vec<u8>,
+  ) -> impl std::future::Future<Output = (std::io::Result<(usize, Self)>, Vec<u8>)>
+  where
+    Self: Sized,
+  {
+    owned::decode_from(reader, buf)
+  }
+
+  /// Decodes the value from the given completion-based reader received over the wire, within the
+  /// given budget.
+  ///
+  /// Exceeding a limit fails with an [`InvalidData`](std::io::ErrorKind::InvalidData) error,
+  /// which wraps a [`LimitError`].
+  /// The default implementation forwards to
+  /// [`decode_from_owned_reader`](Transformable::decode_from_owned_reader), but never reads, nor
+  /// allocates, more than the max message size.
+  #[cfg(feature = "owned-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+  fn decode_from_owned_reader_limited<R: OwnedRead>(
+    reader: &mut R,
+    buf: Vec<u8>,
+    budget: &mut DecodeBudget,
+  ) -> impl std::future::Future<Output = (std::io::Result<(usize, Self)>, Vec<u8>)>
+  where
+    Self: Sized,
+  {
+    let limit = budget.limits().max_message_size();
+    async move {
+      Self::decode_from_owned_reader(&mut owned::LimitedOwnedReader::new(reader, limit), buf).await
+    }
+  }
+
+  /// Encodes the value into the given [`embedded_io::Write`] writer for transmission.
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn encode_to_embedded_writer<W: embedded_io::Write>(
+    &self,
+    writer: &mut W,
+  ) -> Result<usize, EmbeddedIoError<W::Error, Self::Error>> {
+    embedded::encode_to(self, writer)
+  }
+
+  /// Encodes the value into the given [`embedded_io_async::Write`] writer for transmission.
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  fn encode_to_embedded_async_writer<W: embedded_io_async::Write>(
+    &self,
+    writer: &mut W,
+  ) -> impl core::future::Future<Output = Result<usize, EmbeddedIoError<W::Error, Self::Error>>> {
+    embedded::encode_to_async(self, writer)
+  }
+
+  /// Decodes the value from the given [`embedded_io::Read`] reader received over the wire.
+  ///
+  /// Returns the number of bytes read from the reader and the struct.
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn decode_from_embedded_reader<R: embedded_io::Read>(
+    reader: &mut R,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    embedded::decode_from(reader)
+  }
+
+  /// Decodes the value from the given [`embedded_io_async::Read`] reader received over the wire.
+  ///
+  /// Returns the number of bytes read from the reader and the struct.
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  fn decode_from_embedded_async_reader<R: embedded_io_async::Read>(
+    reader: &mut R,
+  ) -> impl core::future::Future<Output = Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>>
+  where
+    Self: Sized,
+  {
+    embedded::decode_from_async(reader)
+  }
+
+  /// Decodes the value from the given [`embedded_io::Read`] reader received over the wire, within
+  /// the given budget.
+  ///
+  /// Exceeding a limit fails with [`EmbeddedIoError::Limit`].
+  /// The default implementation forwards to
+  /// [`decode_from_embedded_reader`](Transformable::decode_from_embedded_reader), but never reads
+  /// more than the max message size from the reader, which is the only limit it enforces. Types
+  /// which allocate, or which decode nested values, override it to check the other limits too,
+  /// like the built-in byte, string and wrapper types do.
+  #[cfg(feature = "embedded-io")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+  fn decode_from_embedded_reader_limited<R: embedded_io::Read>(
+    reader: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>
+  where
+    Self: Sized,
+  {
+    let mut reader = limits::LimitedEmbeddedReader::new(reader, budget.limits().max_message_size());
+    let res = Self::decode_from_embedded_reader(&mut reader);
+    reader.check(res)
+  }
+
+  /// Decodes the value from the given [`embedded_io_async::Read`] reader received over the wire,
+  /// within the given budget.
+  ///
+  /// See [`decode_from_embedded_reader_limited`](Transformable::decode_from_embedded_reader_limited).
+  #[cfg(feature = "embedded-io-async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "embedded-io-async")))]
+  fn decode_from_embedded_async_reader_limited<R: embedded_io_async::Read>(
+    reader: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> impl core::future::Future<Output = Result<(usize, Self), EmbeddedIoError<R::Error, Self::Error>>>
+  where
+    Self: Sized,
+  {
+    async move {
+      let mut reader =
+        limits::LimitedEmbeddedReader::new(reader, budget.limits().max_message_size());
+      let res = Self::decode_from_embedded_async_reader(&mut reader).await;
+      reader.check(res)
+    }
+  }
 }
 
 /// The type can transform its representation between structured and byte form.
@@ -213,6 +716,28 @@ pub trait Encodable {
   /// Returns the encoded length of the value.
   /// This is used to pre-allocate a buffer for encoding.
   fn encoded_len(&self) -> usize;
+
+  /// Feeds the encoded bytes of the value into the given [`Hasher`](core::hash::Hasher),
+  /// e.g. to compute a content hash of the message.
+  ///
+  /// The bytes are exactly the ones [`encode`](Encodable::encode) writes. Small values are encoded
+  /// into an inline buffer, large values are encoded into a heap allocated buffer.
+  ///
+  /// Returns the number of bytes fed into the hasher.
+  #[cfg(feature = "alloc")]
+  fn encode_to_hasher<H: core::hash::Hasher>(&self, hasher: &mut H) -> Result<usize, Self::Error> {
+    utils::encode_to_sink(self, |bytes| hasher.write(bytes))
+  }
+
+  /// Feeds the encoded bytes of the value into the given [`digest::Update`],
+  /// e.g. a SHA-256 or BLAKE3 hasher.
+  ///
+  /// See [`encode_to_hasher`](Encodable::encode_to_hasher).
+  #[cfg(all(feature = "alloc", feature = "digest"))]
+  #[cfg_attr(docsrs, doc(cfg(feature = "digest")))]
+  fn encode_to_digest<D: digest::Update>(&self, digest: &mut D) -> Result<usize, Self::Error> {
+    utils::encode_to_sink(self, |bytes| digest.update(bytes))
+  }
 }
 
 /// The type can transform its representation to byte form.
@@ -236,6 +761,11 @@ pub trait Encodable: Send + Sync {
 
   /// Encodes the value into the given writer for transmission.
   fn encode_to_writer<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<usize> {
+    let mut slices = GatherList::new();
+    if self.as_io_slices(&mut slices) {
+      return slices.write_to(writer);
+    }
+
     let encoded_len = self.encoded_len();
     if encoded_len <= MAX_INLINED_BYTES {
       let mut buf = [0u8; MAX_INLINED_BYTES];
@@ -257,6 +787,11 @@ pub trait Encodable: Send + Sync {
   ) -> impl std::future::Future<Output = std::io::Result<usize>> + Send {
     use futures_util::io::AsyncWriteExt;
     async move {
+      let mut slices = GatherList::new();
+      if self.as_io_slices(&mut slices) {
+        return slices.write_to_async(writer).await;
+      }
+
       let encoded_len = self.encoded_len();
       if encoded_len <= MAX_INLINED_BYTES {
         let mut buf = [0u8; MAX_INLINED_BYTES];
@@ -273,6 +808,41 @@ pub trait Encodable: Send + Sync {
   /// Returns the encoded length of the value.
   /// This is used to pre-allocate a buffer for encoding.
   fn encoded_len(&self) -> usize;
+
+  /// Describes the encoded form of the value as a list of byte segments, which can be written
+  /// out with vectored writes, without copying large payloads into an intermediate buffer.
+  ///
+  /// Returns `false` if the value can not describe itself this way, in which case the contents of
+  /// `slices` are unspecified and the value is encoded with [`encode`](Encodable::encode) instead.
+  /// The default implementation returns `false`.
+  fn as_io_slices<'a>(&'a self, slices: &mut GatherList<'a>) -> bool {
+    let _ = slices;
+    false
+  }
+
+  /// Feeds the encoded bytes of the value into the given [`Hasher`](core::hash::Hasher),
+  /// e.g. to compute a content hash of the message.
+  ///
+  /// The bytes are exactly the ones [`encode`](Encodable::encode) writes. Small values are encoded
+  /// into an inline buffer, and large values are fed segment by segment, see
+  /// [`as_io_slices`](Encodable::as_io_slices). Large values which can not describe themselves
+  /// this way are streamed through [`encode_to_writer`](Encodable::encode_to_writer).
+  ///
+  /// Returns the number of bytes fed into the hasher. On error, the hasher may already have been
+  /// fed a part of the encoded bytes.
+  fn encode_to_hasher<H: core::hash::Hasher>(&self, hasher: &mut H) -> Result<usize, Self::Error> {
+    utils::encode_to_sink(self, |bytes| hasher.write(bytes))
+  }
+
+  /// Feeds the encoded bytes of the value into the given [`digest::Update`],
+  /// e.g. a SHA-256 or BLAKE3 hasher.
+  ///
+  /// See [`encode_to_hasher`](Encodable::encode_to_hasher).
+  #[cfg(feature = "digest")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "digest")))]
+  fn encode_to_digest<D: digest::Update>(&self, digest: &mut D) -> Result<usize, Self::Error> {
+    utils::encode_to_sink(self, |bytes| digest.update(bytes))
+  }
 }
 
 #[cfg(feature = "std")]
@@ -287,6 +857,10 @@ impl<T: Transformable> Encodable for T {
     Transformable::encoded_len(self)
   }
 
+  fn as_io_slices<'a>(&'a self, slices: &mut GatherList<'a>) -> bool {
+    Transformable::as_io_slices(self, slices)
+  }
+
   fn encode_to_vec(&self) -> Result<Vec<u8>, Self::Error> {
     Transformable::encode_to_vec(self)
   }
@@ -346,6 +920,40 @@ pub trait Decodable: Send + 'static {
   where
     Self: Sized;
 
+  /// Decodes the value from the given buffered reader received over the wire.
+  ///
+  /// If the whole message is already buffered, the value is decoded directly from
+  /// [`fill_buf`](std::io::BufRead::fill_buf) without copying it into an intermediate buffer.
+  /// Otherwise, this falls back to [`decode_from_reader`](Decodable::decode_from_reader), if decoding
+  /// from the buffer fails with an error which [`is_incomplete`](Decodable::is_incomplete). Any other
+  /// error is returned as an [`InvalidData`](std::io::ErrorKind::InvalidData) error.
+  ///
+  /// Returns the number of bytes read from the reader and the struct.
+  fn decode_from_buf_reader<R: std::io::BufRead>(reader: &mut R) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    decode_from_buf_reader(
+      reader,
+      Self::decode,
+      Self::is_incomplete,
+      Self::decode_from_reader,
+    )
+  }
+
+  /// Returns `true` if the given error of [`decode`](Decodable::decode) only means that the buffer
+  /// ends before the value, so the value may still be decoded once more bytes are available.
+  ///
+  /// The default implementation checks whether the first [`TransformError`] or I/O error in the
+  /// source chain of the error is of kind [`UnexpectedEof`](ErrorKind::UnexpectedEof). The
+  /// built-in types recognize their own errors.
+  fn is_incomplete(err: &Self::Error) -> bool
+  where
+    Self: Sized,
+  {
+    utils::is_unexpected_eof(err)
+  }
+
   /// Decodes the value from the given async reader received over the wire.
   ///
   /// Returns the number of bytes read from the reader and the struct.
@@ -356,6 +964,104 @@ pub trait Decodable: Send + 'static {
   ) -> impl std::future::Future<Output = std::io::Result<(usize, Self)>> + Send
   where
     Self: Sized;
+
+  /// Decodes the value from the given buffer received over the wire, within the given budget.
+  ///
+  /// The default implementation forwards to [`decode`](Decodable::decode).
+  fn decode_limited(src: &[u8], budget: &mut DecodeBudget) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized,
+  {
+    let _ = budget;
+    Self::decode(src)
+  }
+
+  /// Decodes the value from the given reader received over the wire, within the given budget.
+  ///
+  /// Exceeding a limit fails with an [`InvalidData`](std::io::ErrorKind::InvalidData) error,
+  /// which wraps a [`LimitError`].
+  /// The default implementation forwards to [`decode_from_reader`](Decodable::decode_from_reader),
+  /// but never reads more than the max message size from the reader, which is the only limit it
+  /// enforces. Types which allocate, or which decode nested values, override it to check the
+  /// other limits too, like the built-in byte, string and wrapper types do.
+  fn decode_from_reader_limited<R: std::io::Read>(
+    reader: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    let limit = budget.limits().max_message_size();
+    Self::decode_from_reader(&mut LimitedReader::new(reader, limit))
+  }
+
+  /// Decodes the value from the given async reader received over the wire, within the given budget.
+  ///
+  /// See [`decode_from_reader_limited`](Decodable::decode_from_reader_limited).
+  #[cfg(feature = "async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+  fn decode_from_async_reader_limited<R: futures_util::io::AsyncRead + Send + Unpin>(
+    reader: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> impl std::future::Future<Output = std::io::Result<(usize, Self)>> + Send
+  where
+    Self: Sized,
+  {
+    let limit = budget.limits().max_message_size();
+    async move { Self::decode_from_async_reader(&mut LimitedReader::new(reader, limit)).await }
+  }
+
+  /// Decodes the value from the given buffer, which must contain exactly one encoded value.
+  ///
+  /// Fails with [`DecodeExactError::TrailingBytes`] if the value does not consume the whole buffer.
+  fn decode_exact(src: &[u8]) -> Result<Self, DecodeExactError<Self::Error>>
+  where
+    Self: Sized,
+  {
+    let (readed, val) = Self::decode(src).map_err(DecodeExactError::Decode)?;
+    TrailingBytes::check(readed, src.len())?;
+    Ok(val)
+  }
+
+  /// Decodes the value from the given reader, which must consume all the bytes it reads.
+  ///
+  /// The value is decoded with [`decode_from_reader`](Decodable::decode_from_reader), in its own
+  /// format, and the bytes it reports as consumed are checked against the bytes read from the
+  /// reader. Values which start with their total length, like the default
+  /// [`decode_from_reader`](Decodable::decode_from_reader) expects, read their whole frame, so if they
+  /// do not consume all of it, this fails with an [`InvalidData`](std::io::ErrorKind::InvalidData)
+  /// error, which wraps a [`TrailingBytes`]. Self-delimiting values, e.g. integers, bytes and
+  /// strings, never read more than they consume.
+  ///
+  /// Nothing is read past the value, so the following values stay in the reader.
+  fn decode_from_reader_exact<R: std::io::Read>(reader: &mut R) -> std::io::Result<(usize, Self)>
+  where
+    Self: Sized,
+  {
+    let mut reader = utils::CountingReader::new(reader);
+    let (readed, val) = Self::decode_from_reader(&mut reader)?;
+    TrailingBytes::check(readed, reader.count()).map_err(utils::invalid_data)?;
+    Ok((readed, val))
+  }
+
+  /// Decodes the value from the given async reader, which must consume all the bytes it reads.
+  ///
+  /// See [`decode_from_reader_exact`](Decodable::decode_from_reader_exact).
+  #[cfg(feature = "async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+  fn decode_from_async_reader_exact<R: futures_util::io::AsyncRead + Send + Unpin>(
+    reader: &mut R,
+  ) -> impl std::future::Future<Output = std::io::Result<(usize, Self)>> + Send
+  where
+    Self: Sized,
+  {
+    async move {
+      let mut reader = utils::CountingReader::new(reader);
+      let (readed, val) = Self::decode_from_async_reader(&mut reader).await?;
+      TrailingBytes::check(readed, reader.count()).map_err(utils::invalid_data)?;
+      Ok((readed, val))
+    }
+  }
 }
 
 /// The type can transform its representation from byte form to struct.
@@ -371,6 +1077,29 @@ pub trait Decodable {
   fn decode(src: &[u8]) -> Result<(usize, Self), Self::Error>
   where
     Self: Sized;
+
+  /// Decodes the value from the given buffer received over the wire, within the given budget.
+  ///
+  /// The default implementation forwards to [`decode`](Decodable::decode).
+  fn decode_limited(src: &[u8], budget: &mut DecodeBudget) -> Result<(usize, Self), Self::Error>
+  where
+    Self: Sized,
+  {
+    let _ = budget;
+    Self::decode(src)
+  }
+
+  /// Decodes the value from the given buffer, which must contain exactly one encoded value.
+  ///
+  /// Fails with [`DecodeExactError::TrailingBytes`] if the value does not consume the whole buffer.
+  fn decode_exact(src: &[u8]) -> Result<Self, DecodeExactError<Self::Error>>
+  where
+    Self: Sized,
+  {
+    let (readed, val) = Self::decode(src).map_err(DecodeExactError::Decode)?;
+    TrailingBytes::check(readed, src.len())?;
+    Ok(val)
+  }
 }
 
 #[cfg(feature = "std")]
@@ -385,12 +1114,54 @@ impl<T: Transformable> Decodable for T {
     Transformable::decode_from_reader(reader)
   }
 
+  fn decode_from_buf_reader<R: std::io::BufRead>(reader: &mut R) -> std::io::Result<(usize, Self)> {
+    Transformable::decode_from_buf_reader(reader)
+  }
+
+  fn is_incomplete(err: &Self::Error) -> bool {
+    <Self as Transformable>::is_incomplete(err)
+  }
+
   #[cfg(feature = "async")]
   fn decode_from_async_reader<R: futures_util::io::AsyncRead + Send + Unpin>(
     reader: &mut R,
   ) -> impl std::future::Future<Output = std::io::Result<(usize, Self)>> + Send {
     <Self as Transformable>::decode_from_async_reader::<R>(reader)
   }
+
+  fn decode_limited(src: &[u8], budget: &mut DecodeBudget) -> Result<(usize, Self), Self::Error> {
+    Transformable::decode_limited(src, budget)
+  }
+
+  fn decode_from_reader_limited<R: std::io::Read>(
+    reader: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> std::io::Result<(usize, Self)> {
+    Transformable::decode_from_reader_limited(reader, budget)
+  }
+
+  #[cfg(feature = "async")]
+  fn decode_from_async_reader_limited<R: futures_util::io::AsyncRead + Send + Unpin>(
+    reader: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> impl std::future::Future<Output = std::io::Result<(usize, Self)>> + Send {
+    <Self as Transformable>::decode_from_async_reader_limited::<R>(reader, budget)
+  }
+
+  fn decode_exact(src: &[u8]) -> Result<Self, DecodeExactError<Self::Error>> {
+    Transformable::decode_exact(src)
+  }
+
+  fn decode_from_reader_exact<R: std::io::Read>(reader: &mut R) -> std::io::Result<(usize, Self)> {
+    Transformable::decode_from_reader_exact(reader)
+  }
+
+  #[cfg(feature = "async")]
+  fn decode_from_async_reader_exact<R: futures_util::io::AsyncRead + Send + Unpin>(
+    reader: &mut R,
+  ) -> impl std::future::Future<Output = std::io::Result<(usize, Self)>> + Send {
+    <Self as Transformable>::decode_from_async_reader_exact::<R>(reader)
+  }
 }
 
 #[cfg(not(feature = "std"))]
@@ -400,6 +1171,42 @@ impl<T: Transformable> Decodable for T {
   fn decode(src: &[u8]) -> Result<(usize, Self), Self::Error> {
     Transformable::decode(src)
   }
+
+  fn decode_limited(src: &[u8], budget: &mut DecodeBudget) -> Result<(usize, Self), Self::Error> {
+    Transformable::decode_limited(src, budget)
+  }
+
+  fn decode_exact(src: &[u8]) -> Result<Self, DecodeExactError<Self::Error>> {
+    Transformable::decode_exact(src)
+  }
+}
+
+/// Decodes a value directly from the buffer of the reader if the whole message is buffered,
+/// otherwise falls back to the given reader based decoding.
+#[cfg(feature = "std")]
+fn decode_from_buf_reader<R, T, E>(
+  reader: &mut R,
+  decode: impl FnOnce(&[u8]) -> Result<(usize, T), E>,
+  is_incomplete: impl FnOnce(&E) -> bool,
+  decode_from_reader: impl FnOnce(&mut R) -> std::io::Result<(usize, T)>,
+) -> std::io::Result<(usize, T)>
+where
+  R: std::io::BufRead,
+  E: std::error::Error + Send + Sync + 'static,
+{
+  let buf = reader.fill_buf()?;
+  if !buf.is_empty() {
+    match decode(buf) {
+      Ok((readed, val)) => {
+        reader.consume(readed);
+        return Ok((readed, val));
+      }
+      Err(e) if !is_incomplete(&e) => return Err(utils::invalid_data(e)),
+      Err(_) => {}
+    }
+  }
+
+  decode_from_reader(reader)
 }
 
 #[cfg(test)]
@@ -414,13 +1221,98 @@ trait TestTransformable: Transformable + Eq + core::fmt::Debug + Sized {
     let (_, decoded) = Self::decode(&buf).unwrap();
     assert_eq!(decoded, val);
 
+    #[cfg(feature = "alloc")]
+    {
+      /// Records the bytes fed into it.
+      #[derive(Default)]
+      struct Recorder(std::vec::Vec<u8>);
+
+      impl core::hash::Hasher for Recorder {
+        fn finish(&self) -> u64 {
+          0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+          self.0.extend_from_slice(bytes);
+        }
+      }
+
+      let mut hasher = Recorder::default();
+      assert_eq!(val.encode_to_hasher(&mut hasher).unwrap(), buf.len());
+      assert_eq!(hasher.0, buf);
+
+      #[cfg(feature = "digest")]
+      {
+        use sha2::Digest;
+
+        let mut digest = sha2::Sha256::new();
+        val.encode_to_digest(&mut digest).unwrap();
+        assert_eq!(digest.finalize(), sha2::Sha256::digest(&buf));
+      }
+    }
+
     #[cfg(feature = "std")]
     {
       let mut buf = std::vec::Vec::new();
       val.encode_to_writer(&mut buf).unwrap();
       let (_, decoded) = Self::decode_from_reader(&mut buf.as_slice()).unwrap();
       assert_eq!(decoded, val);
+
+      let mut slices = GatherList::new();
+      if val.as_io_slices(&mut slices) {
+        let mut gathered = std::vec::Vec::new();
+        slices.write_to(&mut gathered).unwrap();
+        assert_eq!(gathered, buf);
+      }
+
+      // fully buffered, decoded in place
+      let mut reader = std::io::BufReader::new(buf.as_slice());
+      let (_, decoded) = Self::decode_from_buf_reader(&mut reader).unwrap();
+      assert_eq!(decoded, val);
+
+      // spans refills, falls back to the copying path
+      let mut reader = std::io::BufReader::with_capacity(1, buf.as_slice());
+      let (_, decoded) = Self::decode_from_buf_reader(&mut reader).unwrap();
+      assert_eq!(decoded, val);
+    }
+
+    #[cfg(feature = "owned-io")]
+    tokio::runtime::Builder::new_current_thread()
+      .build()
+      .unwrap()
+      .block_on(async {
+        let mut io = owned::tests::OwnedCursor::default();
+        let (res, buf) = val.encode_to_owned_writer(&mut io, Vec::new()).await;
+        res.unwrap();
+        let (res, _) = Self::decode_from_owned_reader(&mut io, buf).await;
+        assert_eq!(res.unwrap().1, val);
+      });
+
+    #[cfg(feature = "embedded-io")]
+    {
+      let mut buf = std::vec![0; val.encoded_len()];
+      let len = val
+        .encode_to_embedded_writer(&mut buf.as_mut_slice())
+        .unwrap();
+      let (_, decoded) = Self::decode_from_embedded_reader(&mut &buf[..len]).unwrap();
+      assert_eq!(decoded, val);
     }
+
+    #[cfg(feature = "embedded-io-async")]
+    tokio::runtime::Builder::new_current_thread()
+      .build()
+      .unwrap()
+      .block_on(async {
+        let mut buf = std::vec![0; val.encoded_len()];
+        let len = val
+          .encode_to_embedded_async_writer(&mut buf.as_mut_slice())
+          .await
+          .unwrap();
+        let (_, decoded) = Self::decode_from_embedded_async_reader(&mut &buf[..len])
+          .await
+          .unwrap();
+        assert_eq!(decoded, val);
+      });
   }
 }
 
@@ -430,5 +1322,88 @@ impl<T: Transformable + Eq + core::fmt::Debug + Sized> TestTransformable for T {
 mod impls;
 pub use impls::*;
 
+mod context;
+pub use context::{ContextFree, TransformableWith};
+
+#[cfg(feature = "std")]
+mod dyn_encodable;
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub use dyn_encodable::{DynEncodable, DynEncodeError};
+
+mod error;
+pub use error::{DecodeContext, ErrorKind, PathSegment, TransformError};
+
+mod exact;
+pub use exact::{DecodeExactError, TrailingBytes};
+
+pub mod canonical;
+pub use canonical::{DecodeCanonicalError, NonCanonical};
+
+mod limits;
+#[cfg(feature = "std")]
+use limits::LimitedReader;
+pub use limits::{DecodeBudget, DecodeLimits, LimitError};
+
+#[cfg(feature = "std")]
+mod vectored;
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub use vectored::GatherList;
+
+#[cfg(feature = "async")]
+mod framed;
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+pub use framed::{FlushPolicy, TransformableSink, TransformableStream};
+
+#[cfg(feature = "owned-io")]
+mod owned;
+#[cfg(all(feature = "monoio", target_os = "linux"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "monoio", target_os = "linux"))))]
+pub use owned::MonoioCompat;
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "tokio-uring", target_os = "linux"))))]
+pub use owned::TokioUringCompat;
+#[cfg(feature = "owned-io")]
+#[cfg_attr(docsrs, doc(cfg(feature = "owned-io")))]
+pub use owned::{OwnedRead, OwnedWrite};
+
+#[cfg(feature = "embedded-io")]
+mod embedded;
+#[cfg(feature = "embedded-io")]
+#[cfg_attr(docsrs, doc(cfg(feature = "embedded-io")))]
+pub use embedded::EmbeddedIoError;
+
+pub mod checksum;
+
+#[cfg(any(feature = "lz4", feature = "snappy", feature = "zstd"))]
+#[cfg_attr(
+  docsrs,
+  doc(cfg(any(feature = "lz4", feature = "snappy", feature = "zstd")))
+)]
+pub mod compression;
+
+pub mod endian;
+
+#[cfg(any(feature = "aead", feature = "hmac"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "aead", feature = "hmac"))))]
+pub mod keyring;
+
+pub mod prefix;
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub mod registry;
+
+pub mod tlv;
+
 /// Utilities for encoding and decoding.
 pub mod utils;
+
+/// Re-exports used by the code the macros of this crate expand to, not a public API.
+#[doc(hidden)]
+pub mod __private {
+  #[cfg(feature = "async")]
+  pub use futures_util;
+}
@@ -0,0 +1,715 @@
+/// Limits applied when decoding untrusted input.
+///
+/// Length prefixes read from the wire are checked against the limits before anything is allocated,
+/// so a peer can not make us allocate more memory than the limits allow.
+///
+/// The limits are applied by the `*_limited` decoding methods of
+/// [`Transformable`](crate::Transformable), from slices, readers, async, completion-based and
+/// `embedded-io` readers alike. Only the max message size is enforced for types which do not
+/// override them, e.g. by the default `decode_from_reader_limited`. The built-in byte and string
+/// types check their length before allocating, the wrappers and tagged structs pass the budget
+/// down to the values they hold, and tagged structs check the number of their fields.
+///
+/// The default limits are unlimited, except for the max depth of nested values,
+/// which is [`DEFAULT_MAX_DEPTH`](DecodeLimits::DEFAULT_MAX_DEPTH) to protect the stack,
+/// and the max decompressed length of a compressed value, which is
+/// [`DEFAULT_MAX_DECOMPRESSED_LEN`](DecodeLimits::DEFAULT_MAX_DECOMPRESSED_LEN) to protect
+/// against zip bombs.
+/// Use the `with_*` methods to set them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DecodeLimits {
+  max_message_size: usize,
+  max_bytes_len: usize,
+  max_elements: usize,
+  max_allocation: usize,
+  max_depth: usize,
+  max_decompressed_len: usize,
+}
+
+impl Default for DecodeLimits {
+  #[inline]
+  fn default() -> Self {
+    Self::unlimited()
+      .with_max_depth(Self::DEFAULT_MAX_DEPTH)
+      .with_max_decompressed_len(Self::DEFAULT_MAX_DECOMPRESSED_LEN)
+  }
+}
+
+impl DecodeLimits {
+  /// The default max depth of nested values.
+  pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+  /// The default max decompressed length of a compressed value, 64 MiB.
+  pub const DEFAULT_MAX_DECOMPRESSED_LEN: usize = 64 * 1024 * 1024;
+
+  /// Returns limits which do not restrict decoding.
+  #[inline]
+  pub const fn unlimited() -> Self {
+    Self {
+      max_message_size: usize::MAX,
+      max_bytes_len: usize::MAX,
+      max_elements: usize::MAX,
+      max_allocation: usize::MAX,
+      max_depth: usize::MAX,
+      max_decompressed_len: usize::MAX,
+    }
+  }
+
+  /// Sets the max number of bytes a single message can take on the wire.
+  #[inline]
+  pub const fn with_max_message_size(mut self, size: usize) -> Self {
+    self.max_message_size = size;
+    self
+  }
+
+  /// Sets the max length of a single byte or string value.
+  #[inline]
+  pub const fn with_max_bytes_len(mut self, len: usize) -> Self {
+    self.max_bytes_len = len;
+    self
+  }
+
+  /// Sets the max number of elements of a single collection, e.g. the fields of a tagged struct.
+  #[inline]
+  pub const fn with_max_elements(mut self, count: usize) -> Self {
+    self.max_elements = count;
+    self
+  }
+
+  /// Sets the max number of bytes which can be allocated while decoding, in total.
+  #[inline]
+  pub const fn with_max_allocation(mut self, size: usize) -> Self {
+    self.max_allocation = size;
+    self
+  }
+
+  /// Sets the max depth of nested values, e.g. the max height of a tree of recursive types.
+  #[inline]
+  pub const fn with_max_depth(mut self, depth: usize) -> Self {
+    self.max_depth = depth;
+    self
+  }
+
+  /// Sets the max length of a compressed value once it is decompressed.
+  #[inline]
+  pub const fn with_max_decompressed_len(mut self, len: usize) -> Self {
+    self.max_decompressed_len = len;
+    self
+  }
+
+  /// Returns the max number of bytes a single message can take on the wire.
+  #[inline]
+  pub const fn max_message_size(&self) -> usize {
+    self.max_message_size
+  }
+
+  /// Returns the max length of a single byte or string value.
+  #[inline]
+  pub const fn max_bytes_len(&self) -> usize {
+    self.max_bytes_len
+  }
+
+  /// Returns the max number of elements of a single collection.
+  #[inline]
+  pub const fn max_elements(&self) -> usize {
+    self.max_elements
+  }
+
+  /// Returns the max number of bytes which can be allocated while decoding, in total.
+  #[inline]
+  pub const fn max_allocation(&self) -> usize {
+    self.max_allocation
+  }
+
+  /// Returns the max depth of nested values.
+  #[inline]
+  pub const fn max_depth(&self) -> usize {
+    self.max_depth
+  }
+
+  /// Returns the max length of a compressed value once it is decompressed.
+  #[inline]
+  pub const fn max_decompressed_len(&self) -> usize {
+    self.max_decompressed_len
+  }
+}
+
+/// The error type for errors that get returned when a [`DecodeLimits`] is exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LimitError {
+  /// Returned when a message is larger than the max message size.
+  MessageTooLarge {
+    /// The max message size.
+    limit: usize,
+  },
+  /// Returned when a byte or string value is longer than the max length.
+  BytesTooLong {
+    /// The length read from the wire.
+    len: usize,
+    /// The max length.
+    limit: usize,
+  },
+  /// Returned when a collection has more elements than the max element count.
+  TooManyElements {
+    /// The element count read from the wire.
+    count: usize,
+    /// The max element count.
+    limit: usize,
+  },
+  /// Returned when decoding would allocate more than the remaining allocation budget.
+  AllocationBudgetExceeded {
+    /// The number of bytes to allocate.
+    requested: usize,
+    /// The remaining allocation budget.
+    remaining: usize,
+  },
+  /// Returned when values are nested deeper than the max depth.
+  DepthLimitExceeded {
+    /// The max depth.
+    limit: usize,
+  },
+  /// Returned when a compressed value would be longer than the max length once decompressed.
+  DecompressedTooLong {
+    /// The decompressed length read from the wire.
+    len: usize,
+    /// The max decompressed length.
+    limit: usize,
+  },
+}
+
+impl core::fmt::Display for LimitError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::MessageTooLarge { limit } => {
+        write!(f, "message is larger than the limit of {limit} bytes")
+      }
+      Self::BytesTooLong { len, limit } => {
+        write!(f, "length {len} exceeds the limit of {limit} bytes")
+      }
+      Self::TooManyElements { count, limit } => {
+        write!(f, "element count {count} exceeds the limit of {limit}")
+      }
+      Self::AllocationBudgetExceeded {
+        requested,
+        remaining,
+      } => write!(
+        f,
+        "allocating {requested} bytes exceeds the remaining allocation budget of {remaining} bytes"
+      ),
+      Self::DepthLimitExceeded { limit } => {
+        write!(f, "values are nested deeper than the limit of {limit}")
+      }
+      Self::DecompressedTooLong { len, limit } => {
+        write!(
+          f,
+          "decompressed length {len} exceeds the limit of {limit} bytes"
+        )
+      }
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LimitError {}
+
+/// Tracks the [`DecodeLimits`] while decoding, including how much of the allocation budget is left.
+///
+/// A budget is shared by everything decoded with it, so one budget can be used to bound the
+/// memory spent on a whole message, or on a whole connection.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DecodeBudget {
+  limits: DecodeLimits,
+  remaining: usize,
+  depth: usize,
+}
+
+impl Default for DecodeBudget {
+  #[inline]
+  fn default() -> Self {
+    Self::new(DecodeLimits::default())
+  }
+}
+
+impl From<DecodeLimits> for DecodeBudget {
+  #[inline]
+  fn from(limits: DecodeLimits) -> Self {
+    Self::new(limits)
+  }
+}
+
+impl DecodeBudget {
+  /// Creates a budget with the given limits.
+  #[inline]
+  pub const fn new(limits: DecodeLimits) -> Self {
+    Self {
+      remaining: limits.max_allocation,
+      limits,
+      depth: 0,
+    }
+  }
+
+  /// Creates a budget which does not restrict decoding.
+  #[inline]
+  pub const fn unlimited() -> Self {
+    Self::new(DecodeLimits::unlimited())
+  }
+
+  /// Returns the limits of the budget.
+  #[inline]
+  pub const fn limits(&self) -> &DecodeLimits {
+    &self.limits
+  }
+
+  /// Returns the number of bytes which can still be allocated.
+  #[inline]
+  pub const fn remaining(&self) -> usize {
+    self.remaining
+  }
+
+  /// Returns the current depth of nested values.
+  #[inline]
+  pub const fn depth(&self) -> usize {
+    self.depth
+  }
+
+  /// Enters a nested value, failing if it would be nested deeper than the max depth.
+  ///
+  /// Every successful call must be paired with a call to [`exit`](DecodeBudget::exit),
+  /// once the nested value is decoded.
+  #[inline]
+  pub fn enter(&mut self) -> Result<(), LimitError> {
+    if self.depth >= self.limits.max_depth {
+      return Err(LimitError::DepthLimitExceeded {
+        limit: self.limits.max_depth,
+      });
+    }
+    self.depth += 1;
+    Ok(())
+  }
+
+  /// Leaves a nested value entered with [`enter`](DecodeBudget::enter).
+  #[inline]
+  pub fn exit(&mut self) {
+    self.depth = self.depth.saturating_sub(1);
+  }
+
+  /// Checks the size of a message against the max message size.
+  #[inline]
+  pub const fn check_message_size(&self, size: usize) -> Result<(), LimitError> {
+    if size > self.limits.max_message_size {
+      return Err(LimitError::MessageTooLarge {
+        limit: self.limits.max_message_size,
+      });
+    }
+    Ok(())
+  }
+
+  /// Checks the length of a byte or string value against the max length.
+  #[inline]
+  pub const fn check_bytes_len(&self, len: usize) -> Result<(), LimitError> {
+    if len > self.limits.max_bytes_len {
+      return Err(LimitError::BytesTooLong {
+        len,
+        limit: self.limits.max_bytes_len,
+      });
+    }
+    Ok(())
+  }
+
+  /// Checks the element count of a collection against the max element count.
+  ///
+  /// Collections whose count is not known upfront check it as their elements are decoded.
+  #[inline]
+  pub const fn check_elements(&self, count: usize) -> Result<(), LimitError> {
+    if count > self.limits.max_elements {
+      return Err(LimitError::TooManyElements {
+        count,
+        limit: self.limits.max_elements,
+      });
+    }
+    Ok(())
+  }
+
+  /// Checks the decompressed length of a compressed value against the max decompressed length.
+  #[inline]
+  pub const fn check_decompressed_len(&self, len: usize) -> Result<(), LimitError> {
+    if len > self.limits.max_decompressed_len {
+      return Err(LimitError::DecompressedTooLong {
+        len,
+        limit: self.limits.max_decompressed_len,
+      });
+    }
+    Ok(())
+  }
+
+  /// Takes `size` bytes from the allocation budget.
+  ///
+  /// Should be called before allocating, so nothing is allocated if the budget is exceeded.
+  #[inline]
+  pub fn allocate(&mut self, size: usize) -> Result<(), LimitError> {
+    if size > self.remaining {
+      return Err(LimitError::AllocationBudgetExceeded {
+        requested: size,
+        remaining: self.remaining,
+      });
+    }
+    self.remaining -= size;
+    Ok(())
+  }
+
+  /// Checks a length-prefixed byte or string value of `len` bytes, whose prefix takes
+  /// `prefix_len` bytes, and takes its length from the allocation budget.
+  #[cfg(any(feature = "alloc", feature = "std"))]
+  pub(crate) fn charge_bytes(&mut self, prefix_len: usize, len: usize) -> Result<(), LimitError> {
+    self.check_message_size(prefix_len.saturating_add(len))?;
+    self.check_bytes_len(len)?;
+    self.allocate(len)
+  }
+}
+
+/// A reader which fails with [`LimitError::MessageTooLarge`] instead of reading past the max message size.
+#[cfg(feature = "std")]
+pub(crate) struct LimitedReader<'a, R> {
+  reader: &'a mut R,
+  remaining: usize,
+  limit: usize,
+}
+
+#[cfg(feature = "std")]
+impl<'a, R> LimitedReader<'a, R> {
+  pub(crate) fn new(reader: &'a mut R, limit: usize) -> Self {
+    Self {
+      reader,
+      remaining: limit,
+      limit,
+    }
+  }
+
+  fn limit_buf(&self, len: usize) -> std::io::Result<usize> {
+    if self.remaining == 0 && len > 0 {
+      return Err(crate::utils::invalid_data(LimitError::MessageTooLarge {
+        limit: self.limit,
+      }));
+    }
+    Ok(len.min(self.remaining))
+  }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> std::io::Read for LimitedReader<'_, R> {
+  fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    let max = self.limit_buf(buf.len())?;
+    let readed = self.reader.read(&mut buf[..max])?;
+    self.remaining -= readed;
+    Ok(readed)
+  }
+}
+
+#[cfg(feature = "async")]
+impl<R: futures_util::io::AsyncRead + Unpin> futures_util::io::AsyncRead for LimitedReader<'_, R> {
+  fn poll_read(
+    self: core::pin::Pin<&mut Self>,
+    cx: &mut core::task::Context<'_>,
+    buf: &mut [u8],
+  ) -> core::task::Poll<std::io::Result<usize>> {
+    let this = self.get_mut();
+    let max = match this.limit_buf(buf.len()) {
+      Ok(max) => max,
+      Err(e) => return core::task::Poll::Ready(Err(e)),
+    };
+    let res = core::pin::Pin::new(&mut *this.reader).poll_read(cx, &mut buf[..max]);
+    if let core::task::Poll::Ready(Ok(readed)) = res {
+      this.remaining -= readed;
+    }
+    res
+  }
+}
+
+/// An `embedded-io` reader which stops instead of reading past the max message size, and records
+/// that it did, so the error can be reported as [`LimitError::MessageTooLarge`].
+#[cfg(feature = "embedded-io")]
+pub(crate) struct LimitedEmbeddedReader<'a, R> {
+  reader: &'a mut R,
+  remaining: usize,
+  limit: usize,
+  exceeded: bool,
+}
+
+#[cfg(feature = "embedded-io")]
+impl<'a, R> LimitedEmbeddedReader<'a, R> {
+  pub(crate) fn new(reader: &'a mut R, limit: usize) -> Self {
+    Self {
+      reader,
+      remaining: limit,
+      limit,
+      exceeded: false,
+    }
+  }
+
+  /// Replaces the error of a decoder which read through this reader with
+  /// [`LimitError::MessageTooLarge`], if it tried to read past the max message size.
+  pub(crate) fn check<T, IO, E>(
+    &self,
+    res: Result<(usize, T), crate::EmbeddedIoError<IO, E>>,
+  ) -> Result<(usize, T), crate::EmbeddedIoError<IO, E>> {
+    match res {
+      Err(_) if self.exceeded => Err(crate::EmbeddedIoError::Limit(LimitError::MessageTooLarge {
+        limit: self.limit,
+      })),
+      res => res,
+    }
+  }
+
+  fn limit_buf(&mut self, len: usize) -> usize {
+    if self.remaining == 0 && len > 0 {
+      self.exceeded = true;
+    }
+    len.min(self.remaining)
+  }
+}
+
+#[cfg(feature = "embedded-io")]
+impl<R: embedded_io::ErrorType> embedded_io::ErrorType for LimitedEmbeddedReader<'_, R> {
+  type Error = R::Error;
+}
+
+#[cfg(feature = "embedded-io")]
+impl<R: embedded_io::Read> embedded_io::Read for LimitedEmbeddedReader<'_, R> {
+  fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+    let max = self.limit_buf(buf.len());
+    if max == 0 {
+      return Ok(0);
+    }
+    let readed = self.reader.read(&mut buf[..max])?;
+    self.remaining -= readed;
+    Ok(readed)
+  }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<R: embedded_io_async::Read> embedded_io_async::Read for LimitedEmbeddedReader<'_, R> {
+  async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+    let max = self.limit_buf(buf.len());
+    if max == 0 {
+      return Ok(0);
+    }
+    let readed = self.reader.read(&mut buf[..max]).await?;
+    self.remaining -= readed;
+    Ok(readed)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_budget() {
+    let mut budget = DecodeBudget::new(
+      DecodeLimits::unlimited()
+        .with_max_message_size(16)
+        .with_max_bytes_len(8)
+        .with_max_elements(2)
+        .with_max_allocation(10),
+    );
+
+    assert_eq!(budget.check_message_size(16), Ok(()));
+    assert_eq!(
+      budget.check_message_size(17),
+      Err(LimitError::MessageTooLarge { limit: 16 })
+    );
+    assert_eq!(
+      budget.check_bytes_len(9),
+      Err(LimitError::BytesTooLong { len: 9, limit: 8 })
+    );
+    assert_eq!(budget.check_elements(2), Ok(()));
+    assert_eq!(
+      budget.check_elements(3),
+      Err(LimitError::TooManyElements { count: 3, limit: 2 })
+    );
+
+    budget.allocate(6).unwrap();
+    assert_eq!(budget.remaining(), 4);
+    assert_eq!(
+      budget.allocate(5),
+      Err(LimitError::AllocationBudgetExceeded {
+        requested: 5,
+        remaining: 4
+      })
+    );
+    budget.allocate(4).unwrap();
+    assert_eq!(budget.remaining(), 0);
+
+    let mut budget = DecodeBudget::new(DecodeLimits::unlimited().with_max_depth(2));
+    budget.enter().unwrap();
+    budget.enter().unwrap();
+    assert_eq!(
+      budget.enter(),
+      Err(LimitError::DepthLimitExceeded { limit: 2 })
+    );
+    budget.exit();
+    assert_eq!(budget.depth(), 1);
+    budget.enter().unwrap();
+  }
+
+  #[cfg(feature = "std")]
+  fn limit_error(err: std::io::Error) -> LimitError {
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    *err.into_inner().unwrap().downcast::<LimitError>().unwrap()
+  }
+
+  #[cfg(feature = "std")]
+  fn hostile_bytes() -> std::vec::Vec<u8> {
+    let mut buf = u32::MAX.to_be_bytes().to_vec();
+    buf.extend_from_slice(b"hello");
+    buf
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_hostile_length_prefix() {
+    use crate::Transformable;
+
+    let budget = || DecodeBudget::new(DecodeLimits::unlimited().with_max_bytes_len(1024));
+    let err = <std::vec::Vec<u8> as Transformable>::decode_from_reader_limited(
+      &mut &hostile_bytes()[..],
+      &mut budget(),
+    )
+    .unwrap_err();
+    assert_eq!(
+      limit_error(err),
+      LimitError::BytesTooLong {
+        len: u32::MAX as usize,
+        limit: 1024
+      }
+    );
+
+    let err =
+      <std::string::String as Transformable>::decode_limited(&hostile_bytes(), &mut budget())
+        .unwrap_err();
+    assert!(matches!(
+      err,
+      crate::StringTransformError::Limit(LimitError::BytesTooLong { .. })
+    ));
+
+    // without limits, only what the reader provides is allocated
+    let err = <std::vec::Vec<u8> as Transformable>::decode_from_reader(&mut &hostile_bytes()[..])
+      .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_allocation_budget() {
+    use crate::Transformable;
+
+    let mut budget = DecodeBudget::new(DecodeLimits::unlimited().with_max_allocation(16));
+    let encoded = Transformable::encode_to_vec(&std::string::String::from("0123456789")).unwrap();
+
+    let (_, val) =
+      <std::string::String as Transformable>::decode_limited(&encoded, &mut budget).unwrap();
+    assert_eq!(val, "0123456789");
+    assert_eq!(budget.remaining(), 6);
+
+    let err = <std::string::String as Transformable>::decode_from_reader_limited(
+      &mut &encoded[..],
+      &mut budget,
+    )
+    .unwrap_err();
+    assert_eq!(
+      limit_error(err),
+      LimitError::AllocationBudgetExceeded {
+        requested: 10,
+        remaining: 6
+      }
+    );
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_max_message_size() {
+    use crate::{prefix::Varint, Prefixed, Transformable};
+
+    let mut budget = DecodeBudget::new(DecodeLimits::unlimited().with_max_message_size(4));
+
+    // types without their own limits never read past the max message size
+    let encoded = Transformable::encode_to_vec(&42u64).unwrap();
+    let err = <u64 as Transformable>::decode_from_reader_limited(&mut &encoded[..], &mut budget)
+      .unwrap_err();
+    assert_eq!(limit_error(err), LimitError::MessageTooLarge { limit: 4 });
+
+    let encoded = Transformable::encode_to_vec(&42u32).unwrap();
+    let (_, val) =
+      <u32 as Transformable>::decode_from_reader_limited(&mut &encoded[..], &mut budget).unwrap();
+    assert_eq!(val, 42);
+
+    let val = Prefixed::<std::vec::Vec<u8>, Varint>::new(b"abcd".to_vec());
+    let encoded = Transformable::encode_to_vec(&val).unwrap();
+    let err =
+      <Prefixed<std::vec::Vec<u8>, Varint> as Transformable>::decode_limited(&encoded, &mut budget)
+        .unwrap_err();
+    assert!(matches!(
+      err,
+      crate::PrefixedTransformError::Limit(LimitError::MessageTooLarge { limit: 4 })
+    ));
+  }
+
+  #[cfg(all(feature = "std", feature = "embedded-io"))]
+  #[test]
+  fn test_embedded() {
+    use crate::{EmbeddedIoError, Transformable};
+
+    let mut budget = DecodeBudget::new(DecodeLimits::unlimited().with_max_bytes_len(1024));
+    let err = <std::vec::Vec<u8> as Transformable>::decode_from_embedded_reader_limited(
+      &mut &hostile_bytes()[..],
+      &mut budget,
+    )
+    .unwrap_err();
+    assert!(matches!(
+      err,
+      EmbeddedIoError::Limit(LimitError::BytesTooLong { limit: 1024, .. })
+    ));
+
+    let mut budget = DecodeBudget::new(DecodeLimits::unlimited().with_max_message_size(4));
+    let encoded = Transformable::encode_to_vec(&42u64).unwrap();
+    let err =
+      <u64 as Transformable>::decode_from_embedded_reader_limited(&mut &encoded[..], &mut budget)
+        .unwrap_err();
+    assert!(matches!(
+      err,
+      EmbeddedIoError::Limit(LimitError::MessageTooLarge { limit: 4 })
+    ));
+
+    let encoded = Transformable::encode_to_vec(&42u32).unwrap();
+    let (_, val) =
+      <u32 as Transformable>::decode_from_embedded_reader_limited(&mut &encoded[..], &mut budget)
+        .unwrap();
+    assert_eq!(val, 42);
+  }
+
+  #[cfg(feature = "async")]
+  #[tokio::test]
+  async fn test_hostile_length_prefix_async() {
+    use crate::Transformable;
+    use futures_util::io::Cursor;
+
+    let mut budget = DecodeBudget::new(DecodeLimits::unlimited().with_max_bytes_len(1024));
+    let err = <std::vec::Vec<u8> as Transformable>::decode_from_async_reader_limited(
+      &mut Cursor::new(hostile_bytes()),
+      &mut budget,
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(limit_error(err), LimitError::BytesTooLong { .. }));
+
+    let mut budget = DecodeBudget::new(DecodeLimits::unlimited().with_max_message_size(4));
+    let encoded = Transformable::encode_to_vec(&42u64).unwrap();
+    let err = <u64 as Transformable>::decode_from_async_reader_limited(
+      &mut Cursor::new(encoded),
+      &mut budget,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(limit_error(err), LimitError::MessageTooLarge { limit: 4 });
+  }
+}
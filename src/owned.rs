@@ -0,0 +1,475 @@
+use super::*;
+
+use byteorder::{ByteOrder, NetworkEndian};
+use std::{future::Future, io};
+
+use crate::utils::invalid_data;
+
+/// A writer for completion-based runtimes (e.g. `io_uring`), which takes the ownership of
+/// the buffer while the write is in flight.
+pub trait OwnedWrite {
+  /// Writes the whole buffer into the writer.
+  ///
+  /// Returns the buffer back to the caller once the write completes, so it can be reused.
+  fn write_all_owned(&mut self, buf: Vec<u8>) -> impl Future<Output = (io::Result<()>, Vec<u8>)>;
+}
+
+/// A reader for completion-based runtimes (e.g. `io_uring`), which takes the ownership of
+/// the buffer while the read is in flight.
+pub trait OwnedRead {
+  /// Reads exactly `len` bytes from the reader and appends them to the end of the buffer.
+  ///
+  /// Returns the buffer back to the caller once the read completes, so it can be reused.
+  fn read_exact_owned(
+    &mut self,
+    buf: Vec<u8>,
+    len: usize,
+  ) -> impl Future<Output = (io::Result<()>, Vec<u8>)>;
+}
+
+/// Reserves `len` more bytes in the buffer, failing with an
+/// [`OutOfMemory`](io::ErrorKind::OutOfMemory) error instead of aborting if they can not be
+/// allocated.
+#[cfg(any(
+  all(feature = "monoio", target_os = "linux"),
+  all(feature = "tokio-uring", target_os = "linux")
+))]
+fn try_reserve(buf: &mut Vec<u8>, len: usize) -> io::Result<()> {
+  buf.try_reserve(len).map_err(|_| {
+    io::Error::new(
+      io::ErrorKind::OutOfMemory,
+      BytesTransformError::AllocationFailed { requested: len },
+    )
+  })
+}
+
+/// An [`OwnedRead`] which fails with [`LimitError::MessageTooLarge`] instead of reading past the
+/// max message size, before anything is allocated.
+pub(crate) struct LimitedOwnedReader<'a, R> {
+  reader: &'a mut R,
+  remaining: usize,
+  limit: usize,
+}
+
+impl<'a, R> LimitedOwnedReader<'a, R> {
+  pub(crate) fn new(reader: &'a mut R, limit: usize) -> Self {
+    Self {
+      reader,
+      remaining: limit,
+      limit,
+    }
+  }
+}
+
+impl<R: OwnedRead> OwnedRead for LimitedOwnedReader<'_, R> {
+  async fn read_exact_owned(&mut self, buf: Vec<u8>, len: usize) -> (io::Result<()>, Vec<u8>) {
+    if len > self.remaining {
+      let err = LimitError::MessageTooLarge { limit: self.limit };
+      return (Err(invalid_data(err)), buf);
+    }
+
+    self.remaining -= len;
+    self.reader.read_exact_owned(buf, len).await
+  }
+}
+
+/// Adapts a [`monoio`] reader or writer to [`OwnedRead`] and [`OwnedWrite`].
+#[cfg(all(feature = "monoio", target_os = "linux"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "monoio", target_os = "linux"))))]
+#[derive(Debug)]
+pub struct MonoioCompat<T>(T);
+
+#[cfg(all(feature = "monoio", target_os = "linux"))]
+impl<T> MonoioCompat<T> {
+  /// Wraps the given [`monoio`] reader or writer.
+  #[inline]
+  pub const fn new(io: T) -> Self {
+    Self(io)
+  }
+
+  /// Returns a reference to the inner reader or writer.
+  #[inline]
+  pub const fn get_ref(&self) -> &T {
+    &self.0
+  }
+
+  /// Returns a mutable reference to the inner reader or writer.
+  #[inline]
+  pub fn get_mut(&mut self) -> &mut T {
+    &mut self.0
+  }
+
+  /// Consumes the adapter, returning the inner reader or writer.
+  #[inline]
+  pub fn into_inner(self) -> T {
+    self.0
+  }
+}
+
+#[cfg(all(feature = "monoio", target_os = "linux"))]
+impl<T: monoio::io::AsyncWriteRent> OwnedWrite for MonoioCompat<T> {
+  async fn write_all_owned(&mut self, buf: Vec<u8>) -> (io::Result<()>, Vec<u8>) {
+    use monoio::io::AsyncWriteRentExt;
+
+    let (res, buf) = self.0.write_all(buf).await;
+    (res.map(|_| ()), buf)
+  }
+}
+
+#[cfg(all(feature = "monoio", target_os = "linux"))]
+impl<T: monoio::io::AsyncReadRent> OwnedRead for MonoioCompat<T> {
+  async fn read_exact_owned(&mut self, mut buf: Vec<u8>, len: usize) -> (io::Result<()>, Vec<u8>) {
+    use monoio::{buf::IoBufMut, io::AsyncReadRentExt};
+
+    let start = buf.len();
+    if let Err(e) = try_reserve(&mut buf, len) {
+      return (Err(e), buf);
+    }
+    let (res, buf) = self.0.read_exact(buf.slice_mut(start..start + len)).await;
+    (res.map(|_| ()), buf.into_inner())
+  }
+}
+
+/// Adapts a [`tokio_uring`] stream to [`OwnedRead`] and [`OwnedWrite`].
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "tokio-uring", target_os = "linux"))))]
+#[derive(Debug)]
+pub struct TokioUringCompat<T>(T);
+
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+impl<T> TokioUringCompat<T> {
+  /// Wraps the given [`tokio_uring`] stream.
+  #[inline]
+  pub const fn new(io: T) -> Self {
+    Self(io)
+  }
+
+  /// Returns a reference to the inner stream.
+  #[inline]
+  pub const fn get_ref(&self) -> &T {
+    &self.0
+  }
+
+  /// Returns a mutable reference to the inner stream.
+  #[inline]
+  pub fn get_mut(&mut self) -> &mut T {
+    &mut self.0
+  }
+
+  /// Consumes the adapter, returning the inner stream.
+  #[inline]
+  pub fn into_inner(self) -> T {
+    self.0
+  }
+}
+
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+macro_rules! impl_tokio_uring {
+  ($($ty:ty), + $(,)?) => {
+    $(
+      impl OwnedWrite for TokioUringCompat<$ty> {
+        async fn write_all_owned(&mut self, buf: Vec<u8>) -> (io::Result<()>, Vec<u8>) {
+          self.0.write_all(buf).await
+        }
+      }
+
+      impl OwnedRead for TokioUringCompat<$ty> {
+        async fn read_exact_owned(
+          &mut self,
+          mut buf: Vec<u8>,
+          len: usize,
+        ) -> (io::Result<()>, Vec<u8>) {
+          use tokio_uring::buf::BoundedBuf;
+
+          let end = buf.len() + len;
+          if let Err(e) = try_reserve(&mut buf, len) {
+            return (Err(e), buf);
+          }
+          while buf.len() < end {
+            let start = buf.len();
+            let (res, slice) = self.0.read(buf.slice(start..end)).await;
+            buf = slice.into_inner();
+            match res {
+              Ok(0) => return (Err(io::ErrorKind::UnexpectedEof.into()), buf),
+              Ok(_) => {}
+              Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+              Err(e) => return (Err(e), buf),
+            }
+          }
+          (Ok(()), buf)
+        }
+      }
+    )*
+  };
+}
+
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+impl_tokio_uring!(tokio_uring::net::TcpStream, tokio_uring::net::UnixStream);
+
+pub(crate) fn encode_owned<T: Transformable + ?Sized>(
+  val: &T,
+  mut buf: Vec<u8>,
+) -> (Result<usize, T::Error>, Vec<u8>) {
+  buf.clear();
+  buf.resize(val.encoded_len(), 0);
+  match val.encode(&mut buf) {
+    Ok(len) => {
+      buf.truncate(len);
+      (Ok(len), buf)
+    }
+    Err(e) => (Err(e), buf),
+  }
+}
+
+pub(crate) async fn encode_to<T: Transformable + ?Sized, W: OwnedWrite>(
+  val: &T,
+  writer: &mut W,
+  buf: Vec<u8>,
+) -> (io::Result<usize>, Vec<u8>) {
+  let (len, buf) = match val.encode_owned(buf) {
+    (Ok(len), buf) => (len, buf),
+    (Err(e), buf) => return (Err(invalid_data(e)), buf),
+  };
+  let (res, buf) = writer.write_all_owned(buf).await;
+  (res.map(|_| len), buf)
+}
+
+pub(crate) async fn decode_from<T: Transformable, R: OwnedRead>(
+  reader: &mut R,
+  mut buf: Vec<u8>,
+) -> (io::Result<(usize, T)>, Vec<u8>) {
+  buf.clear();
+  let (res, buf) = reader.read_exact_owned(buf, MESSAGE_SIZE_LEN).await;
+  if let Err(e) = res {
+    return (Err(e), buf);
+  }
+
+  let msg_len = (NetworkEndian::read_u32(&buf) as usize).max(MESSAGE_SIZE_LEN);
+  let (res, buf) = reader
+    .read_exact_owned(buf, msg_len - MESSAGE_SIZE_LEN)
+    .await;
+  if let Err(e) = res {
+    return (Err(e), buf);
+  }
+
+  let res = T::decode(&buf).map_err(invalid_data);
+  (res, buf)
+}
+
+/// Reads exactly `N` bytes from the given reader and decodes the value from them.
+///
+/// Used by types which are always encoded to `N` bytes.
+pub(crate) async fn decode_fixed_from<T: Transformable, R: OwnedRead, const N: usize>(
+  reader: &mut R,
+  mut buf: Vec<u8>,
+) -> (io::Result<(usize, T)>, Vec<u8>) {
+  buf.clear();
+  let (res, buf) = reader.read_exact_owned(buf, N).await;
+  if let Err(e) = res {
+    return (Err(e), buf);
+  }
+
+  let res = T::decode(&buf).map_err(invalid_data);
+  (res, buf)
+}
+
+/// Reads the length prefixed bytes from the given reader and decodes the value from them.
+///
+/// Used by types which are encoded by `encode_bytes`.
+pub(crate) async fn decode_bytes_from<T: Transformable, R: OwnedRead>(
+  reader: &mut R,
+  buf: Vec<u8>,
+) -> (io::Result<(usize, T)>, Vec<u8>) {
+  decode_bytes_from_limited(reader, buf, &mut DecodeBudget::unlimited()).await
+}
+
+/// Reads the length prefixed bytes from the given reader and decodes the value from them,
+/// checking their length against the budget before they are read.
+pub(crate) async fn decode_bytes_from_limited<T: Transformable, R: OwnedRead>(
+  reader: &mut R,
+  mut buf: Vec<u8>,
+  budget: &mut DecodeBudget,
+) -> (io::Result<(usize, T)>, Vec<u8>) {
+  buf.clear();
+  let (res, buf) = reader.read_exact_owned(buf, MESSAGE_SIZE_LEN).await;
+  if let Err(e) = res {
+    return (Err(e), buf);
+  }
+
+  let len = NetworkEndian::read_u32(&buf) as usize;
+  if let Err(e) = budget.charge_bytes(MESSAGE_SIZE_LEN, len) {
+    return (Err(invalid_data(e)), buf);
+  }
+  let (res, buf) = reader.read_exact_owned(buf, len).await;
+  if let Err(e) = res {
+    return (Err(e), buf);
+  }
+
+  let res = T::decode(&buf).map_err(invalid_data);
+  (res, buf)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use super::*;
+
+  /// An in-memory completion-based reader and writer.
+  #[derive(Default)]
+  pub(crate) struct OwnedCursor {
+    data: Vec<u8>,
+    pos: usize,
+  }
+
+  impl OwnedWrite for OwnedCursor {
+    async fn write_all_owned(&mut self, buf: Vec<u8>) -> (io::Result<()>, Vec<u8>) {
+      self.data.extend_from_slice(&buf);
+      (Ok(()), buf)
+    }
+  }
+
+  impl OwnedRead for OwnedCursor {
+    async fn read_exact_owned(
+      &mut self,
+      mut buf: Vec<u8>,
+      len: usize,
+    ) -> (io::Result<()>, Vec<u8>) {
+      if self.data.len() - self.pos < len {
+        return (Err(io::ErrorKind::UnexpectedEof.into()), buf);
+      }
+
+      buf.extend_from_slice(&self.data[self.pos..self.pos + len]);
+      self.pos += len;
+      (Ok(()), buf)
+    }
+  }
+
+  #[tokio::test]
+  async fn test_reuse_buffer() {
+    let mut io = OwnedCursor::default();
+    let mut buf = Vec::with_capacity(64);
+    for msg in ["hello", "world", "transformable"] {
+      let (res, b) = String::from(msg).encode_to_owned_writer(&mut io, buf).await;
+      res.unwrap();
+      buf = b;
+    }
+
+    let ptr = buf.as_ptr();
+    for msg in ["hello", "world", "transformable"] {
+      let (res, b) = String::decode_from_owned_reader(&mut io, buf).await;
+      assert_eq!(res.unwrap().1, msg);
+      buf = b;
+    }
+    assert_eq!(buf.as_ptr(), ptr);
+
+    let (res, _) = String::decode_from_owned_reader(&mut io, buf).await;
+    assert_eq!(res.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+  }
+
+  #[tokio::test]
+  async fn test_limited() {
+    let mut io = OwnedCursor::default();
+    let (res, buf) = String::from("hello world")
+      .encode_to_owned_writer(&mut io, Vec::new())
+      .await;
+    res.unwrap();
+
+    let mut budget = DecodeBudget::new(DecodeLimits::default().with_max_message_size(8));
+    let (res, _) = String::decode_from_owned_reader_limited(&mut io, buf, &mut budget).await;
+    let err = res.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(matches!(
+      err
+        .into_inner()
+        .unwrap()
+        .downcast::<LimitError>()
+        .as_deref(),
+      Ok(LimitError::MessageTooLarge { limit: 8 })
+    ));
+  }
+
+  #[tokio::test]
+  async fn test_hostile_length_prefix() {
+    let mut io = OwnedCursor::default();
+    let (res, _) = io.write_all_owned(u32::MAX.to_be_bytes().to_vec()).await;
+    res.unwrap();
+
+    let mut budget = DecodeBudget::new(DecodeLimits::default().with_max_bytes_len(1024));
+    let (res, buf) =
+      <Vec<u8>>::decode_from_owned_reader_limited(&mut io, Vec::new(), &mut budget).await;
+    let err = res.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(matches!(
+      err
+        .into_inner()
+        .unwrap()
+        .downcast::<LimitError>()
+        .as_deref(),
+      Ok(LimitError::BytesTooLong { limit: 1024, .. })
+    ));
+    assert!(buf.capacity() < 1024);
+  }
+
+  #[tokio::test]
+  async fn test_box_depth() {
+    let mut io = OwnedCursor::default();
+    let (res, buf) = Box::new(String::from("hello world"))
+      .encode_to_owned_writer(&mut io, Vec::new())
+      .await;
+    res.unwrap();
+
+    let mut budget = DecodeBudget::new(DecodeLimits::default().with_max_depth(0));
+    let (res, buf) =
+      <Box<String>>::decode_from_owned_reader_limited(&mut io, buf, &mut budget).await;
+    let err = res.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(matches!(
+      err
+        .into_inner()
+        .unwrap()
+        .downcast::<LimitError>()
+        .as_deref(),
+      Ok(LimitError::DepthLimitExceeded { limit: 0 })
+    ));
+
+    let (res, _) = <Box<String>>::decode_from_owned_reader(&mut io, buf).await;
+    assert_eq!(*res.unwrap().1, "hello world");
+  }
+
+  #[cfg(all(feature = "monoio", target_os = "linux"))]
+  #[test]
+  fn test_monoio() {
+    let mut rt = monoio::RuntimeBuilder::<monoio::IoUringDriver>::new()
+      .build()
+      .unwrap();
+    rt.block_on(async {
+      let (tx, rx) = monoio::net::UnixStream::pair().unwrap();
+      let (mut tx, mut rx) = (MonoioCompat::new(tx), MonoioCompat::new(rx));
+      let msg = String::from("hello world");
+      let (res, buf) = msg.encode_to_owned_writer(&mut tx, Vec::new()).await;
+      let len = res.unwrap();
+      assert_eq!(len, Transformable::encoded_len(&msg));
+
+      let (res, _) = String::decode_from_owned_reader(&mut rx, buf).await;
+      assert_eq!(res.unwrap(), (len, msg));
+    });
+  }
+
+  #[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+  #[test]
+  fn test_tokio_uring() {
+    tokio_uring::start(async {
+      let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+      let (mut tx, mut rx) = (
+        TokioUringCompat::new(tokio_uring::net::UnixStream::from_std(tx)),
+        TokioUringCompat::new(tokio_uring::net::UnixStream::from_std(rx)),
+      );
+      let msg = String::from("hello world");
+      let (res, buf) = msg.encode_to_owned_writer(&mut tx, Vec::new()).await;
+      let len = res.unwrap();
+      assert_eq!(len, Transformable::encoded_len(&msg));
+
+      let (res, _) = String::decode_from_owned_reader(&mut rx, buf).await;
+      assert_eq!(res.unwrap(), (len, msg));
+    });
+  }
+}
@@ -0,0 +1,247 @@
+//! Length prefixes for [`Prefixed`](crate::Prefixed) payloads.
+//!
+//! Byte and string types are encoded with a 4 bytes big-endian length prefix by default,
+//! [`Prefixed`](crate::Prefixed) allows to pick a different one, e.g. [`Varint`] for short payloads.
+
+use byteorder::{ByteOrder, NetworkEndian};
+
+use crate::utils::{decode_varint, encode_varint, encoded_len_varint, DecodeVarintError};
+use crate::{ErrorKind, TransformError};
+
+/// The max number of bytes of any length prefix provided by this crate.
+pub(crate) const MAX_PREFIX_LEN: usize = 10;
+
+/// The error type for errors that get returned when encoding or decoding a length prefix fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrefixError {
+  /// Returned when the buffer is too small to encode the prefix.
+  BufferTooSmall,
+  /// Returned when the buffer does not contain the whole prefix.
+  NotEnoughBytes,
+  /// Returned when the length can not be represented by the prefix.
+  TooLarge(u64),
+  /// Returned when the varint prefix is malformed.
+  Overflow,
+}
+
+impl From<PrefixError> for TransformError {
+  fn from(err: PrefixError) -> Self {
+    match err {
+      PrefixError::BufferTooSmall => Self::from_kind(ErrorKind::BufferTooSmall),
+      PrefixError::NotEnoughBytes => Self::from_kind(ErrorKind::UnexpectedEof),
+      err @ (PrefixError::TooLarge(_) | PrefixError::Overflow) => {
+        Self::wrap(ErrorKind::InvalidValue, err)
+      }
+    }
+  }
+}
+
+impl core::fmt::Display for PrefixError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::BufferTooSmall => write!(f, "buffer is too small to encode the length prefix"),
+      Self::NotEnoughBytes => write!(f, "not enough bytes to decode the length prefix"),
+      Self::TooLarge(len) => write!(f, "length {len} can not be represented by the prefix"),
+      Self::Overflow => write!(f, "malformed varint length prefix"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PrefixError {}
+
+/// A length prefix of a payload.
+pub trait LengthPrefix: Send + Sync + 'static {
+  /// The max length which can be represented by the prefix.
+  const MAX: u64;
+
+  /// Returns the encoded length of the prefix for the given payload length.
+  fn encoded_len(len: usize) -> usize;
+
+  /// Encodes the payload length into the given buffer.
+  ///
+  /// Returns the number of bytes written to the buffer.
+  fn encode(len: usize, dst: &mut [u8]) -> Result<usize, PrefixError>;
+
+  /// Decodes the payload length from the given buffer.
+  ///
+  /// Returns the number of bytes read from the buffer and the payload length.
+  fn decode(src: &[u8]) -> Result<(usize, u64), PrefixError>;
+
+  /// Returns how many more bytes have to be read to complete the prefix which starts with `src`.
+  ///
+  /// Used to read the prefix from a reader without reading past its end.
+  fn remaining(src: &[u8]) -> usize;
+}
+
+macro_rules! fixed_prefix {
+  ($($name:ident($ty:ident)), + $(,)?) => {
+    $(
+      #[doc = concat!("A `", stringify!($ty), "` big-endian length prefix.")]
+      #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+      pub struct $name;
+
+      impl LengthPrefix for $name {
+        const MAX: u64 = $ty::MAX as u64;
+
+        fn encoded_len(_: usize) -> usize {
+          core::mem::size_of::<$ty>()
+        }
+
+        fn encode(len: usize, dst: &mut [u8]) -> Result<usize, PrefixError> {
+          const SIZE: usize = core::mem::size_of::<$ty>();
+
+          if len as u64 > Self::MAX {
+            return Err(PrefixError::TooLarge(len as u64));
+          }
+
+          if dst.len() < SIZE {
+            return Err(PrefixError::BufferTooSmall);
+          }
+
+          paste::paste! {
+            NetworkEndian::[< write_ $ty >](&mut dst[..SIZE], len as $ty);
+          }
+          Ok(SIZE)
+        }
+
+        fn decode(src: &[u8]) -> Result<(usize, u64), PrefixError> {
+          const SIZE: usize = core::mem::size_of::<$ty>();
+
+          if src.len() < SIZE {
+            return Err(PrefixError::NotEnoughBytes);
+          }
+
+          paste::paste! {
+            Ok((SIZE, NetworkEndian::[< read_ $ty >](&src[..SIZE]) as u64))
+          }
+        }
+
+        fn remaining(src: &[u8]) -> usize {
+          core::mem::size_of::<$ty>().saturating_sub(src.len())
+        }
+      }
+    )*
+  };
+}
+
+fixed_prefix!(U16(u16), U32(u32), U64(u64));
+
+/// A `u8` length prefix.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct U8;
+
+impl LengthPrefix for U8 {
+  const MAX: u64 = u8::MAX as u64;
+
+  fn encoded_len(_: usize) -> usize {
+    1
+  }
+
+  fn encode(len: usize, dst: &mut [u8]) -> Result<usize, PrefixError> {
+    if len as u64 > Self::MAX {
+      return Err(PrefixError::TooLarge(len as u64));
+    }
+
+    if dst.is_empty() {
+      return Err(PrefixError::BufferTooSmall);
+    }
+
+    dst[0] = len as u8;
+    Ok(1)
+  }
+
+  fn decode(src: &[u8]) -> Result<(usize, u64), PrefixError> {
+    match src.first() {
+      Some(len) => Ok((1, *len as u64)),
+      None => Err(PrefixError::NotEnoughBytes),
+    }
+  }
+
+  fn remaining(src: &[u8]) -> usize {
+    1usize.saturating_sub(src.len())
+  }
+}
+
+/// A LEB128 varint length prefix, which takes a single byte for payloads shorter than 128 bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Varint;
+
+impl LengthPrefix for Varint {
+  const MAX: u64 = u64::MAX;
+
+  fn encoded_len(len: usize) -> usize {
+    encoded_len_varint(len as u64)
+  }
+
+  fn encode(len: usize, dst: &mut [u8]) -> Result<usize, PrefixError> {
+    encode_varint(len as u64, dst).map_err(|_| PrefixError::BufferTooSmall)
+  }
+
+  fn decode(src: &[u8]) -> Result<(usize, u64), PrefixError> {
+    decode_varint(src).map_err(|e| match e {
+      DecodeVarintError::Overflow => PrefixError::Overflow,
+      DecodeVarintError::NotEnoughBytes => PrefixError::NotEnoughBytes,
+    })
+  }
+
+  fn remaining(src: &[u8]) -> usize {
+    match src.last() {
+      _ if src.len() >= MAX_PREFIX_LEN => 0,
+      Some(b) if b & 0x80 == 0 => 0,
+      _ => 1,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn check<P: LengthPrefix>(len: usize) {
+    let mut buf = [0u8; MAX_PREFIX_LEN];
+    let written = P::encode(len, &mut buf).unwrap();
+    assert_eq!(written, P::encoded_len(len));
+
+    // the prefix is read byte by byte, as it is from a reader
+    let mut filled = 0;
+    loop {
+      let more = P::remaining(&buf[..filled]);
+      if more == 0 {
+        break;
+      }
+      filled += more;
+    }
+    assert_eq!(filled, written);
+    assert_eq!(P::decode(&buf[..filled]).unwrap(), (written, len as u64));
+  }
+
+  #[test]
+  fn test_prefixes() {
+    for len in [0, 1, 127, 128, 255] {
+      check::<U8>(len);
+      check::<U16>(len);
+      check::<U32>(len);
+      check::<U64>(len);
+      check::<Varint>(len);
+    }
+
+    for len in [256, 16384, 65535] {
+      check::<U16>(len);
+      check::<Varint>(len);
+    }
+
+    check::<U64>(u32::MAX as usize);
+    check::<Varint>(u32::MAX as usize);
+
+    assert_eq!(
+      U8::encode(256, &mut [0; 1]),
+      Err(PrefixError::TooLarge(256))
+    );
+    assert_eq!(
+      U16::encode(65536, &mut [0; 2]),
+      Err(PrefixError::TooLarge(65536))
+    );
+    assert_eq!(Varint::decode(&[0x80; 10]), Err(PrefixError::Overflow));
+  }
+}
@@ -0,0 +1,638 @@
+//! Decoding of streams which carry many message types.
+//!
+//! Every message is sent in a frame: the total length of the frame, as a 4 bytes big-endian
+//! integer, the id of the type of the message, as a 4 bytes big-endian integer, then the message.
+//! A [`Registry`] maps the type ids to the [`Transformable`] impls of the messages, and decodes
+//! them into a [`Box<dyn Any>`](AnyMessage) or into an enum of all the messages.
+//!
+//! Type ids are part of the wire format: they must be stable, and never reused for a different
+//! type once a type is removed.
+
+use std::{any::Any, boxed::Box, collections::HashMap, io, vec::Vec};
+
+use byteorder::{ByteOrder, NetworkEndian};
+
+use crate::{utils, DecodeBudget, DecodeLimits, LimitError, TrailingBytes, Transformable};
+
+/// The length of the header of a frame: its total length and the type id.
+pub const FRAME_HEADER_LEN: usize = 2 * core::mem::size_of::<u32>();
+
+/// A decoded message of any type, see [`Registry::register_any`].
+pub type AnyMessage = Box<dyn Any + Send + Sync>;
+
+/// The error type for errors that get returned when registering, encoding or decoding frames
+/// fails.
+#[derive(Debug)]
+pub enum RegistryError {
+  /// Returned when a type is registered with an id which is already taken.
+  DuplicateId {
+    /// The type id.
+    id: u32,
+    /// The name of the type which is registered with the id.
+    registered: &'static str,
+  },
+  /// Returned when the type id of a frame is not registered.
+  UnknownId(u32),
+  /// Returned when the buffer is too small to encode.
+  EncodeBufferTooSmall,
+  /// Returned when the buffer does not contain the whole frame.
+  NotEnoughBytes,
+  /// Returned when the frame is longer than `u32::MAX` bytes.
+  TooLarge(usize),
+  /// Returned when the frame exceeds the limits of the budget.
+  Limit(LimitError),
+  /// Returned when encoding or decoding the message fails.
+  Transform {
+    /// The type id.
+    id: u32,
+    /// The error of the message.
+    source: Box<dyn std::error::Error + Send + Sync>,
+  },
+  /// Returned when the message does not consume all of its frame.
+  TrailingBytes {
+    /// The type id.
+    id: u32,
+    /// The bytes consumed by the message and the length of the frame.
+    trailing: TrailingBytes,
+  },
+}
+
+impl From<LimitError> for RegistryError {
+  #[inline]
+  fn from(err: LimitError) -> Self {
+    Self::Limit(err)
+  }
+}
+
+impl core::fmt::Display for RegistryError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::DuplicateId { id, registered } => {
+        write!(f, "type id {id} is already registered for `{registered}`")
+      }
+      Self::UnknownId(id) => write!(f, "unknown type id {id}"),
+      Self::EncodeBufferTooSmall => write!(
+        f,
+        "buffer is too small, use `registry::encoded_frame_len` to pre-allocate a buffer with enough space"
+      ),
+      Self::NotEnoughBytes => write!(f, "not enough bytes to decode"),
+      Self::TooLarge(len) => write!(f, "frame of {len} bytes is too large"),
+      Self::Limit(e) => write!(f, "{e}"),
+      Self::Transform { id, source } => write!(f, "type id {id}: {source}"),
+      Self::TrailingBytes { id, trailing } => write!(f, "type id {id}: {trailing}"),
+    }
+  }
+}
+
+impl std::error::Error for RegistryError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Limit(e) => Some(e),
+      Self::Transform { source, .. } => Some(source.as_ref()),
+      Self::TrailingBytes { trailing, .. } => Some(trailing),
+      _ => None,
+    }
+  }
+}
+
+/// Returns the encoded length of the frame of the given message.
+#[inline]
+pub fn encoded_frame_len<T: Transformable + ?Sized>(msg: &T) -> usize {
+  FRAME_HEADER_LEN + msg.encoded_len()
+}
+
+/// Encodes the frame of the given message with the given type id into the buffer.
+///
+/// Returns the number of bytes written.
+pub fn encode_frame<T: Transformable + ?Sized>(
+  id: u32,
+  msg: &T,
+  dst: &mut [u8],
+) -> Result<usize, RegistryError> {
+  let len = encoded_frame_len(msg);
+  if len > u32::MAX as usize {
+    return Err(RegistryError::TooLarge(len));
+  }
+  if dst.len() < len {
+    return Err(RegistryError::EncodeBufferTooSmall);
+  }
+
+  let written =
+    msg
+      .encode(&mut dst[FRAME_HEADER_LEN..len])
+      .map_err(|e| RegistryError::Transform {
+        id,
+        source: Box::new(e),
+      })?;
+  let len = FRAME_HEADER_LEN + written;
+  NetworkEndian::write_u32(&mut dst[..4], len as u32);
+  NetworkEndian::write_u32(&mut dst[4..FRAME_HEADER_LEN], id);
+  Ok(len)
+}
+
+/// Encodes the frame of the given message with the given type id into a [`Vec`].
+pub fn encode_frame_to_vec<T: Transformable + ?Sized>(
+  id: u32,
+  msg: &T,
+) -> Result<Vec<u8>, RegistryError> {
+  let mut buf = std::vec![0u8; encoded_frame_len(msg)];
+  let len = encode_frame(id, msg, &mut buf)?;
+  buf.truncate(len);
+  Ok(buf)
+}
+
+type DecodeFn<M> = Box<
+  dyn Fn(&[u8], &mut DecodeBudget) -> Result<(usize, M), Box<dyn std::error::Error + Send + Sync>>
+    + Send
+    + Sync,
+>;
+
+struct Decoder<M> {
+  name: &'static str,
+  decode: DecodeFn<M>,
+}
+
+/// Maps stable type ids to the decoders of the messages, which yield `M`.
+///
+/// `M` is either [`AnyMessage`], whose messages are registered with
+/// [`register_any`](Registry::register_any), or an enum with a variant per message, whose messages
+/// are registered with [`register`](Registry::register) and the constructor of their variant.
+/// The [`registry!`](crate::registry!) and [`register!`](crate::register!) macros register many
+/// messages at once.
+pub struct Registry<M = AnyMessage> {
+  decoders: HashMap<u32, Decoder<M>>,
+}
+
+impl<M> Default for Registry<M> {
+  #[inline]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+// Only shows the registered types, the decoders are closures.
+impl<M> core::fmt::Debug for Registry<M> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_map()
+      .entries(self.decoders.iter().map(|(id, d)| (id, d.name)))
+      .finish()
+  }
+}
+
+impl<M> Registry<M> {
+  /// Creates an empty registry.
+  #[inline]
+  pub fn new() -> Self {
+    Self {
+      decoders: HashMap::new(),
+    }
+  }
+
+  /// Returns the number of registered types.
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.decoders.len()
+  }
+
+  /// Returns `true` if no type is registered.
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.decoders.is_empty()
+  }
+
+  /// Returns the name of the type registered with the given id.
+  #[inline]
+  pub fn type_name(&self, id: u32) -> Option<&'static str> {
+    self.decoders.get(&id).map(|d| d.name)
+  }
+
+  /// Decodes the frame at the start of the buffer, within the [default](DecodeBudget::default)
+  /// budget.
+  ///
+  /// Returns the number of bytes read and the message.
+  pub fn decode(&self, src: &[u8]) -> Result<(usize, M), RegistryError> {
+    self.decode_limited(src, &mut DecodeBudget::default())
+  }
+
+  /// Decodes the frame at the start of the buffer, within the given budget.
+  ///
+  /// Returns the number of bytes read and the message.
+  pub fn decode_limited(
+    &self,
+    src: &[u8],
+    budget: &mut DecodeBudget,
+  ) -> Result<(usize, M), RegistryError> {
+    if src.len() < FRAME_HEADER_LEN {
+      return Err(RegistryError::NotEnoughBytes);
+    }
+
+    let len = NetworkEndian::read_u32(src) as usize;
+    budget.check_message_size(len)?;
+    if len < FRAME_HEADER_LEN || src.len() < len {
+      return Err(RegistryError::NotEnoughBytes);
+    }
+
+    let id = NetworkEndian::read_u32(&src[4..FRAME_HEADER_LEN]);
+    let decoder = self.decoders.get(&id).ok_or(RegistryError::UnknownId(id))?;
+    let payload = &src[FRAME_HEADER_LEN..len];
+    let (readed, msg) = (decoder.decode)(payload, budget)
+      .map_err(|source| RegistryError::Transform { id, source })?;
+    TrailingBytes::check(readed, payload.len())
+      .map_err(|trailing| RegistryError::TrailingBytes { id, trailing })?;
+    Ok((len, msg))
+  }
+
+  /// Decodes the next frame from the given reader, within the [default](DecodeBudget::default)
+  /// budget.
+  ///
+  /// Returns the number of bytes read from the reader and the message. The whole frame is read
+  /// before its message is decoded, so the reader is at the start of the next frame even if the
+  /// type id is unknown or the message is invalid, which fail with an
+  /// [`InvalidData`](io::ErrorKind::InvalidData) error wrapping a [`RegistryError`].
+  pub fn decode_from_reader<R: io::Read>(&self, reader: &mut R) -> io::Result<(usize, M)> {
+    self.decode_from_reader_limited(reader, &mut DecodeBudget::default())
+  }
+
+  /// Decodes the next frame from the given reader, within the given budget.
+  ///
+  /// See [`decode_from_reader`](Registry::decode_from_reader).
+  pub fn decode_from_reader_limited<R: io::Read>(
+    &self,
+    reader: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> io::Result<(usize, M)> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len)?;
+    self.decode_frame_from_reader(len, reader, budget)
+  }
+
+  /// Returns an iterator over the messages of the frames of the given reader, which ends once
+  /// the reader is at its end between two frames. The messages are decoded within the
+  /// [default](DecodeLimits::default) limits.
+  #[inline]
+  pub fn frames<R: io::Read>(&self, reader: R) -> Frames<'_, R, M> {
+    self.frames_limited(reader, DecodeLimits::default())
+  }
+
+  /// Returns an iterator over the messages of the frames of the given reader, which are decoded
+  /// within the given limits. Every frame is decoded within a fresh budget, so the allocations of
+  /// a frame are not charged to the next ones.
+  ///
+  /// See [`frames`](Registry::frames).
+  #[inline]
+  pub fn frames_limited<R: io::Read>(&self, reader: R, limits: DecodeLimits) -> Frames<'_, R, M> {
+    Frames {
+      registry: self,
+      reader,
+      limits,
+    }
+  }
+
+  fn decode_frame_from_reader<R: io::Read>(
+    &self,
+    len: [u8; 4],
+    reader: &mut R,
+    budget: &mut DecodeBudget,
+  ) -> io::Result<(usize, M)> {
+    let frame_len = NetworkEndian::read_u32(&len) as usize;
+    budget
+      .check_message_size(frame_len)
+      .map_err(utils::invalid_data)?;
+    if frame_len < FRAME_HEADER_LEN {
+      return Err(utils::invalid_data(RegistryError::NotEnoughBytes));
+    }
+
+    let mut buf = len.to_vec();
+    utils::read_exact_to_vec(reader, &mut buf, frame_len - len.len())?;
+    self
+      .decode_limited(&buf, budget)
+      .map_err(utils::invalid_data)
+  }
+}
+
+impl<M: 'static> Registry<M> {
+  /// Registers the message type `T` with the given id, whose messages are converted into `M`
+  /// with `into`, e.g. the constructor of the variant of `T` in the enum of all the messages.
+  ///
+  /// Fails with [`DuplicateId`](RegistryError::DuplicateId) if the id is already taken.
+  pub fn register<T: Transformable>(
+    &mut self,
+    id: u32,
+    into: fn(T) -> M,
+  ) -> Result<&mut Self, RegistryError> {
+    use std::collections::hash_map::Entry;
+
+    match self.decoders.entry(id) {
+      Entry::Occupied(entry) => Err(RegistryError::DuplicateId {
+        id,
+        registered: entry.get().name,
+      }),
+      Entry::Vacant(entry) => {
+        entry.insert(Decoder {
+          name: core::any::type_name::<T>(),
+          decode: Box::new(move |src, budget| match T::decode_limited(src, budget) {
+            Ok((readed, msg)) => Ok((readed, into(msg))),
+            Err(e) => Err(Box::new(e)),
+          }),
+        });
+        Ok(self)
+      }
+    }
+  }
+}
+
+impl Registry<AnyMessage> {
+  /// Registers the message type `T` with the given id, whose messages are boxed as
+  /// [`AnyMessage`] and can be [downcast](Box::downcast) back to `T`.
+  ///
+  /// Fails with [`DuplicateId`](RegistryError::DuplicateId) if the id is already taken.
+  #[inline]
+  pub fn register_any<T: Transformable>(&mut self, id: u32) -> Result<&mut Self, RegistryError> {
+    self.register::<T>(id, |msg| Box::new(msg))
+  }
+}
+
+/// An iterator over the messages of the frames of a reader, see [`Registry::frames`].
+pub struct Frames<'a, R, M> {
+  registry: &'a Registry<M>,
+  reader: R,
+  limits: DecodeLimits,
+}
+
+impl<R, M> Frames<'_, R, M> {
+  /// Consumes the iterator, returning the reader.
+  #[inline]
+  pub fn into_inner(self) -> R {
+    self.reader
+  }
+}
+
+impl<R: io::Read, M> Iterator for Frames<'_, R, M> {
+  type Item = io::Result<M>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let mut len = [0u8; 4];
+    let mut readed = 0;
+    while readed < len.len() {
+      match self.reader.read(&mut len[readed..]) {
+        Ok(0) if readed == 0 => return None,
+        Ok(0) => return Some(Err(io::ErrorKind::UnexpectedEof.into())),
+        Ok(n) => readed += n,
+        Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+        Err(e) => return Some(Err(e)),
+      }
+    }
+
+    Some(
+      self
+        .registry
+        .decode_frame_from_reader(len, &mut self.reader, &mut DecodeBudget::new(self.limits))
+        .map(|(_, msg)| msg),
+    )
+  }
+}
+
+/// Registers many message types at once into a [`Registry`](crate::registry::Registry).
+///
+/// Every entry is a type id and a message type, followed by `=>` and the function which converts
+/// the message into the output of the registry, e.g. the constructor of its variant. Without it,
+/// the message is boxed as an [`AnyMessage`](crate::registry::AnyMessage).
+///
+/// Evaluates to a `Result<(), RegistryError>`, which fails on the first duplicate id.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// register!(registry, {
+///   1 => Ping => Message::Ping,
+///   2 => Pong => Message::Pong,
+/// })?;
+/// ```
+#[macro_export]
+macro_rules! register {
+  ($registry:expr, { $($id:expr => $ty:ty $(=> $into:expr)?),+ $(,)? }) => {
+    'register: {
+      let registry = &mut $registry;
+      $(
+        if let ::core::result::Result::Err(e) =
+          registry.register::<$ty>($id, $crate::__registry_into!($ty $(, $into)?))
+        {
+          break 'register ::core::result::Result::Err(e);
+        }
+      )+
+      ::core::result::Result::<(), $crate::registry::RegistryError>::Ok(())
+    }
+  };
+}
+
+/// Creates a [`Registry`](crate::registry::Registry) with many message types, see
+/// [`register!`](crate::register!).
+///
+/// Evaluates to a `Result<Registry<_>, RegistryError>`, which fails on the first duplicate id.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let registry = registry! {
+///   1 => Ping,
+///   2 => Pong,
+/// }?;
+/// ```
+#[macro_export]
+macro_rules! registry {
+  ($($id:expr => $ty:ty $(=> $into:expr)?),+ $(,)?) => {{
+    let mut registry = $crate::registry::Registry::new();
+    match $crate::register!(registry, { $($id => $ty $(=> $into)?),+ }) {
+      ::core::result::Result::Ok(()) => ::core::result::Result::Ok(registry),
+      ::core::result::Result::Err(e) => ::core::result::Result::Err(e),
+    }
+  }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __registry_into {
+  ($ty:ty) => {
+    |msg: $ty| -> $crate::registry::AnyMessage { ::std::boxed::Box::new(msg) }
+  };
+  ($ty:ty, $into:expr) => {
+    $into
+  };
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::string::String;
+
+  #[derive(Debug, PartialEq, Eq)]
+  enum Message {
+    Ping(u64),
+    Name(String),
+  }
+
+  #[test]
+  fn test_enum_registry() {
+    let registry = registry! {
+      1 => u64 => Message::Ping,
+      2 => String => Message::Name,
+    }
+    .unwrap();
+    assert_eq!(registry.len(), 2);
+
+    let mut stream = encode_frame_to_vec(1, &7u64).unwrap();
+    assert_eq!(stream.len(), FRAME_HEADER_LEN + 8);
+    stream.extend(encode_frame_to_vec(2, &String::from("node-7")).unwrap());
+    stream.extend(encode_frame_to_vec(3, &8u64).unwrap());
+    stream.extend(encode_frame_to_vec(1, &9u64).unwrap());
+
+    let (readed, msg) = registry.decode(&stream).unwrap();
+    assert_eq!((readed, msg), (16, Message::Ping(7)));
+
+    // the unknown frame is skipped, and the stream goes on
+    let mut frames = registry.frames(stream.as_slice());
+    assert_eq!(frames.next().unwrap().unwrap(), Message::Ping(7));
+    assert_eq!(
+      frames.next().unwrap().unwrap(),
+      Message::Name(String::from("node-7"))
+    );
+    let err = frames.next().unwrap().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(matches!(
+      err
+        .into_inner()
+        .unwrap()
+        .downcast::<RegistryError>()
+        .as_deref(),
+      Ok(RegistryError::UnknownId(3))
+    ));
+    assert_eq!(frames.next().unwrap().unwrap(), Message::Ping(9));
+    assert!(frames.next().is_none());
+
+    // a truncated frame
+    let mut frames = registry.frames(&stream[..stream.len() - 1]);
+    assert_eq!(
+      frames.nth(2).unwrap().unwrap_err().kind(),
+      io::ErrorKind::InvalidData
+    );
+    assert_eq!(
+      frames.next().unwrap().unwrap_err().kind(),
+      io::ErrorKind::UnexpectedEof
+    );
+  }
+
+  #[test]
+  fn test_any_registry() {
+    let mut registry = Registry::new();
+    register!(registry, {
+      1 => u64,
+      2 => String,
+    })
+    .unwrap();
+    let frame = encode_frame_to_vec(2, &String::from("node-7")).unwrap();
+    let (_, msg) = registry.decode_from_reader(&mut frame.as_slice()).unwrap();
+    assert_eq!(*msg.downcast::<String>().unwrap(), "node-7");
+
+    assert!(matches!(
+      registry.register_any::<u32>(1),
+      Err(RegistryError::DuplicateId {
+        id: 1,
+        registered: "u64"
+      })
+    ));
+    assert!(matches!(
+      registry! { 1 => u64, 1 => u32 },
+      Err(RegistryError::DuplicateId { id: 1, .. })
+    ));
+  }
+
+  #[test]
+  fn test_invalid_frame() {
+    let registry = registry! { 1 => u32 }.unwrap();
+    let frame = encode_frame_to_vec(1, &7u64).unwrap();
+    assert!(matches!(
+      registry.decode(&frame),
+      Err(RegistryError::TrailingBytes { id: 1, .. })
+    ));
+    assert!(matches!(
+      registry.decode(&frame[..frame.len() - 1]),
+      Err(RegistryError::NotEnoughBytes)
+    ));
+
+    let mut budget = DecodeBudget::new(DecodeLimits::default().with_max_message_size(8));
+    assert!(matches!(
+      registry.decode_limited(&frame, &mut budget),
+      Err(RegistryError::Limit(_))
+    ));
+  }
+
+  #[test]
+  fn test_frames_limited() {
+    let registry = registry! { 1 => Vec<u8> }.unwrap();
+    let mut buf = Vec::new();
+    for i in 0..5u8 {
+      buf.extend(encode_frame_to_vec(1, &std::vec![i; 400]).unwrap());
+    }
+
+    // every frame is within the limits, even if all of them together are not
+    let limits = DecodeLimits::default().with_max_allocation(1000);
+    let msgs = registry
+      .frames_limited(buf.as_slice(), limits)
+      .map(|msg| *msg.unwrap().downcast::<Vec<u8>>().unwrap())
+      .collect::<Vec<_>>();
+    assert_eq!(msgs.len(), 5);
+    for (i, msg) in msgs.into_iter().enumerate() {
+      assert_eq!(msg, std::vec![i as u8; 400]);
+    }
+
+    let limits = DecodeLimits::default().with_max_allocation(300);
+    let err = registry
+      .frames_limited(buf.as_slice(), limits)
+      .next()
+      .unwrap()
+      .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+  }
+
+  #[cfg(feature = "lz4")]
+  #[test]
+  fn test_default_budget() {
+    use crate::{
+      compression::Lz4, BytesTransformError, Compressed, CompressedTransformError, LimitError,
+    };
+
+    let registry = registry! { 1 => Compressed<Vec<u8>, Lz4> }.unwrap();
+    let val = Compressed::<Vec<u8>, Lz4>::new(std::vec![0; 1024 * 1024]);
+    let mut frame = encode_frame_to_vec(1, &val).unwrap();
+    // the header claims 200 MiB once decompressed
+    frame[FRAME_HEADER_LEN + 1..FRAME_HEADER_LEN + 5]
+      .copy_from_slice(&(200u32 * 1024 * 1024).to_be_bytes());
+
+    let check = |err: &(dyn std::error::Error + Send + Sync + 'static)| {
+      assert!(matches!(
+        err.downcast_ref::<CompressedTransformError<BytesTransformError>>(),
+        Some(CompressedTransformError::Limit(
+          LimitError::DecompressedTooLong { .. }
+        ))
+      ));
+    };
+    match registry.decode(&frame) {
+      Err(RegistryError::Transform { id: 1, source }) => check(source.as_ref()),
+      res => panic!("unexpected result: {res:?}"),
+    }
+    let err = registry
+      .frames(frame.as_slice())
+      .next()
+      .unwrap()
+      .unwrap_err();
+    match err
+      .into_inner()
+      .unwrap()
+      .downcast::<RegistryError>()
+      .as_deref()
+    {
+      Ok(RegistryError::Transform { id: 1, source }) => check(source.as_ref()),
+      res => panic!("unexpected result: {res:?}"),
+    }
+  }
+}
@@ -0,0 +1,945 @@
+//! Tagged-field (TLV) encoding of structs, which lets fields be added and removed without a version
+//! bump, like protobuf.
+//!
+//! A tagged struct is encoded as the total length of the message, as a 4 bytes big-endian integer,
+//! followed by its fields. Every field is encoded as its tag and the length of its value, both in
+//! LEB128 variable length format, followed by the value. Decoders skip the fields whose tag they do
+//! not know, or keep them in `UnknownFields` to forward them unchanged, and fields which are
+//! missing keep their default value.
+//!
+//! [`impl_tagged!`](crate::impl_tagged) implements [`Transformable`] for a struct with this layout,
+//! the helpers of this module can be used to write the implementation by hand.
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use ::alloc::vec::Vec;
+
+use byteorder::{ByteOrder, NetworkEndian};
+
+#[cfg(feature = "std")]
+use crate::utils;
+use crate::{
+  utils::{decode_varint, encode_varint, encoded_len_varint, DecodeVarintError, EncodeVarintError},
+  DecodeBudget, DecodeContext, ErrorKind, LimitError, TrailingBytes, TransformError, Transformable,
+};
+
+#[cfg(feature = "std")]
+use crate::GatherList;
+
+/// The length of the total length of the message, which comes first.
+pub const MESSAGE_LEN_SIZE: usize = core::mem::size_of::<u32>();
+
+/// The max length of the header of a field, a `u32` tag and a `u64` length as varints.
+#[cfg(feature = "std")]
+const MAX_FIELD_HEADER_LEN: usize = 5 + 10;
+
+/// The error type for errors that get returned when encoding or decoding the layout of a tagged
+/// struct fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TlvError {
+  /// Returned when the buffer is too small to encode.
+  EncodeBufferTooSmall,
+  /// Returned when the buffer does not contain the whole message.
+  NotEnoughBytes,
+  /// Returned when the encoded message is longer than `u32::MAX` bytes.
+  TooLarge(usize),
+  /// Returned when the tag or the length of a field is not a valid varint.
+  Varint(DecodeVarintError),
+  /// Returned when the value of a field does not consume all of its bytes.
+  TrailingBytes {
+    /// The tag of the field.
+    tag: u32,
+    /// The bytes consumed by the value and the length of the field.
+    trailing: TrailingBytes,
+  },
+  /// Returned when the memory for an unknown field can not be allocated.
+  AllocationFailed {
+    /// The number of bytes which failed to be allocated.
+    requested: usize,
+  },
+  /// Returned when a [`DecodeLimits`](crate::DecodeLimits) is exceeded.
+  Limit(LimitError),
+}
+
+impl From<TlvError> for TransformError {
+  fn from(err: TlvError) -> Self {
+    match err {
+      TlvError::EncodeBufferTooSmall => Self::from_kind(ErrorKind::BufferTooSmall),
+      TlvError::NotEnoughBytes => Self::from_kind(ErrorKind::UnexpectedEof),
+      err @ (TlvError::TooLarge(_) | TlvError::Varint(_) | TlvError::TrailingBytes { .. }) => {
+        Self::wrap(ErrorKind::InvalidValue, err)
+      }
+      err @ TlvError::AllocationFailed { .. } => Self::wrap(ErrorKind::LimitExceeded, err),
+      TlvError::Limit(e) => e.into(),
+    }
+  }
+}
+
+impl From<LimitError> for TlvError {
+  #[inline]
+  fn from(err: LimitError) -> Self {
+    Self::Limit(err)
+  }
+}
+
+impl From<DecodeVarintError> for TlvError {
+  #[inline]
+  fn from(err: DecodeVarintError) -> Self {
+    match err {
+      DecodeVarintError::NotEnoughBytes => Self::NotEnoughBytes,
+      err => Self::Varint(err),
+    }
+  }
+}
+
+impl core::fmt::Display for TlvError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::EncodeBufferTooSmall => write!(
+        f,
+        "buffer is too small, use `Transformable::encoded_len` to pre-allocate a buffer with enough space"
+      ),
+      Self::NotEnoughBytes => write!(f, "not enough bytes to decode"),
+      Self::TooLarge(len) => write!(f, "message of {len} bytes is too large to be tagged"),
+      Self::Varint(e) => write!(f, "invalid field header: {e}"),
+      Self::TrailingBytes { tag, trailing } => write!(f, "field {tag}: {trailing}"),
+      Self::AllocationFailed { requested } => write!(f, "failed to allocate {requested} bytes"),
+      Self::Limit(e) => write!(f, "{e}"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TlvError {}
+
+/// Returns the encoded length of a field with the given tag and value length.
+#[inline]
+pub const fn encoded_field_header_len(tag: u32, len: usize) -> usize {
+  encoded_len_varint(tag as u64) + encoded_len_varint(len as u64)
+}
+
+/// Returns the encoded length of the field with the given tag and value.
+#[inline]
+pub fn encoded_field_len<T: Transformable + ?Sized>(tag: u32, val: &T) -> usize {
+  let len = Transformable::encoded_len(val);
+  encoded_field_header_len(tag, len) + len
+}
+
+/// Encodes the field with the given tag and value into the buffer.
+///
+/// Returns the number of bytes written.
+pub fn encode_field<T, E>(tag: u32, val: &T, dst: &mut [u8]) -> Result<usize, E>
+where
+  T: Transformable + ?Sized,
+  E: From<TlvError> + From<T::Error>,
+{
+  let len = Transformable::encoded_len(val);
+  let header_len = encode_field_header(tag, len, dst)?;
+  let written = Transformable::encode(val, &mut dst[header_len..header_len + len])?;
+  Ok(header_len + written)
+}
+
+/// Encodes the tag and the length of a field into the buffer, checking that the value fits too.
+///
+/// Returns the number of bytes written.
+pub fn encode_field_header(tag: u32, len: usize, dst: &mut [u8]) -> Result<usize, TlvError> {
+  let header_len = encoded_field_header_len(tag, len);
+  if dst.len() < header_len + len {
+    return Err(TlvError::EncodeBufferTooSmall);
+  }
+
+  write_field_header(tag, len, dst).map_err(|_| TlvError::EncodeBufferTooSmall)
+}
+
+#[inline]
+fn write_field_header(tag: u32, len: usize, dst: &mut [u8]) -> Result<usize, EncodeVarintError> {
+  let offset = encode_varint(tag as u64, dst)?;
+  encode_varint(len as u64, &mut dst[offset..]).map(|n| offset + n)
+}
+
+/// Describes the field with the given tag and value as segments of the gather list, see
+/// [`Transformable::as_io_slices`].
+///
+/// Small values are copied into the list together with the header of the field, large values
+/// describe themselves. Returns `false` if a large value can not describe itself this way, or if
+/// a small one fails to encode.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub fn field_as_io_slices<'a, T: Transformable + ?Sized>(
+  tag: u32,
+  val: &'a T,
+  slices: &mut GatherList<'a>,
+) -> bool {
+  let len = Transformable::encoded_len(val);
+  let mut buf = [0u8; MAX_FIELD_HEADER_LEN + crate::MAX_INLINED_BYTES];
+  let Ok(header_len) = write_field_header(tag, len, &mut buf) else {
+    return false;
+  };
+
+  if len > crate::MAX_INLINED_BYTES {
+    slices.push_copied(&buf[..header_len]);
+    return Transformable::as_io_slices(val, slices);
+  }
+
+  match Transformable::encode(val, &mut buf[header_len..header_len + len]) {
+    Ok(written) => {
+      slices.push_copied(&buf[..header_len + written]);
+      true
+    }
+    Err(_) => false,
+  }
+}
+
+/// Decodes the value of the field with the given tag, which must consume all of its bytes.
+///
+/// If it does not, the error records the offset of the first trailing byte.
+pub fn decode_field<T, E>(tag: u32, src: &[u8]) -> Result<T, E>
+where
+  T: Transformable,
+  E: From<TlvError> + From<T::Error> + DecodeContext,
+{
+  let (readed, val) = T::decode(src)?;
+  check_field::<E>(tag, readed, src.len())?;
+  Ok(val)
+}
+
+/// Decodes the value of the field with the given tag within the given budget, which must consume
+/// all of its bytes.
+///
+/// See [`decode_field`].
+pub fn decode_field_limited<T, E>(tag: u32, src: &[u8], budget: &mut DecodeBudget) -> Result<T, E>
+where
+  T: Transformable,
+  E: From<TlvError> + From<T::Error> + DecodeContext,
+{
+  let (readed, val) = T::decode_limited(src, budget)?;
+  check_field::<E>(tag, readed, src.len())?;
+  Ok(val)
+}
+
+fn check_field<E>(tag: u32, readed: usize, len: usize) -> Result<(), E>
+where
+  E: From<TlvError> + DecodeContext,
+{
+  TrailingBytes::check(readed, len)
+    .map_err(|trailing| E::from(TlvError::TrailingBytes { tag, trailing }).at_offset(readed))
+}
+
+/// Writes the total length of the message in front of its fields.
+///
+/// `dst` must be at least [`MESSAGE_LEN_SIZE`] bytes long. Fails with
+/// [`TooLarge`](TlvError::TooLarge) if the length does not fit in the `u32` of the header.
+#[inline]
+pub fn encode_message_len(len: usize, dst: &mut [u8]) -> Result<(), TlvError> {
+  let len = u32::try_from(len).map_err(|_| TlvError::TooLarge(len))?;
+  NetworkEndian::write_u32(&mut dst[..MESSAGE_LEN_SIZE], len);
+  Ok(())
+}
+
+/// Decodes a tagged struct from the given reader within the given budget, reading the whole
+/// message before it is decoded.
+///
+/// The length of the message is checked against the max message size before it is read.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub fn decode_from_reader_limited<T, R>(
+  reader: &mut R,
+  budget: &mut DecodeBudget,
+) -> std::io::Result<(usize, T)>
+where
+  T: Transformable,
+  R: std::io::Read,
+{
+  let mut len = [0u8; MESSAGE_LEN_SIZE];
+  reader.read_exact(&mut len)?;
+  let rest = message_rest_len(len, budget)?;
+  let mut buf = len.to_vec();
+  utils::read_exact_to_vec(reader, &mut buf, rest)?;
+  T::decode_limited(&buf, budget).map_err(utils::invalid_data)
+}
+
+/// Decodes a tagged struct from the given async reader within the given budget, reading the
+/// whole message before it is decoded.
+///
+/// See [`decode_from_reader_limited`].
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+pub async fn decode_from_async_reader_limited<T, R>(
+  reader: &mut R,
+  budget: &mut DecodeBudget,
+) -> std::io::Result<(usize, T)>
+where
+  T: Transformable,
+  R: futures_util::io::AsyncRead + Send + Unpin,
+{
+  use futures_util::io::AsyncReadExt;
+
+  let mut len = [0u8; MESSAGE_LEN_SIZE];
+  reader.read_exact(&mut len).await?;
+  let rest = message_rest_len(len, budget)?;
+  let mut buf = len.to_vec();
+  utils::read_exact_to_vec_async(reader, &mut buf, rest).await?;
+  T::decode_limited(&buf, budget).map_err(utils::invalid_data)
+}
+
+/// Checks the total length of a message against the budget, and returns the number of bytes
+/// which follow its header.
+#[cfg(feature = "std")]
+fn message_rest_len(len: [u8; MESSAGE_LEN_SIZE], budget: &DecodeBudget) -> std::io::Result<usize> {
+  let len = NetworkEndian::read_u32(&len) as usize;
+  budget
+    .check_message_size(len)
+    .map_err(utils::invalid_data)?;
+  utils::frame_rest_len(len)
+}
+
+/// An iterator over the `(tag, value bytes)` of the fields of a tagged struct.
+#[derive(Debug, Clone)]
+pub struct Fields<'a> {
+  src: &'a [u8],
+  offset: usize,
+}
+
+impl<'a> Fields<'a> {
+  /// Reads the total length of the message at the start of `src`, and returns it together with
+  /// the iterator over the fields of the message.
+  pub fn new(src: &'a [u8]) -> Result<(usize, Self), TlvError> {
+    if src.len() < MESSAGE_LEN_SIZE {
+      return Err(TlvError::NotEnoughBytes);
+    }
+
+    let len = NetworkEndian::read_u32(src) as usize;
+    if len < MESSAGE_LEN_SIZE || src.len() < len {
+      return Err(TlvError::NotEnoughBytes);
+    }
+
+    Ok((
+      len,
+      Self {
+        src: &src[MESSAGE_LEN_SIZE..len],
+        offset: MESSAGE_LEN_SIZE,
+      },
+    ))
+  }
+
+  /// Returns the offset of the next field from the start of the message, or, once a field fails to
+  /// decode, the offset of the tag, the length or the value which failed.
+  #[inline]
+  pub const fn offset(&self) -> usize {
+    self.offset
+  }
+
+  fn next_field(&mut self) -> Result<(u32, &'a [u8]), TlvError> {
+    let (offset, tag) = decode_varint(self.src)?;
+    let tag = u32::try_from(tag).map_err(|_| TlvError::Varint(DecodeVarintError::Overflow))?;
+    self.offset += offset;
+    let (n, len) = decode_varint(&self.src[offset..])?;
+    self.offset += n;
+    let start = offset + n;
+    let end = usize::try_from(len)
+      .ok()
+      .and_then(|len| start.checked_add(len))
+      .filter(|end| *end <= self.src.len())
+      .ok_or(TlvError::NotEnoughBytes)?;
+    let val = &self.src[start..end];
+    self.offset -= start;
+    self.src = &self.src[end..];
+    self.offset += end;
+    Ok((tag, val))
+  }
+}
+
+impl<'a> Iterator for Fields<'a> {
+  type Item = Result<(u32, &'a [u8]), TlvError>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.src.is_empty() {
+      return None;
+    }
+
+    let res = self.next_field();
+    if res.is_err() {
+      self.src = &[];
+    }
+    Some(res)
+  }
+}
+
+/// The fields of a tagged struct whose tag is unknown to the decoder, kept to be encoded again
+/// unchanged, e.g. by a node which forwards messages from newer peers.
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct UnknownFields {
+  fields: Vec<(u32, Vec<u8>)>,
+}
+
+#[cfg(feature = "alloc")]
+impl UnknownFields {
+  /// Creates an empty set of unknown fields.
+  #[inline]
+  pub const fn new() -> Self {
+    Self { fields: Vec::new() }
+  }
+
+  /// Returns `true` if there is no unknown field.
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.fields.is_empty()
+  }
+
+  /// Returns the number of unknown fields.
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.fields.len()
+  }
+
+  /// Returns an iterator over the `(tag, value bytes)` of the unknown fields.
+  #[inline]
+  pub fn iter(&self) -> impl Iterator<Item = (u32, &[u8])> {
+    self.fields.iter().map(|(tag, val)| (*tag, val.as_slice()))
+  }
+
+  /// Keeps the field with the given tag and value bytes.
+  pub fn push(&mut self, tag: u32, val: &[u8]) -> Result<(), TlvError> {
+    self.push_limited(tag, val, &mut DecodeBudget::unlimited())
+  }
+
+  /// Keeps the field with the given tag and value bytes, taking its length from the allocation
+  /// budget.
+  pub fn push_limited(
+    &mut self,
+    tag: u32,
+    val: &[u8],
+    budget: &mut DecodeBudget,
+  ) -> Result<(), TlvError> {
+    budget.allocate(val.len())?;
+    let mut buf = Vec::new();
+    buf
+      .try_reserve_exact(val.len())
+      .map_err(|_| TlvError::AllocationFailed {
+        requested: val.len(),
+      })?;
+    buf.extend_from_slice(val);
+    self.fields.push((tag, buf));
+    Ok(())
+  }
+
+  /// Returns the encoded length of the unknown fields.
+  pub fn encoded_len(&self) -> usize {
+    self
+      .fields
+      .iter()
+      .map(|(tag, val)| encoded_field_header_len(*tag, val.len()) + val.len())
+      .sum()
+  }
+
+  /// Encodes the unknown fields into the buffer.
+  ///
+  /// Returns the number of bytes written.
+  pub fn encode(&self, dst: &mut [u8]) -> Result<usize, TlvError> {
+    let mut offset = 0;
+    for (tag, val) in &self.fields {
+      offset += encode_field_header(*tag, val.len(), &mut dst[offset..])?;
+      dst[offset..offset + val.len()].copy_from_slice(val);
+      offset += val.len();
+    }
+    Ok(offset)
+  }
+
+  /// Describes the unknown fields as segments of the gather list, borrowing their values.
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  pub fn as_io_slices<'a>(&'a self, slices: &mut GatherList<'a>) {
+    let mut header = [0u8; MAX_FIELD_HEADER_LEN];
+    for (tag, val) in &self.fields {
+      if let Ok(header_len) = write_field_header(*tag, val.len(), &mut header) {
+        slices.push_copied(&header[..header_len]);
+      }
+      slices.push(val);
+    }
+  }
+}
+
+/// Implements [`Transformable`] for a struct with the tagged-field layout of the [`tlv`](crate::tlv)
+/// module.
+///
+/// The struct must implement [`Default`], whose values are used for the missing fields, and the
+/// error type must implement `From<TlvError>`, `From` the errors of all the fields and
+/// [`DecodeContext`](crate::DecodeContext), as [`TransformError`] does, which records the name and
+/// the offset of the field which fails to decode. Unknown fields are skipped, unless an
+/// `UnknownFields` field is given after `unknown =`, which keeps them and encodes them again after
+/// the known fields. If a tag occurs more than once, the last value wins.
+///
+/// Within a [`DecodeBudget`](crate::DecodeBudget), every tagged struct is a level of nesting, its
+/// fields, known or not, count against the max element count, the fields are decoded within the
+/// budget and the unknown fields are taken from its allocation budget. The `*_limited` reader methods read the whole message before decoding it.
+///
+/// The struct does not implement [`ContextFree`](crate::ContextFree), so it can be given its own
+/// [`TransformableWith`](crate::TransformableWith) impl; opt in with an empty impl to use it where
+/// a context is expected, e.g. in a `Sealed` value.
+///
+/// Tags must be unique and should never be reused for a different field, once a field is removed.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// impl_tagged!(Member: TransformError {
+///   1 => id,
+///   2 => addr,
+///   3 => meta,
+/// }, unknown = unknown);
+/// ```
+#[macro_export]
+macro_rules! impl_tagged {
+  ($ty:ty: $err:ty { $($tag:literal => $field:ident),+ $(,)? } $(, unknown = $unknown:ident)? $(,)?) => {
+    impl $crate::Transformable for $ty {
+      type Error = $err;
+
+      fn encode(&self, dst: &mut [u8]) -> ::core::result::Result<usize, Self::Error> {
+        if dst.len() < <Self as $crate::Transformable>::encoded_len(self) {
+          return ::core::result::Result::Err(::core::convert::From::from(
+            $crate::tlv::TlvError::EncodeBufferTooSmall,
+          ));
+        }
+
+        let mut offset = $crate::tlv::MESSAGE_LEN_SIZE;
+        $(
+          offset += $crate::tlv::encode_field::<_, Self::Error>($tag, &self.$field, &mut dst[offset..])?;
+        )+
+        $(
+          offset += self.$unknown.encode(&mut dst[offset..])?;
+        )?
+        $crate::tlv::encode_message_len(offset, dst)?;
+        ::core::result::Result::Ok(offset)
+      }
+
+      fn encoded_len(&self) -> usize {
+        $crate::tlv::MESSAGE_LEN_SIZE
+          $(+ $crate::tlv::encoded_field_len($tag, &self.$field))+
+          $(+ self.$unknown.encoded_len())?
+      }
+
+      $crate::__tagged_as_io_slices!($($tag => $field),+ $(; $unknown)?);
+
+      $crate::__tagged_read_limited!();
+
+      fn decode(src: &[u8]) -> ::core::result::Result<(usize, Self), Self::Error>
+      where
+        Self: Sized,
+      {
+        <Self as $crate::Transformable>::decode_limited(src, &mut $crate::DecodeBudget::default())
+      }
+
+      fn decode_limited(
+        src: &[u8],
+        budget: &mut $crate::DecodeBudget,
+      ) -> ::core::result::Result<(usize, Self), Self::Error>
+      where
+        Self: Sized,
+      {
+        budget
+          .enter()
+          .map_err(|e| <Self::Error>::from($crate::tlv::TlvError::from(e)))?;
+        let res = (|| {
+          let (len, mut fields) = $crate::tlv::Fields::new(src)?;
+          budget
+            .check_message_size(len)
+            .map_err($crate::tlv::TlvError::from)?;
+          let mut this = <Self as ::core::default::Default>::default();
+          let mut count = 0usize;
+          loop {
+            let (tag, val) = match fields.next() {
+              ::core::option::Option::Some(field) => {
+                let field = field.map_err(<Self::Error>::from);
+                $crate::DecodeContext::at_offset(field, fields.offset())?
+              }
+              ::core::option::Option::None => break,
+            };
+            count += 1;
+            budget
+              .check_elements(count)
+              .map_err($crate::tlv::TlvError::from)?;
+            let offset = fields.offset() - val.len();
+            match tag {
+              $(
+                $tag => {
+                  let res = $crate::tlv::decode_field_limited::<_, Self::Error>(tag, val, budget);
+                  this.$field = $crate::DecodeContext::at_offset(
+                    $crate::DecodeContext::in_field(res, ::core::stringify!($field)),
+                    offset,
+                  )?;
+                }
+              )+
+              _ => {
+                $(
+                  this.$unknown.push_limited(tag, val, budget)?;
+                )?
+                let _ = val;
+              }
+            }
+          }
+          ::core::result::Result::Ok((len, this))
+        })();
+        budget.exit();
+        res
+      }
+    }
+  };
+}
+
+#[cfg(feature = "std")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __tagged_as_io_slices {
+  ($($tag:literal => $field:ident),+ $(; $unknown:ident)?) => {
+    fn as_io_slices<'a>(&'a self, slices: &mut $crate::GatherList<'a>) -> bool {
+      let mut header = [0u8; $crate::tlv::MESSAGE_LEN_SIZE];
+      let len = <Self as $crate::Transformable>::encoded_len(self);
+      if $crate::tlv::encode_message_len(len, &mut header).is_err() {
+        return false;
+      }
+      slices.push_copied(&header);
+      $(
+        if !$crate::tlv::field_as_io_slices($tag, &self.$field, slices) {
+          return false;
+        }
+      )+
+      $(
+        self.$unknown.as_io_slices(slices);
+      )?
+      true
+    }
+  };
+}
+
+#[cfg(not(feature = "std"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __tagged_as_io_slices {
+  ($($tt:tt)*) => {};
+}
+
+#[cfg(feature = "async")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __tagged_read_limited {
+  () => {
+    fn decode_from_reader_limited<R: ::std::io::Read>(
+      reader: &mut R,
+      budget: &mut $crate::DecodeBudget,
+    ) -> ::std::io::Result<(usize, Self)> {
+      $crate::tlv::decode_from_reader_limited(reader, budget)
+    }
+
+    async fn decode_from_async_reader_limited<
+      R: $crate::__private::futures_util::io::AsyncRead + Send + Unpin,
+    >(
+      reader: &mut R,
+      budget: &mut $crate::DecodeBudget,
+    ) -> ::std::io::Result<(usize, Self)> {
+      $crate::tlv::decode_from_async_reader_limited(reader, budget).await
+    }
+  };
+}
+
+#[cfg(all(feature = "std", not(feature = "async")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __tagged_read_limited {
+  () => {
+    fn decode_from_reader_limited<R: ::std::io::Read>(
+      reader: &mut R,
+      budget: &mut $crate::DecodeBudget,
+    ) -> ::std::io::Result<(usize, Self)> {
+      $crate::tlv::decode_from_reader_limited(reader, budget)
+    }
+  };
+}
+
+#[cfg(not(feature = "std"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __tagged_read_limited {
+  () => {};
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+  use super::*;
+  use crate::TransformError;
+  use std::{string::String, vec::Vec};
+
+  /// The v1 of the member, which forwards the fields it does not know.
+  #[derive(Debug, Default, Clone, PartialEq, Eq)]
+  struct MemberV1 {
+    id: u64,
+    name: String,
+    unknown: UnknownFields,
+  }
+
+  impl_tagged!(MemberV1: TransformError {
+    1 => id,
+    2 => name,
+  }, unknown = unknown);
+
+  /// The v2 of the member, which has a new field and dropped the name.
+  #[derive(Debug, Default, Clone, PartialEq, Eq)]
+  struct Member {
+    id: u64,
+    meta: Vec<u8>,
+  }
+
+  impl_tagged!(Member: TransformError {
+    1 => id,
+    3 => meta,
+  });
+
+  test_transformable!(Member => test_tagged_transformable(Member { id: rand::random(), meta: std::vec![1, 2, 3] }));
+
+  #[test]
+  fn test_evolution() {
+    let v1 = MemberV1 {
+      id: 7,
+      name: String::from("node-7"),
+      unknown: UnknownFields::new(),
+    };
+    let encoded = Transformable::encode_to_vec(&v1).unwrap();
+
+    // the name is skipped, the meta is missing
+    assert_eq!(
+      <Member as Transformable>::decode(&encoded).unwrap(),
+      (
+        encoded.len(),
+        Member {
+          id: 7,
+          meta: Vec::new()
+        }
+      )
+    );
+
+    // the meta is kept by the v1, and forwarded to the v2
+    let v2 = Member {
+      id: 8,
+      meta: std::vec![1, 2, 3],
+    };
+    let encoded = Transformable::encode_to_vec(&v2).unwrap();
+    let (_, forwarded) = <MemberV1 as Transformable>::decode(&encoded).unwrap();
+    assert_eq!(forwarded.id, 8);
+    assert_eq!(forwarded.name, "");
+    assert_eq!(forwarded.unknown.len(), 1);
+    let encoded = Transformable::encode_to_vec(&forwarded).unwrap();
+    assert_eq!(<Member as Transformable>::decode(&encoded).unwrap().1, v2);
+  }
+
+  #[test]
+  fn test_fields() {
+    let member = Member {
+      id: 1,
+      meta: std::vec![9; 200],
+    };
+    let encoded = Transformable::encode_to_vec(&member).unwrap();
+    let (len, fields) = Fields::new(&encoded).unwrap();
+    assert_eq!(len, encoded.len());
+    let fields = fields.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(fields.len(), 2);
+    assert_eq!((fields[0].0, fields[0].1.len()), (1, 8));
+    assert_eq!((fields[1].0, fields[1].1.len()), (3, 204));
+
+    // a field which claims more bytes than the message has
+    let mut corrupted = encoded.clone();
+    corrupted[4 + 10 + 2] = 0x7f;
+    assert_eq!(
+      <Member as Transformable>::decode(&corrupted)
+        .unwrap_err()
+        .kind(),
+      ErrorKind::UnexpectedEof
+    );
+
+    // a field whose value does not consume all of its bytes
+    let mut buf = std::vec![0u8; 64];
+    let mut offset = MESSAGE_LEN_SIZE;
+    offset += encode_field_header(1, 9, &mut buf[offset..]).unwrap();
+    offset += 9;
+    encode_message_len(offset, &mut buf).unwrap();
+    let err = <Member as Transformable>::decode(&buf[..offset]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidValue);
+    assert!(matches!(
+      err.downcast_ref::<TlvError>(),
+      Some(TlvError::TrailingBytes { tag: 1, .. })
+    ));
+  }
+
+  #[derive(Debug, Default, Clone, PartialEq, Eq)]
+  struct Group {
+    name: String,
+    leader: Member,
+  }
+
+  impl_tagged!(Group: TransformError {
+    1 => name,
+    2 => leader,
+  });
+
+  #[test]
+  fn test_error_path() {
+    let group = Group {
+      name: String::from("g"),
+      leader: Member {
+        id: 1,
+        meta: std::vec![1, 2, 3],
+      },
+    };
+    let mut encoded = Transformable::encode_to_vec(&group).unwrap();
+
+    // the leader starts at 4 + 2 + 5 + 2, and its meta at 4 + 2 + 8 + 2 in it
+    let meta = 13 + 16;
+    assert_eq!(&encoded[meta..meta + 4], &[0, 0, 0, 3]);
+    encoded[meta + 3] = 4;
+    let err = <Group as Transformable>::decode(&encoded).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    assert_eq!(err.offset(), Some(meta));
+    assert_eq!(
+      err.path(),
+      &[
+        crate::PathSegment::Field("leader"),
+        crate::PathSegment::Field("meta")
+      ]
+    );
+    assert_eq!(
+      std::string::ToString::to_string(&err),
+      "decoding leader.meta at byte 29: not enough bytes to decode"
+    );
+  }
+
+  #[test]
+  fn test_error_offset() {
+    // the id field holds one byte more than the id, which is the failing byte
+    let mut encoded = std::vec![0, 0, 0, 15, 1, 9];
+    encoded.extend_from_slice(&[0; 9]);
+    let err = <Member as Transformable>::decode(&encoded).unwrap_err();
+    assert_eq!(err.offset(), Some(4 + 2 + 8));
+    assert_eq!(err.path(), &[crate::PathSegment::Field("id")]);
+
+    // the length of the field is malformed
+    let encoded = [0, 0, 0, 6, 1, 0x80];
+    let err = <Member as Transformable>::decode(&encoded).unwrap_err();
+    assert_eq!(err.offset(), Some(5));
+  }
+
+  #[test]
+  fn test_limits() {
+    use crate::{DecodeBudget, DecodeLimits, LimitError};
+
+    let limit = |err: &TransformError| *err.downcast_ref::<LimitError>().unwrap();
+    let group = Group {
+      name: String::from("g"),
+      leader: Member {
+        id: 1,
+        meta: std::vec![1, 2, 3],
+      },
+    };
+    let encoded = Transformable::encode_to_vec(&group).unwrap();
+
+    // the fields are decoded within the budget
+    let mut budget = DecodeBudget::new(DecodeLimits::default().with_max_bytes_len(2));
+    let err = <Group as Transformable>::decode_limited(&encoded, &mut budget).unwrap_err();
+    assert_eq!(limit(&err), LimitError::BytesTooLong { len: 3, limit: 2 });
+    assert_eq!(
+      err.path(),
+      &[
+        crate::PathSegment::Field("leader"),
+        crate::PathSegment::Field("meta")
+      ]
+    );
+    assert_eq!(budget.depth(), 0);
+
+    // every tagged struct is a level of nesting
+    let mut budget = DecodeBudget::new(DecodeLimits::default().with_max_depth(1));
+    let err = <Group as Transformable>::decode_limited(&encoded, &mut budget).unwrap_err();
+    assert_eq!(limit(&err), LimitError::DepthLimitExceeded { limit: 1 });
+    assert_eq!(budget.depth(), 0);
+
+    let mut budget = DecodeBudget::new(DecodeLimits::default().with_max_message_size(8));
+    let err = <Group as Transformable>::decode_limited(&encoded, &mut budget).unwrap_err();
+    assert_eq!(limit(&err), LimitError::MessageTooLarge { limit: 8 });
+
+    // the fields count against the max element count
+    let mut budget = DecodeBudget::new(DecodeLimits::default().with_max_elements(1));
+    let err = <Group as Transformable>::decode_limited(&encoded, &mut budget).unwrap_err();
+    assert_eq!(
+      limit(&err),
+      LimitError::TooManyElements { count: 2, limit: 1 }
+    );
+    assert_eq!(budget.depth(), 0);
+
+    // the unknown fields are taken from the allocation budget
+    let member = Member {
+      id: 1,
+      meta: std::vec![0; 100],
+    };
+    let encoded = Transformable::encode_to_vec(&member).unwrap();
+    let mut budget = DecodeBudget::new(DecodeLimits::default().with_max_allocation(64));
+    let err = <MemberV1 as Transformable>::decode_limited(&encoded, &mut budget).unwrap_err();
+    assert!(matches!(
+      limit(&err),
+      LimitError::AllocationBudgetExceeded { requested: 104, .. }
+    ));
+
+    // the readers decode within the budget too
+    let mut budget = DecodeBudget::new(DecodeLimits::default().with_max_allocation(64));
+    let err =
+      <MemberV1 as Transformable>::decode_from_reader_limited(&mut encoded.as_slice(), &mut budget)
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let mut budget = DecodeBudget::default();
+    let (readed, decoded) =
+      <MemberV1 as Transformable>::decode_from_reader_limited(&mut encoded.as_slice(), &mut budget)
+        .unwrap();
+    assert_eq!((readed, decoded.id), (encoded.len(), 1));
+  }
+
+  #[test]
+  fn test_message_too_large() {
+    let mut header = [0u8; MESSAGE_LEN_SIZE];
+    encode_message_len(u32::MAX as usize, &mut header).unwrap();
+    assert_eq!(header, [0xff; MESSAGE_LEN_SIZE]);
+
+    #[cfg(target_pointer_width = "64")]
+    assert_eq!(
+      encode_message_len(u32::MAX as usize + 1, &mut header),
+      Err(TlvError::TooLarge(u32::MAX as usize + 1))
+    );
+  }
+
+  #[test]
+  fn test_as_io_slices() {
+    fn gathered<T: Transformable>(val: &T) -> Vec<u8> {
+      let mut slices = GatherList::new();
+      assert!(Transformable::as_io_slices(val, &mut slices));
+      slices.iter().flatten().copied().collect()
+    }
+
+    let member = Member {
+      id: 1,
+      meta: std::vec![7; 1000],
+    };
+    assert_eq!(
+      gathered(&member),
+      Transformable::encode_to_vec(&member).unwrap()
+    );
+
+    // the large meta is borrowed instead of being copied
+    let mut slices = GatherList::new();
+    assert!(Transformable::as_io_slices(&member, &mut slices));
+    assert!(slices.iter().any(|s| s.as_ptr() == member.meta.as_ptr()));
+
+    // the unknown meta is forwarded unchanged
+    let encoded = Transformable::encode_to_vec(&member).unwrap();
+    let (_, v1) = <MemberV1 as Transformable>::decode(&encoded).unwrap();
+    assert_eq!(gathered(&v1), Transformable::encode_to_vec(&v1).unwrap());
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    let len = crate::Encodable::encode_to_hasher(&member, &mut hasher).unwrap();
+    assert_eq!(len, encoded.len());
+  }
+}
@@ -1,9 +1,221 @@
+use crate::{ErrorKind, TransformError};
+
 #[cfg(feature = "std")]
 #[inline]
 pub(crate) fn invalid_data<E: std::error::Error + Send + Sync + 'static>(e: E) -> std::io::Error {
   std::io::Error::new(std::io::ErrorKind::InvalidData, e)
 }
 
+/// Returns `true` if the first [`TransformError`](crate::TransformError) or I/O error in the
+/// source chain of the error is of kind `UnexpectedEof`.
+#[cfg(feature = "std")]
+pub(crate) fn is_unexpected_eof(err: &(dyn std::error::Error + 'static)) -> bool {
+  let mut err = Some(err);
+  while let Some(e) = err {
+    if let Some(e) = e.downcast_ref::<crate::TransformError>() {
+      return e.kind() == crate::ErrorKind::UnexpectedEof;
+    }
+    if let Some(e) = e.downcast_ref::<std::io::Error>() {
+      return e.kind() == std::io::ErrorKind::UnexpectedEof;
+    }
+    err = e.source();
+  }
+  false
+}
+
+/// Returns the number of bytes of a frame of the given total length which follow its length prefix.
+#[cfg(feature = "std")]
+#[inline]
+pub(crate) fn frame_rest_len(len: usize) -> std::io::Result<usize> {
+  len.checked_sub(crate::MESSAGE_SIZE_LEN).ok_or_else(|| {
+    std::io::Error::new(
+      std::io::ErrorKind::InvalidData,
+      "frame length is shorter than its length prefix",
+    )
+  })
+}
+
+/// A reader which counts the bytes read through it.
+#[cfg(feature = "std")]
+pub(crate) struct CountingReader<'a, R> {
+  reader: &'a mut R,
+  count: usize,
+}
+
+#[cfg(feature = "std")]
+impl<'a, R> CountingReader<'a, R> {
+  #[inline]
+  pub(crate) fn new(reader: &'a mut R) -> Self {
+    Self { reader, count: 0 }
+  }
+
+  /// Returns the number of bytes read so far.
+  #[inline]
+  pub(crate) const fn count(&self) -> usize {
+    self.count
+  }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> std::io::Read for CountingReader<'_, R> {
+  fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    let readed = self.reader.read(buf)?;
+    self.count += readed;
+    Ok(readed)
+  }
+}
+
+#[cfg(feature = "async")]
+impl<R: futures_util::io::AsyncRead + Unpin> futures_util::io::AsyncRead for CountingReader<'_, R> {
+  fn poll_read(
+    self: core::pin::Pin<&mut Self>,
+    cx: &mut core::task::Context<'_>,
+    buf: &mut [u8],
+  ) -> core::task::Poll<std::io::Result<usize>> {
+    let this = self.get_mut();
+    let res = core::pin::Pin::new(&mut *this.reader).poll_read(cx, buf);
+    if let core::task::Poll::Ready(Ok(readed)) = res {
+      this.count += readed;
+    }
+    res
+  }
+}
+
+/// The max number of bytes reserved at once by [`read_exact_to_vec`].
+#[cfg(feature = "std")]
+const MAX_RESERVE: usize = 64 * 1024;
+
+/// Reserves the next chunk of at most [`MAX_RESERVE`] bytes of `buf`, which is going to hold `len`
+/// bytes in total, and returns the chunk to read into.
+///
+/// Fails with an [`OutOfMemory`](std::io::ErrorKind::OutOfMemory) error instead of aborting
+/// if the memory can not be allocated.
+#[cfg(feature = "std")]
+fn next_chunk(buf: &mut std::vec::Vec<u8>, end: usize, len: usize) -> std::io::Result<&mut [u8]> {
+  let start = buf.len();
+  let chunk = (end - start).min(MAX_RESERVE);
+  buf.try_reserve(chunk).map_err(|_| {
+    std::io::Error::new(
+      std::io::ErrorKind::OutOfMemory,
+      crate::BytesTransformError::AllocationFailed { requested: len },
+    )
+  })?;
+  buf.resize(start + chunk, 0);
+  Ok(&mut buf[start..])
+}
+
+/// Reads exactly `len` bytes from the reader, appending them to `buf`.
+///
+/// `buf` grows as the bytes arrive instead of being allocated upfront, so a corrupted or hostile
+/// length prefix can not make us allocate more memory than the reader actually provides.
+#[cfg(feature = "std")]
+pub(crate) fn read_exact_to_vec<R: std::io::Read>(
+  reader: &mut R,
+  buf: &mut std::vec::Vec<u8>,
+  len: usize,
+) -> std::io::Result<()> {
+  let end = buf.len() + len;
+  while buf.len() < end {
+    let start = buf.len();
+    let res = reader.read_exact(next_chunk(buf, end, len)?);
+    if let Err(e) = res {
+      buf.truncate(start);
+      return Err(e);
+    }
+  }
+  Ok(())
+}
+
+/// The async version of [`read_exact_to_vec`].
+#[cfg(feature = "async")]
+pub(crate) async fn read_exact_to_vec_async<R: futures_util::io::AsyncRead + Unpin>(
+  reader: &mut R,
+  buf: &mut std::vec::Vec<u8>,
+  len: usize,
+) -> std::io::Result<()> {
+  use futures_util::io::AsyncReadExt;
+
+  let end = buf.len() + len;
+  while buf.len() < end {
+    let start = buf.len();
+    let res = reader.read_exact(next_chunk(buf, end, len)?).await;
+    if let Err(e) = res {
+      buf.truncate(start);
+      return Err(e);
+    }
+  }
+  Ok(())
+}
+
+/// Encodes the value and feeds the encoded bytes into `sink`, which is used to hash values
+/// without encoding them into a vec first.
+///
+/// Small values are encoded into an inline buffer, and large values which describe themselves as
+/// [`GatherList`](crate::GatherList) segments, e.g. bytes, strings and tagged structs, are fed
+/// segment by segment. Other large values are streamed through their
+/// [`encode_to_writer`](crate::Encodable::encode_to_writer), so wrappers which encode into a
+/// writer, e.g. checksummed or compressed values, are not buffered again. Without the `std`
+/// feature, large values are encoded into a heap allocated buffer.
+///
+/// If the value fails to encode, `sink` may already have received a part of the encoded bytes.
+#[cfg(feature = "alloc")]
+pub(crate) fn encode_to_sink<T: crate::Encodable + ?Sized>(
+  val: &T,
+  mut sink: impl FnMut(&[u8]),
+) -> Result<usize, T::Error> {
+  let encoded_len = val.encoded_len();
+  if encoded_len <= crate::MAX_INLINED_BYTES {
+    let mut buf = [0u8; crate::MAX_INLINED_BYTES];
+    let len = val.encode(&mut buf[..encoded_len])?;
+    sink(&buf[..len]);
+    return Ok(len);
+  }
+
+  #[cfg(feature = "std")]
+  {
+    let mut slices = crate::GatherList::new();
+    if val.as_io_slices(&mut slices) {
+      return Ok(slices.iter().fold(0, |len, segment| {
+        sink(segment);
+        len + segment.len()
+      }));
+    }
+
+    // The sink never fails, so an error means the value failed to encode. Wrappers report the
+    // error of the wrapped value as is, which is not `T::Error`, so the value is encoded again
+    // below to get its error.
+    match val.encode_to_writer(&mut SinkWriter(&mut sink)) {
+      Ok(len) => return Ok(len),
+      Err(err) => {
+        if let Some(Ok(err)) = err.into_inner().map(|e| e.downcast::<T::Error>()) {
+          return Err(*err);
+        }
+      }
+    }
+  }
+
+  let mut buf = ::alloc::vec![0u8; encoded_len];
+  let len = val.encode(&mut buf)?;
+  sink(&buf[..len]);
+  Ok(len)
+}
+
+/// A [`Write`](std::io::Write) which feeds everything written into it into a sink.
+#[cfg(feature = "std")]
+struct SinkWriter<F>(F);
+
+#[cfg(feature = "std")]
+impl<F: FnMut(&[u8])> std::io::Write for SinkWriter<F> {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    (self.0)(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    Ok(())
+  }
+}
+
 /// Returns the encoded length of the value in LEB128 variable length format.
 /// The returned value will be between 1 and 10, inclusive.
 #[inline]
@@ -20,6 +232,14 @@ pub enum EncodeVarintError {
   BufferTooSmall,
 }
 
+impl From<EncodeVarintError> for TransformError {
+  fn from(err: EncodeVarintError) -> Self {
+    match err {
+      EncodeVarintError::BufferTooSmall => Self::from_kind(ErrorKind::BufferTooSmall),
+    }
+  }
+}
+
 impl core::fmt::Display for EncodeVarintError {
   fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     match self {
@@ -61,6 +281,15 @@ pub enum DecodeVarintError {
   NotEnoughBytes,
 }
 
+impl From<DecodeVarintError> for TransformError {
+  fn from(err: DecodeVarintError) -> Self {
+    match err {
+      DecodeVarintError::NotEnoughBytes => Self::from_kind(ErrorKind::UnexpectedEof),
+      err @ DecodeVarintError::Overflow => Self::wrap(ErrorKind::InvalidValue, err),
+    }
+  }
+}
+
 impl core::fmt::Display for DecodeVarintError {
   fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     match self {
@@ -87,7 +316,7 @@ impl std::error::Error for DecodeVarintError {}
 /// * Returns the bytes readed and the decoded value as `u64` if successful.
 ///
 /// * Returns [`DecodeVarintError`] if the buffer did not contain a valid LEB128 encoding
-/// or the decode buffer did not contain enough bytes to decode a value.
+///   or the decode buffer did not contain enough bytes to decode a value.
 pub const fn decode_varint(buf: &[u8]) -> Result<(usize, u64), DecodeVarintError> {
   let (mut x, mut s) = (0, 0);
   let mut i = 0usize;
@@ -214,4 +443,37 @@ mod tests {
       _ => panic!("Expected Overflow error"),
     }
   }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_read_exact_to_vec_grows_with_input() {
+    // a hostile length must not be allocated upfront
+    let mut buf = std::vec![1, 2];
+    let err = read_exact_to_vec(&mut &[3u8; 100][..], &mut buf, usize::MAX / 2).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    assert_eq!(buf, [1, 2]);
+
+    let src = (0..=255u8)
+      .cycle()
+      .take(MAX_RESERVE * 2 + 7)
+      .collect::<std::vec::Vec<_>>();
+    read_exact_to_vec(&mut &src[..], &mut buf, src.len()).unwrap();
+    assert_eq!(&buf[..2], [1, 2]);
+    assert_eq!(&buf[2..], src);
+  }
+
+  #[cfg(all(feature = "embedded-io", feature = "alloc"))]
+  #[test]
+  fn test_try_zeroed() {
+    assert_eq!(
+      crate::embedded::try_zeroed::<(), ()>(16).unwrap(),
+      [0u8; 16]
+    );
+    assert!(matches!(
+      crate::embedded::try_zeroed::<(), ()>(usize::MAX),
+      Err(crate::EmbeddedIoError::AllocationFailed {
+        requested: usize::MAX
+      })
+    ));
+  }
 }
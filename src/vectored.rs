@@ -0,0 +1,271 @@
+use std::io::{self, IoSlice};
+
+/// The max size of a segment which is copied into the [`GatherList`] instead of being borrowed.
+const INLINED_SEGMENT_SIZE: usize = 16;
+
+#[derive(Debug, Clone)]
+enum Segment<'a> {
+  Borrowed(&'a [u8]),
+  Inlined([u8; INLINED_SEGMENT_SIZE], usize),
+  Owned(Vec<u8>),
+}
+
+impl Segment<'_> {
+  #[inline]
+  fn as_slice(&self) -> &[u8] {
+    match self {
+      Self::Borrowed(buf) => buf,
+      Self::Inlined(buf, len) => &buf[..*len],
+      Self::Owned(buf) => buf,
+    }
+  }
+}
+
+/// A list of byte segments which together form the encoded representation of a value.
+///
+/// The segments are written out with vectored writes, so large payloads can be sent
+/// without being copied into an intermediate buffer first.
+///
+/// See [`Transformable::as_io_slices`](crate::Transformable::as_io_slices).
+#[derive(Debug, Default, Clone)]
+pub struct GatherList<'a> {
+  segments: Vec<Segment<'a>>,
+  len: usize,
+}
+
+impl<'a> GatherList<'a> {
+  /// Creates an empty gather list.
+  #[inline]
+  pub const fn new() -> Self {
+    Self {
+      segments: Vec::new(),
+      len: 0,
+    }
+  }
+
+  /// Creates an empty gather list with space for at least `capacity` segments.
+  #[inline]
+  pub fn with_capacity(capacity: usize) -> Self {
+    Self {
+      segments: Vec::with_capacity(capacity),
+      len: 0,
+    }
+  }
+
+  /// Appends a borrowed segment, e.g. the payload of a value.
+  #[inline]
+  pub fn push(&mut self, buf: &'a [u8]) {
+    self.len += buf.len();
+    self.segments.push(Segment::Borrowed(buf));
+  }
+
+  /// Appends a copy of the given bytes, e.g. a length prefix or a tag.
+  ///
+  /// Small segments are stored inline, without allocation.
+  #[inline]
+  pub fn push_copied(&mut self, buf: &[u8]) {
+    self.len += buf.len();
+    if buf.len() <= INLINED_SEGMENT_SIZE {
+      let mut inlined = [0; INLINED_SEGMENT_SIZE];
+      inlined[..buf.len()].copy_from_slice(buf);
+      self.segments.push(Segment::Inlined(inlined, buf.len()));
+    } else {
+      self.segments.push(Segment::Owned(buf.to_vec()));
+    }
+  }
+
+  /// Returns the total number of bytes in the gather list.
+  #[inline]
+  pub const fn len(&self) -> usize {
+    self.len
+  }
+
+  /// Returns `true` if the gather list contains no bytes.
+  #[inline]
+  pub const fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Returns an iterator over the segments of the gather list.
+  #[inline]
+  pub fn iter(&self) -> impl Iterator<Item = &[u8]> {
+    self.segments.iter().map(Segment::as_slice)
+  }
+
+  /// Clears the gather list, keeping the allocated capacity.
+  #[inline]
+  pub fn clear(&mut self) {
+    self.segments.clear();
+    self.len = 0;
+  }
+
+  /// Writes all segments into the given writer with vectored writes.
+  ///
+  /// Returns the number of bytes written.
+  pub fn write_to<W: io::Write>(&self, writer: &mut W) -> io::Result<usize> {
+    let bufs = self.iter().collect::<Vec<_>>();
+    write_all_vectored(writer, &bufs).map(|_| self.len)
+  }
+
+  /// Writes all segments into the given async writer with vectored writes.
+  ///
+  /// Returns the number of bytes written.
+  #[cfg(feature = "async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+  pub async fn write_to_async<W: futures_util::io::AsyncWrite + Unpin>(
+    &self,
+    writer: &mut W,
+  ) -> io::Result<usize> {
+    let bufs = self.iter().collect::<Vec<_>>();
+    write_all_vectored_async(writer, &bufs)
+      .await
+      .map(|_| self.len)
+  }
+}
+
+/// Skips `n` written bytes, dropping the buffers which have been fully written.
+fn advance(bufs: &mut &[&[u8]], offset: &mut usize, n: usize) {
+  let mut n = *offset + n;
+  while let Some(first) = bufs.first() {
+    if n < first.len() {
+      break;
+    }
+    n -= first.len();
+    *bufs = &bufs[1..];
+  }
+  *offset = n;
+}
+
+fn to_io_slices<'a>(bufs: &[&'a [u8]], offset: usize, slices: &mut Vec<IoSlice<'a>>) {
+  slices.clear();
+  let first: &'a [u8] = bufs[0];
+  slices.push(IoSlice::new(&first[offset..]));
+  slices.extend(bufs[1..].iter().map(|&buf| IoSlice::new(buf)));
+}
+
+/// Writes all buffers into the given writer with vectored writes.
+pub(crate) fn write_all_vectored<W: io::Write>(
+  writer: &mut W,
+  mut bufs: &[&[u8]],
+) -> io::Result<()> {
+  let mut offset = 0;
+  let mut slices = Vec::with_capacity(bufs.len());
+  loop {
+    advance(&mut bufs, &mut offset, 0);
+    if bufs.is_empty() {
+      return Ok(());
+    }
+
+    to_io_slices(bufs, offset, &mut slices);
+    match writer.write_vectored(&slices) {
+      Ok(0) => {
+        return Err(io::Error::new(
+          io::ErrorKind::WriteZero,
+          "failed to write whole buffer",
+        ))
+      }
+      Ok(n) => advance(&mut bufs, &mut offset, n),
+      Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+      Err(e) => return Err(e),
+    }
+  }
+}
+
+/// Async version of [`write_all_vectored`].
+#[cfg(feature = "async")]
+pub(crate) async fn write_all_vectored_async<W: futures_util::io::AsyncWrite + Unpin>(
+  writer: &mut W,
+  mut bufs: &[&[u8]],
+) -> io::Result<()> {
+  use core::pin::Pin;
+
+  let mut offset = 0;
+  let mut slices = Vec::with_capacity(bufs.len());
+  loop {
+    advance(&mut bufs, &mut offset, 0);
+    if bufs.is_empty() {
+      return Ok(());
+    }
+
+    to_io_slices(bufs, offset, &mut slices);
+    let res =
+      futures_util::future::poll_fn(|cx| Pin::new(&mut *writer).poll_write_vectored(cx, &slices))
+        .await;
+    match res {
+      Ok(0) => {
+        return Err(io::Error::new(
+          io::ErrorKind::WriteZero,
+          "failed to write whole buffer",
+        ))
+      }
+      Ok(n) => advance(&mut bufs, &mut offset, n),
+      Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+      Err(e) => return Err(e),
+    }
+  }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use super::*;
+
+  /// A writer which only writes a few bytes of the first buffer per call.
+  pub(crate) struct Trickle(pub(crate) Vec<u8>);
+
+  impl io::Write for Trickle {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+      let n = buf.len().min(3);
+      self.0.extend_from_slice(&buf[..n]);
+      Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn test_write_all_vectored() {
+    let mut list = GatherList::new();
+    list.push_copied(&[1, 2, 3, 4]);
+    list.push(&[]);
+    list.push(b"hello world");
+    list.push_copied(&[0; 20]);
+    assert_eq!(list.len(), 35);
+
+    let mut expected = std::vec![1, 2, 3, 4];
+    expected.extend_from_slice(b"hello world");
+    expected.extend_from_slice(&[0; 20]);
+
+    let mut writer = Trickle(Vec::new());
+    assert_eq!(list.write_to(&mut writer).unwrap(), 35);
+    assert_eq!(writer.0, expected);
+
+    let mut writer = Vec::new();
+    assert_eq!(list.write_to(&mut writer).unwrap(), 35);
+    assert_eq!(writer, expected);
+  }
+
+  #[test]
+  fn test_large_bytes_to_writer() {
+    use crate::Transformable;
+
+    let val = (0..1024u32).map(|i| i as u8).collect::<Vec<u8>>();
+    let mut writer = Trickle(Vec::new());
+    let len = val.encode_to_writer(&mut writer).unwrap();
+    assert_eq!(len, val.encoded_len());
+    assert_eq!(writer.0, val.encode_to_vec().unwrap());
+  }
+
+  #[cfg(feature = "async")]
+  #[tokio::test]
+  async fn test_write_all_vectored_async() {
+    let mut list = GatherList::with_capacity(2);
+    list.push_copied(&[0, 0, 0, 5]);
+    list.push(b"hello");
+
+    let mut writer = futures_util::io::Cursor::new(Vec::new());
+    assert_eq!(list.write_to_async(&mut writer).await.unwrap(), 9);
+    assert_eq!(writer.into_inner(), b"\0\0\0\x05hello");
+  }
+}